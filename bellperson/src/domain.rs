@@ -99,7 +99,12 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
         best_fft(kern, &mut self.coeffs, worker, &self.omegainv, self.exp)?;
+        self.mul_by_minv(worker);
 
+        Ok(())
+    }
+
+    fn mul_by_minv(&mut self, worker: &Worker) {
         worker.scope(self.coeffs.len(), |scope, chunk| {
             let minv = self.minv;
 
@@ -111,10 +116,59 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
                 });
             }
         });
+    }
+
+    // FFTs of `domains`, with `omegas[i]` for `domains[i]`
+    fn fft_many_with(
+        domains: &mut [&mut Self],
+        omegas: &[E::Fr],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        let log_ns = domains.iter().map(|d| d.exp).collect::<Vec<_>>();
+        let mut coeffs = domains
+            .iter_mut()
+            .map(|d| &mut d.coeffs[..])
+            .collect::<Vec<_>>();
+        best_fft_many(kern, &mut coeffs, worker, omegas, &log_ns)
+    }
 
+    /// `fft` of several domains, which run on different devices if the kernel has several
+    pub fn fft_many(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        let omegas = domains.iter().map(|d| d.omega).collect::<Vec<_>>();
+        Self::fft_many_with(domains, &omegas, worker, kern)
+    }
+
+    /// `ifft` of several domains, which run on different devices if the kernel has several
+    pub fn ifft_many(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        let omegas = domains.iter().map(|d| d.omegainv).collect::<Vec<_>>();
+        Self::fft_many_with(domains, &omegas, worker, kern)?;
+        for domain in domains.iter_mut() {
+            domain.mul_by_minv(worker);
+        }
         Ok(())
     }
 
+    /// `coset_fft` of several domains, which run on different devices if the kernel has several
+    pub fn coset_fft_many(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        for domain in domains.iter_mut() {
+            domain.distribute_powers(worker, E::Fr::multiplicative_generator());
+        }
+        Self::fft_many(domains, worker, kern)
+    }
+
     pub fn distribute_powers(&mut self, worker: &Worker, g: E::Fr) {
         worker.scope(self.coeffs.len(), |scope, chunk| {
             for (i, v) in self.coeffs.chunks_mut(chunk).enumerate() {
@@ -223,6 +277,11 @@ pub trait Group<E: ScalarEngine>: Sized + Copy + Clone + Send + Sync {
     fn group_mul_assign(&mut self, by: &E::Fr);
     fn group_add_assign(&mut self, other: &Self);
     fn group_sub_assign(&mut self, other: &Self);
+
+    /// The elements as scalars if the group is the one of `E::Fr`, which the FFT kernel runs on
+    fn as_scalars(_: &mut [Self]) -> Option<&mut [E::Fr]> {
+        None
+    }
 }
 
 pub struct Point<G: CurveProjective>(pub G);
@@ -256,6 +315,7 @@ impl<G: CurveProjective> Group<G::Engine> for Point<G> {
     }
 }
 
+#[repr(transparent)]
 pub struct Scalar<E: ScalarEngine>(pub E::Fr);
 
 impl<E: ScalarEngine> PartialEq for Scalar<E> {
@@ -285,6 +345,12 @@ impl<E: ScalarEngine> Group<E> for Scalar<E> {
     fn group_sub_assign(&mut self, other: &Self) {
         self.0.sub_assign(&other.0);
     }
+    fn as_scalars(elements: &mut [Self]) -> Option<&mut [E::Fr]> {
+        // `Scalar<E>` has the layout of `E::Fr`
+        Some(unsafe {
+            std::slice::from_raw_parts_mut(elements.as_mut_ptr() as *mut E::Fr, elements.len())
+        })
+    }
 }
 
 fn best_fft<E: Engine, T: Group<E>>(
@@ -313,24 +379,70 @@ fn best_fft<E: Engine, T: Group<E>>(
     Ok(())
 }
 
+fn best_fft_many<E: Engine, T: Group<E>>(
+    kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    inputs: &mut [&mut [T]],
+    worker: &Worker,
+    omegas: &[E::Fr],
+    log_ns: &[u32],
+) -> gpu::GPUResult<()> {
+    // FFTs the GPU completed before failing aren't performed again on the CPU
+    let mut done = vec![false; inputs.len()];
+    if let Some(ref mut kern) = kern {
+        if kern
+            .with(|k: &mut gpu::FFTKernel<E>| gpu_fft_many(k, inputs, omegas, log_ns, &mut done))
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+
+    let log_cpus = worker.log_num_cpus();
+    for (((a, omega), &log_n), _) in inputs
+        .iter_mut()
+        .zip(omegas)
+        .zip(log_ns)
+        .zip(done)
+        .filter(|(_, done)| !done)
+    {
+        if log_n <= log_cpus {
+            serial_fft(a, omega, log_n);
+        } else {
+            parallel_fft(a, worker, omega, log_n, log_cpus);
+        }
+    }
+
+    Ok(())
+}
+
 pub fn gpu_fft<E: Engine, T: Group<E>>(
     kern: &mut gpu::FFTKernel<E>,
     a: &mut [T],
     omega: &E::Fr,
     log_n: u32,
 ) -> gpu::GPUResult<()> {
-    // EvaluationDomain module is supposed to work only with E::Fr elements, and not CurveProjective
-    // points. The Bellman authors have implemented an unnecessarry abstraction called Group<E>
-    // which is implemented for both PrimeField and CurveProjective elements. As nowhere in the code
-    // is the CurveProjective version used, T and E::Fr are guaranteed to be equal and thus have same
-    // size.
-    // For compatibility/performance reasons we decided to transmute the array to the desired type
-    // as it seems safe and needs less modifications in the current structure of Bellman library.
-    let a = unsafe { std::mem::transmute::<&mut [T], &mut [E::Fr]>(a) };
+    let a = T::as_scalars(a).ok_or(gpu::GPUError::Simple("Only scalars are supported!"))?;
     kern.radix_fft(a, omega, log_n)?;
     Ok(())
 }
 
+/// `gpu_fft` of several inputs, see `FFTKernel::radix_fft_many`
+pub fn gpu_fft_many<E: Engine, T: Group<E>>(
+    kern: &mut gpu::FFTKernel<E>,
+    inputs: &mut [&mut [T]],
+    omegas: &[E::Fr],
+    log_ns: &[u32],
+    done: &mut [bool],
+) -> gpu::GPUResult<()> {
+    let mut inputs = inputs
+        .iter_mut()
+        .map(|a| T::as_scalars(a))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(gpu::GPUError::Simple("Only scalars are supported!"))?;
+    kern.radix_fft_many(&mut inputs, omegas, log_ns, done)?;
+    Ok(())
+}
+
 pub fn serial_fft<E: ScalarEngine, T: Group<E>>(a: &mut [T], omega: &E::Fr, log_n: u32) {
     fn bitreverse(mut n: u32, l: u32) -> u32 {
         let mut r = 0;
@@ -559,11 +671,37 @@ fn parallel_fft_consistency() {
     test_consistency::<Bls12, _>(rng);
 }
 
-pub fn create_fft_kernel<E>(_log_d: usize, priority: bool) -> Option<gpu::FFTKernel<E>>
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn group_as_scalars() {
+    use crate::bls::{Bls12, Fr, G1Projective};
+
+    let rng = &mut rand::thread_rng();
+    let mut fr = (0..10).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+    let mut scalars = fr.iter().map(|&s| Scalar::<Bls12>(s)).collect::<Vec<_>>();
+    assert_eq!(Scalar::as_scalars(&mut scalars), Some(&mut fr[..]));
+
+    // Only the FFTs of scalars can run on the GPU
+    let mut points = vec![Point(G1Projective::one()); 10];
+    assert!(Point::as_scalars(&mut points).is_none());
+}
+
+pub fn create_fft_kernel<E>(
+    _log_d: usize,
+    priority: bool,
+    selector: Option<&gpu::DeviceSelector>,
+) -> Option<gpu::FFTKernel<E>>
 where
     E: Engine,
+    E::Fr: gpu::GpuField,
+    E::G1Affine: gpu::GpuCurve,
+    E::G2Affine: gpu::GpuCurve,
 {
-    match gpu::FFTKernel::create(priority) {
+    let kernel = match selector {
+        Some(selector) => gpu::FFTKernel::create_with_selector(priority, selector),
+        None => gpu::FFTKernel::create(priority),
+    };
+    match kernel {
         Ok(k) => {
             info!("GPU FFT kernel instantiated!");
             Some(k)
@@ -629,4 +767,35 @@ mod tests {
             println!("============================");
         }
     }
+
+    #[test]
+    pub fn cpu_backend_fft_consistency() {
+        let rng = &mut rand::thread_rng();
+
+        let backend = gpu::CpuBackend::new("CPU");
+        let kernel = gpu::SingleFFTKernel::<Bls12, _>::with_backend(backend, false)
+            .expect("Cannot initialize kernel!");
+        let mut kern = gpu::FFTKernel::with_kernels(vec![kernel]).unwrap();
+
+        // Up to two rounds of radix-256
+        for &log_d in &[1, 3, 8, 9, 12] {
+            let d = 1 << log_d;
+
+            let elems = (0..d)
+                .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                .collect::<Vec<_>>();
+            let mut v1 = EvaluationDomain::from_coeffs(elems.clone()).unwrap();
+            let mut v2 = EvaluationDomain::from_coeffs(elems).unwrap();
+
+            let mut coeffs = v1.coeffs.iter().map(|s| s.0).collect::<Vec<_>>();
+            kern.radix_fft(&mut coeffs, &v1.omega, log_d)
+                .expect("CPU backend FFT failed!");
+            for (c, s) in v1.coeffs.iter_mut().zip(coeffs) {
+                c.0 = s;
+            }
+            serial_fft(&mut v2.coeffs, &v2.omega, log_d);
+
+            assert!(v1.coeffs == v2.coeffs);
+        }
+    }
 }
@@ -0,0 +1,68 @@
+use super::error::GPUResult;
+use super::GpuCurve;
+use crate::bls::Engine;
+use ff::{PrimeField, ScalarEngine};
+use groupy::CurveAffine;
+
+/// Exponents of a multiexp over `G`, in regular form
+pub type Exponent<G> = <<<G as CurveAffine>::Engine as ScalarEngine>::Fr as PrimeField>::Repr;
+
+/// Buffer of `T`s on the device of backend `B`
+pub type Buffer<B, T> = <B as BufferBackend<T>>::Buffer;
+
+/// Allocation, upload and readback of buffers of `T` on the device of a backend
+pub trait BufferBackend<T> {
+    type Buffer;
+
+    fn create_buffer(&mut self, length: usize) -> GPUResult<Self::Buffer>;
+
+    /// Writes `data` into `buffer`, starting at element `offset`
+    fn write_from(&mut self, buffer: &mut Self::Buffer, offset: usize, data: &[T])
+        -> GPUResult<()>;
+
+    /// Reads `data.len()` elements of `buffer` into `data`, starting at element `offset`
+    fn read_into(&mut self, buffer: &Self::Buffer, offset: usize, data: &mut [T]) -> GPUResult<()>;
+}
+
+/// Device the FFT and multiexp kernels of engine `E` run on. The kernels are the ones of
+/// `gpu/fft/fft.cl` and `gpu/multiexp/multiexp.cl`, launched with `global_work_size` work-items in
+/// total.
+pub trait ComputeBackend<E: Engine>: BufferBackend<E::Fr> + Send {
+    /// Name of the device, for logging
+    fn device_name(&self) -> String;
+
+    /// Runs a round of `FIELD_radix_fft`, where `FIELD` is the name of `E::Fr`, in work-groups of
+    /// `local_work_size` work-items
+    #[allow(clippy::too_many_arguments)]
+    fn radix_fft(
+        &mut self,
+        global_work_size: usize,
+        local_work_size: usize,
+        src: &Buffer<Self, E::Fr>,
+        dst: &mut Buffer<Self, E::Fr>,
+        pq: &Buffer<Self, E::Fr>,
+        omegas: &Buffer<Self, E::Fr>,
+        n: u32,
+        log_p: u32,
+        deg: u32,
+        max_deg: u32,
+    ) -> GPUResult<()>;
+
+    /// Runs `POINT_bellman_multiexp`, where `POINT` is the name of `G`
+    #[allow(clippy::too_many_arguments)]
+    fn multiexp<G>(
+        &mut self,
+        global_work_size: usize,
+        bases: &Buffer<Self, G>,
+        buckets: &mut Buffer<Self, G::Projective>,
+        results: &mut Buffer<Self, G::Projective>,
+        exps: &Buffer<Self, Exponent<G>>,
+        n: u32,
+        num_groups: u32,
+        num_windows: u32,
+        window_size: u32,
+    ) -> GPUResult<()>
+    where
+        G: GpuCurve,
+        Self: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>;
+}
@@ -0,0 +1,409 @@
+use super::error::{GPUError, GPUResult};
+use super::opencl_backend::OpenCLProgram;
+use ff_cl_gen as ffgen;
+use log::{info, warn};
+use rust_gpu_tools::*;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const CACHE_DIR_ENV: &str = "BELLMAN_PROGRAM_CACHE";
+const DEFAULT_CACHE_DIR: &str = "bellman.program.cache";
+
+// Entries are the magic, the SHA-256 of the binary and the binary itself
+const MAGIC: &[u8; 8] = b"BLMNPRG1";
+const HEADER_LEN: usize = 8 + 32;
+
+/// Builds the programs of a device, from source or from a binary built earlier on the same device
+/// and driver. `ProgramCache` only goes through this trait.
+pub trait ProgramBuilder {
+    type Program;
+
+    fn device_name(&self) -> String;
+    fn driver_version(&self) -> String;
+    fn compile(&self, src: &str) -> GPUResult<Self::Program>;
+    /// Fails if the driver rejects `binary`
+    fn load_binary(&self, binary: &[u8]) -> GPUResult<Self::Program>;
+    fn program_binary(&self, program: &Self::Program) -> GPUResult<Vec<u8>>;
+}
+
+impl ProgramBuilder for opencl::Device {
+    type Program = OpenCLProgram;
+
+    fn device_name(&self) -> String {
+        self.name()
+    }
+    // Binaries the driver rejects are rebuilt anyway, so an unknown version only costs a rebuild
+    fn driver_version(&self) -> String {
+        const CL_DRIVER_VERSION: u32 = 0x102D;
+        match self.device.info_raw(CL_DRIVER_VERSION) {
+            Ok(version) => String::from_utf8_lossy(&version)
+                .trim_end_matches('\0')
+                .to_string(),
+            Err(_) => String::new(),
+        }
+    }
+    fn compile(&self, src: &str) -> GPUResult<OpenCLProgram> {
+        OpenCLProgram::from_source(self, src)
+    }
+    fn load_binary(&self, binary: &[u8]) -> GPUResult<OpenCLProgram> {
+        OpenCLProgram::from_binary(self, binary)
+    }
+    fn program_binary(&self, program: &OpenCLProgram) -> GPUResult<Vec<u8>> {
+        program.binary()
+    }
+}
+
+/// On-disk cache of program binaries, keyed by the digest of the source, the device name and the
+/// driver version. Entries are written atomically, and entries that are corrupted or rejected by
+/// the driver are rebuilt from source.
+///
+/// Whoever can write the entries controls the code the provers run, so the cache is only used if
+/// the directory and its entries belong to the current user and can't be written by anyone else.
+#[derive(Clone, Debug)]
+pub struct ProgramCache {
+    dir: PathBuf,
+}
+
+impl ProgramCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> ProgramCache {
+        ProgramCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Cache in the directory `BELLMAN_PROGRAM_CACHE`, or in the cache directory of the user
+    /// (e.g. `~/.cache`) if it's not set
+    pub fn from_env() -> ProgramCache {
+        match env::var(CACHE_DIR_ENV) {
+            Ok(dir) => Self::new(dir),
+            Err(_) => Self::new(
+                dirs::cache_dir()
+                    .unwrap_or_else(env::temp_dir)
+                    .join(DEFAULT_CACHE_DIR),
+            ),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path<B: ProgramBuilder>(&self, builder: &B, src: &str) -> PathBuf {
+        let key = format!(
+            "{}\n{}\n{}",
+            ffgen::digest(src),
+            builder.device_name(),
+            builder.driver_version()
+        );
+        self.dir.join(format!("{}.bin", ffgen::digest(&key)))
+    }
+
+    // Creates the directory, private to the current user, if it doesn't exist
+    fn create_dir(&self) -> GPUResult<()> {
+        create_private_dir(&self.dir)?;
+        check_private(&fs::metadata(&self.dir)?)
+    }
+
+    // `None` if there's no valid entry at `path`
+    fn load(&self, path: &Path) -> Option<Vec<u8>> {
+        // Symbolic links aren't followed
+        let private = check_private(&fs::symlink_metadata(path).ok()?);
+        if let Err(e) = private {
+            warn!("Ignoring program binary {:?}! Error: {}", path, e);
+            return None;
+        }
+        let entry = fs::read(path).ok()?;
+        if entry.len() < HEADER_LEN
+            || &entry[..8] != MAGIC
+            || entry[8..HEADER_LEN] != Sha256::digest(&entry[HEADER_LEN..])[..]
+        {
+            warn!("Corrupted program binary {:?}, rebuilding it...", path);
+            return None;
+        }
+        Some(entry[HEADER_LEN..].to_vec())
+    }
+
+    // Written to a temporary file first, so that concurrent provers never read partial entries.
+    // The name of the temporary file is unique to the call, and it's removed if anything fails.
+    fn store(&self, path: &Path, binary: &[u8]) -> GPUResult<()> {
+        let tmp = path.with_extension(format!(
+            "tmp.{}.{:016x}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut file = create_private_file(&tmp)?;
+        let res = (|| {
+            file.write_all(MAGIC)?;
+            file.write_all(&Sha256::digest(binary))?;
+            file.write_all(binary)?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)
+        })();
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        Ok(res?)
+    }
+
+    /// Builds `src` from the cached binary if there is a valid one, otherwise builds it from
+    /// source and caches its binary. Failing to write the cache isn't an error.
+    pub fn build<B: ProgramBuilder>(&self, builder: &B, src: &str) -> GPUResult<B::Program> {
+        if let Err(e) = self.create_dir() {
+            warn!("Program cache {:?} is not used! Error: {}", self.dir, e);
+            return builder.compile(src);
+        }
+
+        let path = self.path(builder, src);
+        if let Some(binary) = self.load(&path) {
+            match builder.load_binary(&binary) {
+                Ok(program) => {
+                    info!("Program binary loaded from {:?}", path);
+                    return Ok(program);
+                }
+                Err(e) => warn!("Program binary {:?} was rejected! Error: {}", path, e),
+            }
+        }
+
+        let program = builder.compile(src)?;
+        if let Err(e) = builder
+            .program_binary(&program)
+            .and_then(|binary| self.store(&path, &binary))
+        {
+            warn!("Cannot cache program binary {:?}! Error: {}", path, e);
+        }
+        Ok(program)
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+// Fails if the file already exists
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+// Fails unless the file is a directory or a regular file, owned by the current user and not
+// writable by anyone else
+#[cfg(unix)]
+fn check_private(metadata: &fs::Metadata) -> GPUResult<()> {
+    use std::os::unix::fs::MetadataExt;
+    if !metadata.is_dir() && !metadata.is_file() {
+        return Err(GPUError::Simple("Not a directory or a regular file!"));
+    }
+    if metadata.uid() != unsafe { libc::geteuid() } {
+        return Err(GPUError::Simple("Not owned by the current user!"));
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(GPUError::Simple("Writable by other users!"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_private(_: &fs::Metadata) -> GPUResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    const BINARY_PREFIX: &str = "binary of ";
+
+    // Programs are their source, binaries are the source with a prefix
+    struct FakeBuilder {
+        name: &'static str,
+        driver: &'static str,
+        compilations: Cell<usize>,
+    }
+
+    impl FakeBuilder {
+        fn new(name: &'static str, driver: &'static str) -> Self {
+            FakeBuilder {
+                name,
+                driver,
+                compilations: Cell::new(0),
+            }
+        }
+    }
+
+    impl ProgramBuilder for FakeBuilder {
+        type Program = String;
+
+        fn device_name(&self) -> String {
+            self.name.to_string()
+        }
+        fn driver_version(&self) -> String {
+            self.driver.to_string()
+        }
+        fn compile(&self, src: &str) -> GPUResult<String> {
+            self.compilations.set(self.compilations.get() + 1);
+            Ok(src.to_string())
+        }
+        fn load_binary(&self, binary: &[u8]) -> GPUResult<String> {
+            match std::str::from_utf8(binary) {
+                Ok(binary) if binary.starts_with(BINARY_PREFIX) => {
+                    Ok(binary[BINARY_PREFIX.len()..].to_string())
+                }
+                _ => Err(GPUError::Simple("Invalid binary!")),
+            }
+        }
+        fn program_binary(&self, program: &String) -> GPUResult<Vec<u8>> {
+            Ok(format!("{}{}", BINARY_PREFIX, program).into_bytes())
+        }
+    }
+
+    fn cache(test: &str) -> ProgramCache {
+        let dir = env::temp_dir().join(format!(
+            "bellman-program-cache-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        ProgramCache::new(dir)
+    }
+
+    fn entries(cache: &ProgramCache) -> Vec<PathBuf> {
+        fs::read_dir(cache.dir())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn test_program_cache() {
+        let cache = cache("hit");
+        let rtx = FakeBuilder::new("GeForce RTX 2080 Ti", "450.80.02");
+
+        assert_eq!(cache.build(&rtx, "kernel").unwrap(), "kernel");
+        assert_eq!(cache.build(&rtx, "kernel").unwrap(), "kernel");
+        assert_eq!(rtx.compilations.get(), 1);
+        assert_eq!(entries(&cache).len(), 1);
+
+        // The source, the device and the driver are part of the key
+        assert_eq!(cache.build(&rtx, "other kernel").unwrap(), "other kernel");
+        let gtx = FakeBuilder::new("GeForce GTX 1060", "450.80.02");
+        cache.build(&gtx, "kernel").unwrap();
+        let updated = FakeBuilder::new("GeForce RTX 2080 Ti", "455.23.04");
+        cache.build(&updated, "kernel").unwrap();
+        assert_eq!(rtx.compilations.get(), 2);
+        assert_eq!(gtx.compilations.get(), 1);
+        assert_eq!(updated.compilations.get(), 1);
+        assert_eq!(entries(&cache).len(), 4);
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_entries() {
+        let cache = cache("corrupted");
+        let rtx = FakeBuilder::new("GeForce RTX 2080 Ti", "450.80.02");
+        cache.build(&rtx, "kernel").unwrap();
+        let path = entries(&cache).pop().unwrap();
+        let entry = fs::read(&path).unwrap();
+
+        let mut flipped = entry.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        for corrupted in &[
+            entry[..entry.len() - 1].to_vec(),
+            entry[..HEADER_LEN - 1].to_vec(),
+            flipped,
+            Vec::new(),
+        ] {
+            fs::write(&path, corrupted).unwrap();
+            let compilations = rtx.compilations.get();
+            assert_eq!(cache.build(&rtx, "kernel").unwrap(), "kernel");
+            assert_eq!(rtx.compilations.get(), compilations + 1);
+            // The entry is rewritten
+            assert_eq!(fs::read(&path).unwrap(), entry);
+        }
+
+        // Valid entry, but the driver rejects the binary
+        let mut rejected = MAGIC.to_vec();
+        rejected.extend(Sha256::digest(b"garbage"));
+        rejected.extend(b"garbage");
+        fs::write(&path, rejected).unwrap();
+        assert_eq!(cache.build(&rtx, "kernel").unwrap(), "kernel");
+        assert_eq!(fs::read(&path).unwrap(), entry);
+        assert_eq!(entries(&cache).len(), 1);
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_cache() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let cache = cache("private");
+        let rtx = FakeBuilder::new("GeForce RTX 2080 Ti", "450.80.02");
+        cache.build(&rtx, "kernel").unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(cache.dir()), 0o700);
+        let path = entries(&cache).pop().unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        // Entries other users can write are rebuilt
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o620)).unwrap();
+        cache.build(&rtx, "kernel").unwrap();
+        assert_eq!(rtx.compilations.get(), 2);
+        assert_eq!(mode(&path), 0o600);
+
+        // The cache isn't used at all if other users can write the directory
+        fs::set_permissions(cache.dir(), fs::Permissions::from_mode(0o777)).unwrap();
+        fs::remove_file(&path).unwrap();
+        cache.build(&rtx, "kernel").unwrap();
+        cache.build(&rtx, "kernel").unwrap();
+        assert_eq!(rtx.compilations.get(), 4);
+        assert!(entries(&cache).is_empty());
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_failed_store() {
+        let cache = cache("failed");
+        let rtx = FakeBuilder::new("GeForce RTX 2080 Ti", "450.80.02");
+        cache.build(&rtx, "kernel").unwrap();
+        let path = entries(&cache).pop().unwrap();
+
+        // The entry can't be replaced by a file, the temporary file is removed
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert_eq!(cache.build(&rtx, "kernel").unwrap(), "kernel");
+        assert_eq!(rtx.compilations.get(), 2);
+        assert_eq!(entries(&cache), vec![path]);
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+use super::backend::{Buffer, BufferBackend, ComputeBackend, Exponent};
+use super::error::{GPUError, GPUResult};
+use super::GpuCurve;
+use crate::bls::Engine;
+use ff::{Field, PrimeFieldRepr};
+use groupy::CurveProjective;
+
+/// Memory of `CpuBackend`. Elements are `None` until they are written, so that reading memory the
+/// kernels or the host haven't initialized is an error, instead of returning garbage.
+pub struct HostBuffer<T>(Vec<Option<T>>);
+
+impl<T: Copy> HostBuffer<T> {
+    fn get(&self, i: usize) -> GPUResult<T> {
+        match self.0.get(i) {
+            Some(Some(v)) => Ok(*v),
+            Some(None) => Err(GPUError::Simple("Read of uninitialized device memory!")),
+            None => Err(GPUError::Simple("Out of bounds device memory access!")),
+        }
+    }
+
+    fn set(&mut self, i: usize, v: T) -> GPUResult<()> {
+        match self.0.get_mut(i) {
+            Some(elem) => {
+                *elem = Some(v);
+                Ok(())
+            }
+            None => Err(GPUError::Simple("Out of bounds device memory access!")),
+        }
+    }
+}
+
+/// Reference implementation of the kernels in plain Rust, for testing the GPU code on machines
+/// without GPUs. Work-items are run one after the other, following the semantics of the OpenCL
+/// code (Including the work-group barriers of `radix_fft`) and with the same memory accesses.
+pub struct CpuBackend {
+    name: String,
+}
+
+impl CpuBackend {
+    pub fn new(name: &str) -> CpuBackend {
+        CpuBackend {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl<T: Copy> BufferBackend<T> for CpuBackend {
+    type Buffer = HostBuffer<T>;
+
+    fn create_buffer(&mut self, length: usize) -> GPUResult<HostBuffer<T>> {
+        Ok(HostBuffer(vec![None; length]))
+    }
+
+    fn write_from(
+        &mut self,
+        buffer: &mut HostBuffer<T>,
+        offset: usize,
+        data: &[T],
+    ) -> GPUResult<()> {
+        for (i, v) in data.iter().enumerate() {
+            buffer.set(offset + i, *v)?;
+        }
+        Ok(())
+    }
+
+    fn read_into(
+        &mut self,
+        buffer: &HostBuffer<T>,
+        offset: usize,
+        data: &mut [T],
+    ) -> GPUResult<()> {
+        for (i, v) in data.iter_mut().enumerate() {
+            *v = buffer.get(offset + i)?;
+        }
+        Ok(())
+    }
+}
+
+fn bitreverse(mut n: usize, bits: usize) -> usize {
+    let mut r = 0;
+    for _ in 0..bits {
+        r = (r << 1) | (n & 1);
+        n >>= 1;
+    }
+    r
+}
+
+// `FIELD_pow_lookup`, `bases` holds the squares of the base
+fn pow_lookup<F: Field>(bases: &HostBuffer<F>, mut exponent: usize) -> GPUResult<F> {
+    let mut res = F::one();
+    let mut i = 0;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            res.mul_assign(&bases.get(i)?);
+        }
+        exponent >>= 1;
+        i += 1;
+    }
+    Ok(res)
+}
+
+// `EXPONENT_get_bits`, bits are counted from the most significant one
+fn get_bits<R: PrimeFieldRepr>(exp: &R, skip: usize, window: usize) -> usize {
+    let limbs = exp.as_ref();
+    let mut ret = 0;
+    for i in skip..skip + window {
+        let limb = limbs[limbs.len() - 1 - i / 64];
+        ret = (ret << 1) | ((limb >> (63 - i % 64)) & 1) as usize;
+    }
+    ret
+}
+
+// Digit of the window `window` of the recoded exponent `exp`, like in `POINT_bellman_multiexp`
+fn window_digit<R: PrimeFieldRepr>(
+    exp: &R,
+    window: usize,
+    num_windows: usize,
+    window_size: usize,
+) -> i64 {
+    let exp_bits = exp.as_ref().len() * 64;
+    let bits = window * window_size;
+    let w = std::cmp::min(window_size, exp_bits - bits);
+    let offset = if window == num_windows - 1 {
+        0
+    } else {
+        1 << (window_size - 1)
+    };
+    get_bits(exp, exp_bits - bits - w, w) as i64 - offset
+}
+
+impl<E> ComputeBackend<E> for CpuBackend
+where
+    E: Engine,
+{
+    fn device_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn radix_fft(
+        &mut self,
+        global_work_size: usize,
+        local_work_size: usize,
+        x: &HostBuffer<E::Fr>,
+        y: &mut HostBuffer<E::Fr>,
+        pq: &HostBuffer<E::Fr>,
+        omegas: &HostBuffer<E::Fr>,
+        n: u32,
+        lgp: u32,
+        deg: u32,
+        max_deg: u32,
+    ) -> GPUResult<()> {
+        let (n, lgp, deg, max_deg) = (n as usize, lgp as usize, deg as usize, max_deg as usize);
+        let lsize = local_work_size;
+        let t = n >> deg;
+        let p = 1 << lgp;
+        let count = 1 << deg;
+        let counth = count >> 1;
+        // Range of `u` each work-item of a group works on
+        let range = |lid: usize| (count / lsize * lid, count / lsize * (lid + 1));
+
+        for index in 0..global_work_size / lsize {
+            let k = index & (p - 1);
+            let y_offset = ((index - k) << deg) + k;
+            let mut u = vec![E::Fr::zero(); count];
+
+            let twiddle = pow_lookup(omegas, (n >> lgp >> deg) * k)?;
+            for lid in 0..lsize {
+                let (counts, counte) = range(lid);
+                let mut tmp = twiddle.pow([counts as u64]);
+                for (i, elem) in u.iter_mut().enumerate().take(counte).skip(counts) {
+                    *elem = tmp;
+                    elem.mul_assign(&x.get(index + i * t)?);
+                    tmp.mul_assign(&twiddle);
+                }
+            }
+
+            let pqshift = max_deg - deg;
+            for rnd in 0..deg {
+                let bit = counth >> rnd;
+                for lid in 0..lsize {
+                    let (counts, counte) = range(lid);
+                    for i in counts >> 1..counte >> 1 {
+                        let di = i & (bit - 1);
+                        let i0 = (i << 1) - di;
+                        let i1 = i0 + bit;
+                        let (a, b) = (u[i0], u[i1]);
+                        u[i0].add_assign(&b);
+                        u[i1] = a;
+                        u[i1].sub_assign(&b);
+                        if di != 0 {
+                            u[i1].mul_assign(&pq.get(di << rnd << pqshift)?);
+                        }
+                    }
+                }
+            }
+
+            for lid in 0..lsize {
+                let (counts, counte) = range(lid);
+                for i in counts >> 1..counte >> 1 {
+                    y.set(y_offset + i * p, u[bitreverse(i, deg)])?;
+                    y.set(y_offset + (i + counth) * p, u[bitreverse(i + counth, deg)])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn multiexp<G>(
+        &mut self,
+        global_work_size: usize,
+        bases: &Buffer<Self, G>,
+        buckets: &mut Buffer<Self, G::Projective>,
+        results: &mut Buffer<Self, G::Projective>,
+        exps: &Buffer<Self, Exponent<G>>,
+        n: u32,
+        num_groups: u32,
+        num_windows: u32,
+        window_size: u32,
+    ) -> GPUResult<()>
+    where
+        G: GpuCurve,
+    {
+        let (n, num_groups, num_windows, window_size) = (
+            n as usize,
+            num_groups as usize,
+            num_windows as usize,
+            window_size as usize,
+        );
+        let bucket_len = 1 << (window_size - 1);
+        let len = (n as f32 / num_groups as f32).ceil() as usize;
+
+        for gid in 0..global_work_size {
+            if gid >= num_windows * num_groups {
+                continue;
+            }
+            let offset = bucket_len * gid;
+            for i in 0..bucket_len {
+                buckets.set(offset + i, G::Projective::zero())?;
+            }
+
+            let nstart = len * (gid / num_windows);
+            let nend = std::cmp::min(nstart + len, n);
+            for i in nstart..nend {
+                let digit =
+                    window_digit(&exps.get(i)?, gid % num_windows, num_windows, window_size);
+                if digit != 0 {
+                    let mut base = bases.get(i)?;
+                    if digit < 0 {
+                        base.negate();
+                    }
+                    let ind = offset + digit.abs() as usize - 1;
+                    let mut bucket = buckets.get(ind)?;
+                    bucket.add_assign_mixed(&base);
+                    buckets.set(ind, bucket)?;
+                }
+            }
+
+            // Summation by parts
+            let mut res = G::Projective::zero();
+            let mut acc = G::Projective::zero();
+            for j in (0..bucket_len).rev() {
+                acc.add_assign(&buckets.get(offset + j)?);
+                res.add_assign(&acc);
+            }
+            results.set(gid, res)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::multiexp::SignedDigits;
+    use ff::PrimeField;
+
+    #[test]
+    fn test_host_buffer() {
+        let mut backend = CpuBackend::new("CPU");
+        let mut buffer = BufferBackend::<u32>::create_buffer(&mut backend, 4).unwrap();
+        backend.write_from(&mut buffer, 1, &[1, 2]).unwrap();
+        let mut data = [0u32; 2];
+        backend.read_into(&buffer, 1, &mut data).unwrap();
+        assert_eq!(data, [1, 2]);
+        assert!(backend.read_into(&buffer, 2, &mut data).is_err());
+        assert!(backend.write_from(&mut buffer, 3, &[3, 4]).is_err());
+        assert_eq!(ComputeBackend::<Bls12>::device_name(&backend), "CPU");
+    }
+
+    #[test]
+    fn test_window_digits() {
+        let rng = &mut rand::thread_rng();
+        for window_size in 1..=12 {
+            let digits = SignedDigits::<Fr>::new(window_size);
+            let num_windows = digits.num_windows();
+            for _ in 0..10 {
+                let recoded = digits.recode(&Fr::random(rng).into_repr());
+                for window in 0..num_windows {
+                    assert_eq!(
+                        window_digit(
+                            &recoded,
+                            window as usize,
+                            num_windows as usize,
+                            window_size as usize
+                        ),
+                        digits.digit(&recoded, window)
+                    );
+                }
+            }
+        }
+    }
+}
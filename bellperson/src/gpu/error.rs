@@ -9,6 +9,9 @@ pub enum GPUError {
     #[error("OpenCL Error: {0}")]
     OpenCL(#[from] opencl::GPUError),
     #[cfg(feature = "gpu")]
+    #[error("IO Error: {0}")]
+    IO(#[from] std::io::Error),
+    #[cfg(feature = "gpu")]
     #[error("GPU taken by a high priority process!")]
     GPUTaken,
     #[cfg(feature = "gpu")]
@@ -16,6 +19,8 @@ pub enum GPUError {
     KernelUninitialized,
     #[error("GPU accelerator is disabled!")]
     GPUDisabled,
+    #[error("Invalid device selector criterion: {0}")]
+    InvalidDeviceSelector(String),
 }
 
 pub type GPUResult<T> = std::result::Result<T, GPUError>;
@@ -29,3 +34,17 @@ impl From<std::boxed::Box<dyn std::any::Any + std::marker::Send>> for GPUError {
         }
     }
 }
+
+#[cfg(feature = "gpu")]
+impl From<ocl::Error> for GPUError {
+    fn from(e: ocl::Error) -> Self {
+        GPUError::OpenCL(e.into())
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl From<ocl::core::Error> for GPUError {
+    fn from(e: ocl::core::Error) -> Self {
+        GPUError::OpenCL(e.into())
+    }
+}
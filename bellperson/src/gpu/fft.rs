@@ -1,10 +1,14 @@
 use crate::bls::Engine;
 use crate::gpu::{
+    backend::{Buffer, ComputeBackend},
     error::{GPUError, GPUResult},
-    locks, sources,
+    locks,
+    opencl_backend::OpenCLBackend,
+    selector::DeviceSelector,
+    sources::{GpuCurve, GpuField},
 };
 use ff::Field;
-use log::info;
+use log::{error, info};
 use rust_gpu_tools::*;
 use std::cmp;
 
@@ -12,46 +16,43 @@ const LOG2_MAX_ELEMENTS: usize = 32; // At most 2^32 elements is supported.
 const MAX_LOG2_RADIX: u32 = 8; // Radix256
 const MAX_LOG2_LOCAL_WORK_SIZE: u32 = 7; // 128
 
-pub struct FFTKernel<E>
+// FFT kernel for a single GPU
+pub struct SingleFFTKernel<E, B = OpenCLBackend>
 where
     E: Engine,
+    B: ComputeBackend<E>,
 {
-    program: opencl::Program,
-    pq_buffer: opencl::Buffer<E::Fr>,
-    omegas_buffer: opencl::Buffer<E::Fr>,
-    _lock: locks::GPULock, // RFC 1857: struct fields are dropped in the same order as they are declared.
+    backend: B,
+    pq_buffer: Buffer<B, E::Fr>,
+    omegas_buffer: Buffer<B, E::Fr>,
     priority: bool,
 }
 
-impl<E> FFTKernel<E>
+impl<E> SingleFFTKernel<E>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
 {
-    pub fn create(priority: bool) -> GPUResult<FFTKernel<E>> {
-        let lock = locks::GPULock::lock();
-
-        let devices = opencl::Device::all()?;
-        if devices.is_empty() {
-            return Err(GPUError::Simple("No working GPUs found!"));
-        }
-
-        // Select the first device for FFT
-        let device = devices[0].clone();
-
-        let src = sources::kernel::<E>(device.brand() == opencl::Brand::Nvidia);
-
-        let program = opencl::Program::from_opencl(device, &src)?;
-        let pq_buffer = program.create_buffer::<E::Fr>(1 << MAX_LOG2_RADIX >> 1)?;
-        let omegas_buffer = program.create_buffer::<E::Fr>(LOG2_MAX_ELEMENTS)?;
+    pub fn create(d: opencl::Device, priority: bool) -> GPUResult<SingleFFTKernel<E>> {
+        Self::with_backend(OpenCLBackend::create::<E>(d)?, priority)
+    }
+}
 
-        info!("FFT: 1 working device(s) selected.");
-        info!("FFT: Device 0: {}", program.device().name());
+impl<E, B> SingleFFTKernel<E, B>
+where
+    E: Engine,
+    B: ComputeBackend<E>,
+{
+    pub fn with_backend(mut backend: B, priority: bool) -> GPUResult<SingleFFTKernel<E, B>> {
+        let pq_buffer = backend.create_buffer(1 << MAX_LOG2_RADIX >> 1)?;
+        let omegas_buffer = backend.create_buffer(LOG2_MAX_ELEMENTS)?;
 
-        Ok(FFTKernel {
-            program,
+        Ok(SingleFFTKernel {
+            backend,
             pq_buffer,
             omegas_buffer,
-            _lock: lock,
             priority,
         })
     }
@@ -63,8 +64,8 @@ where
     /// * `max_deg` - The precalculated values pq` and `omegas` are valid for radix degrees up to `max_deg`
     fn radix_fft_round(
         &mut self,
-        src_buffer: &opencl::Buffer<E::Fr>,
-        dst_buffer: &opencl::Buffer<E::Fr>,
+        src_buffer: &Buffer<B, E::Fr>,
+        dst_buffer: &mut Buffer<B, E::Fr>,
         log_n: u32,
         log_p: u32,
         deg: u32,
@@ -77,24 +78,18 @@ where
         let n = 1u32 << log_n;
         let local_work_size = 1 << cmp::min(deg - 1, MAX_LOG2_LOCAL_WORK_SIZE);
         let global_work_size = (n >> deg) * local_work_size;
-        let kernel = self.program.create_kernel(
-            "radix_fft",
+        self.backend.radix_fft(
             global_work_size as usize,
-            Some(local_work_size as usize),
-        );
-        call_kernel!(
-            kernel,
+            local_work_size as usize,
             src_buffer,
             dst_buffer,
             &self.pq_buffer,
             &self.omegas_buffer,
-            opencl::LocalBuffer::<E::Fr>::new(1 << deg),
             n,
             log_p,
             deg,
-            max_deg
-        )?;
-        Ok(())
+            max_deg,
+        )
     }
 
     /// Share some precalculated values between threads to boost the performance
@@ -111,7 +106,7 @@ where
                 pq[i].mul_assign(&twiddle);
             }
         }
-        self.pq_buffer.write_from(0, &pq)?;
+        self.backend.write_from(&mut self.pq_buffer, 0, &pq)?;
 
         // Precalculate [omega, omega^2, omega^4, omega^8, ..., omega^(2^31)]
         let mut omegas = vec![E::Fr::zero(); 32];
@@ -119,7 +114,8 @@ where
         for i in 1..LOG2_MAX_ELEMENTS {
             omegas[i] = omegas[i - 1].pow([2u64]);
         }
-        self.omegas_buffer.write_from(0, &omegas)?;
+        self.backend
+            .write_from(&mut self.omegas_buffer, 0, &omegas)?;
 
         Ok(())
     }
@@ -129,23 +125,341 @@ where
     /// * `log_n` - Specifies log2 of number of elements
     pub fn radix_fft(&mut self, a: &mut [E::Fr], omega: &E::Fr, log_n: u32) -> GPUResult<()> {
         let n = 1 << log_n;
-        let mut src_buffer = self.program.create_buffer::<E::Fr>(n)?;
-        let mut dst_buffer = self.program.create_buffer::<E::Fr>(n)?;
+        let mut src_buffer = self.backend.create_buffer(n)?;
+        let mut dst_buffer = self.backend.create_buffer(n)?;
 
         let max_deg = cmp::min(MAX_LOG2_RADIX, log_n);
         self.setup_pq_omegas(omega, n, max_deg)?;
 
-        src_buffer.write_from(0, &*a)?;
+        self.backend.write_from(&mut src_buffer, 0, &*a)?;
         let mut log_p = 0u32;
         while log_p < log_n {
             let deg = cmp::min(max_deg, log_n - log_p);
-            self.radix_fft_round(&src_buffer, &dst_buffer, log_n, log_p, deg, max_deg)?;
+            self.radix_fft_round(&src_buffer, &mut dst_buffer, log_n, log_p, deg, max_deg)?;
             log_p += deg;
             std::mem::swap(&mut src_buffer, &mut dst_buffer);
         }
 
-        src_buffer.read_into(0, a)?;
+        self.backend.read_into(&src_buffer, 0, a)?;
 
         Ok(())
     }
 }
+
+/// Assigns FFTs of sizes `2^log_ns[i]` to `num_devices` devices, as the index of the device of each
+/// FFT. The largest FFTs are assigned first, each to the device with the least work so far.
+fn partition(log_ns: &[u32], num_devices: usize) -> Vec<usize> {
+    // An FFT of size `n` costs `n * log(n)`
+    let cost = |log_n: u32| (log_n as u64) << log_n;
+    let mut order = (0..log_ns.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| cmp::Reverse(cost(log_ns[i])));
+
+    let mut loads = vec![0u64; num_devices];
+    let mut devices = vec![0; log_ns.len()];
+    for i in order {
+        let device = (0..num_devices).min_by_key(|&d| loads[d]).unwrap();
+        loads[device] += cost(log_ns[i]);
+        devices[i] = device;
+    }
+    devices
+}
+
+// A struct that contains several FFT kernels for different devices
+pub struct FFTKernel<E, B = OpenCLBackend>
+where
+    E: Engine,
+    B: ComputeBackend<E>,
+{
+    kernels: Vec<SingleFFTKernel<E, B>>,
+    _lock: Option<locks::GPULock>, // RFC 1857: struct fields are dropped in the same order as they are declared.
+}
+
+impl<E> FFTKernel<E>
+where
+    E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
+{
+    /// Runs on the devices selected by `BELLMAN_GPU_DEVICES`
+    pub fn create(priority: bool) -> GPUResult<FFTKernel<E>> {
+        Self::create_with_selector(priority, &DeviceSelector::from_env()?)
+    }
+
+    /// Runs on the devices selected by `selector`
+    pub fn create_with_selector(
+        priority: bool,
+        selector: &DeviceSelector,
+    ) -> GPUResult<FFTKernel<E>> {
+        let lock = locks::GPULock::lock();
+
+        let devices = selector.select(opencl::Device::all()?);
+
+        let kernels: Vec<_> = devices
+            .into_iter()
+            .map(|d| (d.clone(), SingleFFTKernel::<E>::create(d, priority)))
+            .filter_map(|(device, res)| {
+                if let Err(ref e) = res {
+                    error!(
+                        "Cannot initialize kernel for device '{}'! Error: {}",
+                        device.name(),
+                        e
+                    );
+                }
+                res.ok()
+            })
+            .collect();
+
+        let mut kernel = Self::with_kernels(kernels)?;
+        kernel._lock = Some(lock);
+        info!(
+            "FFT: {} working device(s) selected. (Selector: {})",
+            kernel.kernels.len(),
+            selector
+        );
+        for (i, k) in kernel.kernels.iter().enumerate() {
+            info!(
+                "FFT: Device {}: {}",
+                i,
+                ComputeBackend::<E>::device_name(&k.backend)
+            );
+        }
+        Ok(kernel)
+    }
+}
+
+impl<E, B> FFTKernel<E, B>
+where
+    E: Engine,
+    B: ComputeBackend<E>,
+{
+    /// Distributes the FFTs between `kernels`, without taking the GPU lock
+    pub fn with_kernels(kernels: Vec<SingleFFTKernel<E, B>>) -> GPUResult<FFTKernel<E, B>> {
+        if kernels.is_empty() {
+            return Err(GPUError::Simple("No working GPUs found!"));
+        }
+        Ok(FFTKernel {
+            kernels,
+            _lock: None,
+        })
+    }
+
+    /// Performs FFT on `a`, on the first device
+    /// * `omega` - Special value `omega` is used for FFT over finite-fields
+    /// * `log_n` - Specifies log2 of number of elements
+    pub fn radix_fft(&mut self, a: &mut [E::Fr], omega: &E::Fr, log_n: u32) -> GPUResult<()> {
+        self.kernels[0].radix_fft(a, omega, log_n)
+    }
+
+    /// Performs independent FFTs on `inputs`, spread across the devices. The FFT of `inputs[i]`
+    /// uses `omegas[i]` and `log_ns[i]`, like `radix_fft`.
+    /// * `done` - FFTs are skipped if their flag is set, and flags are set as FFTs complete, so that
+    /// calling again after an error only performs the remaining FFTs
+    pub fn radix_fft_many(
+        &mut self,
+        inputs: &mut [&mut [E::Fr]],
+        omegas: &[E::Fr],
+        log_ns: &[u32],
+        done: &mut [bool],
+    ) -> GPUResult<()>
+    where
+        Buffer<B, E::Fr>: Send,
+    {
+        let pending_log_ns = log_ns
+            .iter()
+            .zip(done.iter())
+            .filter(|(_, done)| !**done)
+            .map(|(log_n, _)| *log_n)
+            .collect::<Vec<_>>();
+        let devices = partition(&pending_log_ns, self.kernels.len());
+
+        let mut jobs = self.kernels.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+        let pending = inputs
+            .iter_mut()
+            .zip(omegas)
+            .zip(log_ns)
+            .zip(done.iter_mut())
+            .filter(|(_, done)| !**done);
+        for ((((a, omega), log_n), done), device) in pending.zip(devices) {
+            jobs[device].push((&mut **a, omega, *log_n, done));
+        }
+
+        crate::multicore::THREAD_POOL.install(|| {
+            use rayon::prelude::*;
+
+            self.kernels
+                .par_iter_mut()
+                .zip(jobs.into_par_iter())
+                .map(|(kern, jobs)| -> GPUResult<()> {
+                    for (a, omega, log_n, done) in jobs {
+                        kern.radix_fft(a, omega, log_n)?;
+                        *done = true;
+                    }
+                    Ok(())
+                })
+                .collect::<GPUResult<Vec<_>>>()
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::gpu::{BufferBackend, CpuBackend, Exponent, HostBuffer};
+    use ff::PrimeField;
+    use std::sync::{Arc, Mutex};
+
+    // Records the sizes of the FFTs it performs
+    struct RecordingBackend {
+        cpu: CpuBackend,
+        log_ns: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl<T: Copy> BufferBackend<T> for RecordingBackend {
+        type Buffer = HostBuffer<T>;
+
+        fn create_buffer(&mut self, length: usize) -> GPUResult<HostBuffer<T>> {
+            self.cpu.create_buffer(length)
+        }
+        fn write_from(
+            &mut self,
+            buffer: &mut HostBuffer<T>,
+            offset: usize,
+            data: &[T],
+        ) -> GPUResult<()> {
+            self.cpu.write_from(buffer, offset, data)
+        }
+        fn read_into(
+            &mut self,
+            buffer: &HostBuffer<T>,
+            offset: usize,
+            data: &mut [T],
+        ) -> GPUResult<()> {
+            self.cpu.read_into(buffer, offset, data)
+        }
+    }
+
+    impl ComputeBackend<Bls12> for RecordingBackend {
+        fn device_name(&self) -> String {
+            ComputeBackend::<Bls12>::device_name(&self.cpu)
+        }
+
+        fn radix_fft(
+            &mut self,
+            global_work_size: usize,
+            local_work_size: usize,
+            src: &HostBuffer<Fr>,
+            dst: &mut HostBuffer<Fr>,
+            pq: &HostBuffer<Fr>,
+            omegas: &HostBuffer<Fr>,
+            n: u32,
+            log_p: u32,
+            deg: u32,
+            max_deg: u32,
+        ) -> GPUResult<()> {
+            if log_p == 0 {
+                self.log_ns.lock().unwrap().push(n.trailing_zeros());
+            }
+            ComputeBackend::<Bls12>::radix_fft(
+                &mut self.cpu,
+                global_work_size,
+                local_work_size,
+                src,
+                dst,
+                pq,
+                omegas,
+                n,
+                log_p,
+                deg,
+                max_deg,
+            )
+        }
+
+        fn multiexp<G>(
+            &mut self,
+            _: usize,
+            _: &Buffer<Self, G>,
+            _: &mut Buffer<Self, G::Projective>,
+            _: &mut Buffer<Self, G::Projective>,
+            _: &Buffer<Self, Exponent<G>>,
+            _: u32,
+            _: u32,
+            _: u32,
+            _: u32,
+        ) -> GPUResult<()>
+        where
+            G: GpuCurve,
+        {
+            Err(GPUError::Simple("not supported by the test backend"))
+        }
+    }
+
+    #[test]
+    fn test_partition() {
+        assert_eq!(partition(&[10, 10, 10], 1), vec![0, 0, 0]);
+        assert_eq!(partition(&[10, 10, 10], 3), vec![0, 1, 2]);
+        assert_eq!(partition(&[4, 10, 10], 2), vec![0, 0, 1]);
+        assert_eq!(
+            partition(&[10, 10, 10, 10, 10, 10, 10], 3),
+            vec![0, 1, 2, 0, 1, 2, 0]
+        );
+        assert_eq!(partition(&[8, 12, 8, 9], 2), vec![1, 0, 1, 1]);
+        assert_eq!(partition(&[10], 4), vec![0]);
+        assert!(partition(&[], 2).is_empty());
+    }
+
+    #[test]
+    fn test_radix_fft_many() {
+        let rng = &mut rand::thread_rng();
+        let recorded = (0..3)
+            .map(|_| Arc::new(Mutex::new(Vec::new())))
+            .collect::<Vec<_>>();
+        let kernels = recorded
+            .iter()
+            .enumerate()
+            .map(|(i, log_ns)| {
+                let backend = RecordingBackend {
+                    cpu: CpuBackend::new(&format!("CPU {}", i)),
+                    log_ns: log_ns.clone(),
+                };
+                SingleFFTKernel::<Bls12, _>::with_backend(backend, false).unwrap()
+            })
+            .collect();
+        let mut kern = FFTKernel::with_kernels(kernels).unwrap();
+
+        let log_ns = [9, 4, 9, 3, 9];
+        let omegas = log_ns
+            .iter()
+            .map(|&log_n| Fr::root_of_unity().pow([1u64 << (Fr::S - log_n)]))
+            .collect::<Vec<_>>();
+        let mut inputs = log_ns
+            .iter()
+            .map(|&log_n| (0..1 << log_n).map(|_| Fr::random(rng)).collect())
+            .collect::<Vec<Vec<_>>>();
+        let mut expected = inputs.clone();
+        for ((a, omega), &log_n) in expected.iter_mut().zip(&omegas).zip(&log_ns) {
+            kern.radix_fft(a, omega, log_n).unwrap();
+        }
+        recorded[0].lock().unwrap().clear();
+
+        // The third FFT was already performed by an earlier call
+        let mut done = [false, false, true, false, false];
+        inputs[2] = expected[2].clone();
+        let mut slices = inputs.iter_mut().map(|a| &mut a[..]).collect::<Vec<_>>();
+        kern.radix_fft_many(&mut slices, &omegas, &log_ns, &mut done)
+            .unwrap();
+        assert_eq!(done, [true; 5]);
+        assert_eq!(inputs, expected);
+
+        // The two largest remaining FFTs go to different devices, the small ones to the third
+        let mut assigned = recorded
+            .iter()
+            .map(|log_ns| log_ns.lock().unwrap().clone())
+            .collect::<Vec<_>>();
+        assigned.sort();
+        assert_eq!(assigned, vec![vec![4, 3], vec![9], vec![9]]);
+    }
+}
@@ -1,4 +1,4 @@
-uint bitreverse(uint n, uint bits) {
+uint FIELD_bitreverse(uint n, uint bits) {
   uint r = 0;
   for(int i = 0; i < bits; i++) {
     r = (r << 1) | (n & 1);
@@ -10,15 +10,15 @@ uint bitreverse(uint n, uint bits) {
 /*
  * FFT algorithm is inspired from: http://www.bealto.com/gpu-fft_group-1.html
  */
-__kernel void radix_fft(__global FIELD* x, // Source buffer
-                        __global FIELD* y, // Destination buffer
-                        __global FIELD* pq, // Precalculated twiddle factors
-                        __global FIELD* omegas, // [omega, omega^2, omega^4, ...]
-                        __local FIELD* u, // Local buffer to store intermediary values
-                        uint n, // Number of elements
-                        uint lgp, // Log2 of `p` (Read more in the link above)
-                        uint deg, // 1=>radix2, 2=>radix4, 3=>radix8, ...
-                        uint max_deg) // Maximum degree supported, according to `pq` and `omegas`
+__kernel void FIELD_radix_fft(__global FIELD* x, // Source buffer
+                              __global FIELD* y, // Destination buffer
+                              __global FIELD* pq, // Precalculated twiddle factors
+                              __global FIELD* omegas, // [omega, omega^2, omega^4, ...]
+                              __local FIELD* u, // Local buffer to store intermediary values
+                              uint n, // Number of elements
+                              uint lgp, // Log2 of `p` (Read more in the link above)
+                              uint deg, // 1=>radix2, 2=>radix4, 3=>radix8, ...
+                              uint max_deg) // Maximum degree supported, according to `pq` and `omegas`
 {
   uint lid = get_local_id(0);
   uint lsize = get_local_size(0);
@@ -62,8 +62,8 @@ __kernel void radix_fft(__global FIELD* x, // Source buffer
   }
 
   for(uint i = counts >> 1; i < counte >> 1; i++) {
-    y[i*p] = u[bitreverse(i, deg)];
-    y[(i+counth)*p] = u[bitreverse(i + counth, deg)];
+    y[i*p] = u[FIELD_bitreverse(i, deg)];
+    y[(i+counth)*p] = u[FIELD_bitreverse(i + counth, deg)];
   }
 }
 
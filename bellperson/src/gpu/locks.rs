@@ -68,6 +68,8 @@ impl Drop for PriorityLock {
 use super::error::{GPUError, GPUResult};
 use super::fft::FFTKernel;
 use super::multiexp::MultiexpKernel;
+use super::selector::DeviceSelector;
+use super::sources::{GpuCurve, GpuField};
 use crate::bls::Engine;
 use crate::domain::create_fft_kernel;
 use crate::multiexp::create_multiexp_kernel;
@@ -80,26 +82,55 @@ macro_rules! locked_kernel {
         {
             log_d: usize,
             priority: bool,
+            selector: Option<DeviceSelector>,
+            // Only the constructors need `E` to be supported by the kernels
+            create: fn(usize, bool, Option<&DeviceSelector>) -> Option<$kern<E>>,
             kernel: Option<$kern<E>>,
         }
 
         impl<E> $class<E>
         where
             E: Engine,
+            E::Fr: GpuField,
+            E::G1Affine: GpuCurve,
+            E::G2Affine: GpuCurve,
         {
+            /// The kernel runs on the devices selected by `BELLMAN_GPU_DEVICES`
             pub fn new(log_d: usize, priority: bool) -> $class<E> {
                 $class::<E> {
                     log_d,
                     priority,
+                    selector: None,
+                    create: $func::<E>,
                     kernel: None,
                 }
             }
 
+            /// The kernel runs on the devices selected by `selector`
+            pub fn with_selector(
+                log_d: usize,
+                priority: bool,
+                selector: DeviceSelector,
+            ) -> $class<E> {
+                $class::<E> {
+                    log_d,
+                    priority,
+                    selector: Some(selector),
+                    create: $func::<E>,
+                    kernel: None,
+                }
+            }
+        }
+
+        impl<E> $class<E>
+        where
+            E: Engine,
+        {
             fn init(&mut self) {
                 if self.kernel.is_none() {
                     PriorityLock::wait(self.priority);
                     info!("GPU is available for {}!", $name);
-                    self.kernel = $func::<E>(self.log_d, self.priority);
+                    self.kernel = (self.create)(self.log_d, self.priority, self.selector.as_ref());
                 }
             }
 
@@ -2,6 +2,10 @@ mod error;
 
 pub use self::error::*;
 
+mod selector;
+
+pub use self::selector::*;
+
 #[cfg(feature = "gpu")]
 mod locks;
 
@@ -20,6 +24,38 @@ mod utils;
 #[cfg(feature = "gpu")]
 pub use self::utils::*;
 
+mod backend;
+
+pub use self::backend::*;
+
+#[cfg(feature = "gpu")]
+mod opencl_backend;
+
+#[cfg(feature = "gpu")]
+pub use self::opencl_backend::*;
+
+#[cfg(feature = "gpu")]
+mod cache;
+
+#[cfg(feature = "gpu")]
+pub use self::cache::*;
+
+mod cpu_backend;
+
+pub use self::cpu_backend::*;
+
+#[cfg(feature = "gpu")]
+mod resident;
+
+#[cfg(feature = "gpu")]
+pub use self::resident::*;
+
+#[cfg(feature = "gpu")]
+mod scheduler;
+
+#[cfg(feature = "gpu")]
+pub use self::scheduler::*;
+
 #[cfg(feature = "gpu")]
 mod fft;
 
@@ -1,18 +1,24 @@
+use super::backend::{Buffer, BufferBackend, ComputeBackend, Exponent};
 use super::error::{GPUError, GPUResult};
 use super::locks;
-use super::sources;
+use super::opencl_backend::OpenCLBackend;
+use super::resident::{BasesKey, ResidentBases};
+use super::scheduler::{DoubleBuffered, Scheduler, Step};
+use super::selector::DeviceSelector;
+use super::sources::{GpuCurve, GpuField};
 use super::utils;
 use crate::bls::Engine;
 use crate::multicore::Worker;
-use crate::multiexp::{multiexp as cpu_multiexp, FullDensity};
+use crate::multiexp::{multiexp as cpu_multiexp, FullDensity, SignedDigits};
 use ff::{PrimeField, ScalarEngine};
 use groupy::{CurveAffine, CurveProjective};
-use log::{error, info};
+use log::{error, info, warn};
 use rust_gpu_tools::*;
-use std::any::TypeId;
 use std::sync::Arc;
 
-const MAX_WINDOW_SIZE: usize = 10;
+// Signed digits need half as many buckets as unsigned ones, so windows can be one bit larger in the
+// same memory
+const MAX_WINDOW_SIZE: usize = 11;
 const LOCAL_WORK_SIZE: usize = 256;
 const MEMORY_PADDING: f64 = 0.2f64; // Let 20% of GPU memory be free
 
@@ -32,14 +38,17 @@ pub fn get_cpu_utilization() -> f64 {
 }
 
 // Multiexp kernel for a single GPU
-pub struct SingleMultiexpKernel<E>
+pub struct SingleMultiexpKernel<E, B = OpenCLBackend>
 where
     E: Engine,
+    B: ComputeBackend<E>,
 {
-    program: opencl::Program,
+    backend: B,
 
     core_count: usize,
     n: usize,
+    resident: ResidentBases,
+    scheduler: Box<dyn Scheduler>,
 
     priority: bool,
     _phantom: std::marker::PhantomData<E::Fr>,
@@ -88,10 +97,32 @@ where
     let exp_size = exp_size::<E>();
     let proj_size = std::mem::size_of::<E::G1>() + std::mem::size_of::<E::G2>();
     ((((mem as f64) * (1f64 - MEMORY_PADDING)) as usize)
-        - (2 * core_count * ((1 << MAX_WINDOW_SIZE) + 1) * proj_size))
+        - (2 * core_count * ((1 << (MAX_WINDOW_SIZE - 1)) + 1) * proj_size))
         / (aff_size + exp_size)
 }
 
+// Memory left for resident bases, once `slots` chunks of `n` bases and exponents, and the buckets,
+// are allocated
+fn calc_resident_capacity<E>(mem: u64, core_count: usize, n: usize, slots: usize) -> usize
+where
+    E: Engine,
+{
+    let aff_size = std::mem::size_of::<E::G1Affine>() + std::mem::size_of::<E::G2Affine>();
+    let exp_size = exp_size::<E>();
+    let proj_size = std::mem::size_of::<E::G1>() + std::mem::size_of::<E::G2>();
+    (((mem as f64) * (1f64 - MEMORY_PADDING)) as usize)
+        .saturating_sub(2 * core_count * ((1 << (MAX_WINDOW_SIZE - 1)) + 1) * proj_size)
+        .saturating_sub(slots * n * (aff_size + exp_size))
+}
+
+// Recoding of the exponents and number of groups of a chunk of `n` elements
+fn calc_params<F: PrimeField>(n: usize, core_count: usize) -> (SignedDigits<F>, usize) {
+    let window_size = calc_window_size(n, F::NUM_BITS as usize + 1, core_count);
+    let digits = SignedDigits::new(window_size as u32);
+    let num_groups = calc_num_groups(core_count, digits.num_windows() as usize);
+    (digits, num_groups)
+}
+
 fn exp_size<E: Engine>() -> usize {
     std::mem::size_of::<<E::Fr as ff::PrimeField>::Repr>()
 }
@@ -99,21 +130,54 @@ fn exp_size<E: Engine>() -> usize {
 impl<E> SingleMultiexpKernel<E>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
 {
     pub fn create(d: opencl::Device, priority: bool) -> GPUResult<SingleMultiexpKernel<E>> {
-        let src = sources::kernel::<E>(d.brand() == opencl::Brand::Nvidia);
-
-        let exp_bits = exp_size::<E>() * 8;
         let core_count = utils::get_core_count(&d);
         let mem = d.memory();
-        let max_n = calc_chunk_size::<E>(mem, core_count);
+        Self::with_backend(OpenCLBackend::create::<E>(d)?, core_count, mem, priority)
+    }
+}
+
+impl<E, B> SingleMultiexpKernel<E, B>
+where
+    E: Engine,
+    B: ComputeBackend<E>,
+{
+    /// Runs on `backend`, a device with `core_count` cores and `mem` bytes of memory, overlapping
+    /// the uploads with the computations
+    pub fn with_backend(
+        backend: B,
+        core_count: usize,
+        mem: u64,
+        priority: bool,
+    ) -> GPUResult<SingleMultiexpKernel<E, B>> {
+        Self::with_scheduler(backend, core_count, mem, priority, Box::new(DoubleBuffered))
+    }
+
+    /// Like `with_backend`, with the chunks of the multiexps processed as `scheduler` decides
+    pub fn with_scheduler(
+        backend: B,
+        core_count: usize,
+        mem: u64,
+        priority: bool,
+        scheduler: Box<dyn Scheduler>,
+    ) -> GPUResult<SingleMultiexpKernel<E, B>> {
+        let exp_bits = exp_size::<E>() * 8;
+        let slots = scheduler.slots();
+        let max_n = calc_chunk_size::<E>(mem, core_count) / slots;
         let best_n = calc_best_chunk_size(MAX_WINDOW_SIZE, core_count, exp_bits);
         let n = std::cmp::min(max_n, best_n);
+        let resident = ResidentBases::new(calc_resident_capacity::<E>(mem, core_count, n, slots));
 
         Ok(SingleMultiexpKernel {
-            program: opencl::Program::from_opencl(d, &src)?,
+            backend,
             core_count,
             n,
+            resident,
+            scheduler,
             priority,
             _phantom: std::marker::PhantomData,
         })
@@ -125,82 +189,207 @@ where
         exps: &[<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr],
         n: usize,
     ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: GpuCurve,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: 'static,
+    {
+        let keys = vec![None; self.chunks(n).len()];
+        self.run(bases, &keys, exps, n)
+    }
+
+    /// Multiexp of `bases[offset..offset + n]`, whose chunks are kept in device memory for the next
+    /// multiexps of the same range. Chunks are uploaded like in `multiexp` if they don't fit.
+    pub fn multiexp_resident<G>(
+        &mut self,
+        bases: &Arc<Vec<G>>,
+        offset: usize,
+        exps: &[<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr],
+        n: usize,
+    ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: GpuCurve,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: Send + 'static,
+    {
+        let keys = self
+            .chunks(n)
+            .into_iter()
+            .map(|chunk| {
+                let key = BasesKey::new(bases, offset + chunk.start, chunk.len());
+                self.make_resident(key, bases, offset + chunk.start..offset + chunk.end)?;
+                Ok(Some(key))
+            })
+            .collect::<GPUResult<Vec<_>>>()?;
+        self.run(&bases[offset..offset + n], &keys, exps, n)
+    }
+
+    // Ranges of the chunks `n` elements are split into
+    fn chunks(&self, n: usize) -> Vec<std::ops::Range<usize>> {
+        (0..n)
+            .step_by(self.n)
+            .map(|start| start..std::cmp::min(start + self.n, n))
+            .collect()
+    }
+
+    // Uploads `bases[range]` into a resident buffer, unless it's already there or can't fit
+    fn make_resident<G>(
+        &mut self,
+        key: BasesKey,
+        bases: &Arc<Vec<G>>,
+        range: std::ops::Range<usize>,
+    ) -> GPUResult<()>
     where
         G: CurveAffine,
+        B: BufferBackend<G>,
+        Buffer<B, G>: Send + 'static,
     {
-        if locks::PriorityLock::should_break(self.priority) {
-            return Err(GPUError::GPUTaken);
+        let size = range.len() * std::mem::size_of::<G>();
+        // Hits become the most recently used buffers
+        if self.resident.get::<Buffer<B, G>>(&key).is_some() || !self.resident.reserve(size) {
+            return Ok(());
+        }
+        // Evict more buffers if the device is out of memory anyway
+        loop {
+            match BufferBackend::<G>::create_buffer(&mut self.backend, range.len()) {
+                Ok(mut buffer) => {
+                    self.backend.write_from(&mut buffer, 0, &bases[range])?;
+                    self.resident.insert(key, bases, buffer, size);
+                    return Ok(());
+                }
+                Err(e) => {
+                    if !self.resident.evict() {
+                        warn!("Cannot keep bases in device memory! Error: {}", e);
+                        return Ok(());
+                    }
+                }
+            }
         }
+    }
 
-        let exp_bits = exp_size::<E>() * 8;
-        let window_size = calc_window_size(n as usize, exp_bits, self.core_count);
-        let num_windows = ((exp_bits as f64) / (window_size as f64)).ceil() as usize;
-        let num_groups = calc_num_groups(self.core_count, num_windows);
-        let bucket_len = 1 << window_size;
+    // Multiexp of `bases[..n]`, in the chunks of `self.chunks(n)` and in the order of the
+    // scheduler. The bases of the chunks whose key is resident aren't uploaded.
+    fn run<G>(
+        &mut self,
+        bases: &[G],
+        keys: &[Option<BasesKey>],
+        exps: &[<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr],
+        n: usize,
+    ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: GpuCurve,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: 'static,
+    {
+        let chunks = self.chunks(n);
+        if chunks.is_empty() {
+            return Ok(<G as CurveAffine>::Projective::zero());
+        }
+
+        let core_count = self.core_count;
+        let params = |n| calc_params::<<G::Engine as ScalarEngine>::Fr>(n, core_count);
+        let backend = &mut self.backend;
+        let resident = &self.resident;
+        let resident_buffer =
+            |chunk: usize| keys[chunk].and_then(|key| resident.peek::<Buffer<B, G>>(&key));
+
+        // The first chunk is the largest one, it needs the largest buffers
+        let len = chunks[0].len();
+        let (digits, _) = params(len);
+        let slots = std::cmp::min(self.scheduler.slots(), chunks.len());
 
         // Each group will have `num_windows` threads and as there are `num_groups` groups, there will
         // be `num_groups` * `num_windows` threads in total.
-        // Each thread will use `num_groups` * `num_windows` * `bucket_len` buckets.
-
-        let mut base_buffer = self.program.create_buffer::<G>(n)?;
-        base_buffer.write_from(0, bases)?;
-        let mut exp_buffer = self
-            .program
-            .create_buffer::<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>(n)?;
-        exp_buffer.write_from(0, exps)?;
-
-        let bucket_buffer = self
-            .program
-            .create_buffer::<<G as CurveAffine>::Projective>(2 * self.core_count * bucket_len)?;
-        let result_buffer = self
-            .program
-            .create_buffer::<<G as CurveAffine>::Projective>(2 * self.core_count)?;
-
-        // Make global work size divisible by `LOCAL_WORK_SIZE`
-        let mut global_work_size = num_windows * num_groups;
-        global_work_size +=
-            (LOCAL_WORK_SIZE - (global_work_size % LOCAL_WORK_SIZE)) % LOCAL_WORK_SIZE;
-
-        let kernel = self.program.create_kernel(
-            if TypeId::of::<G>() == TypeId::of::<E::G1Affine>() {
-                "G1_bellman_multiexp"
-            } else if TypeId::of::<G>() == TypeId::of::<E::G2Affine>() {
-                "G2_bellman_multiexp"
-            } else {
-                return Err(GPUError::Simple("Only E::G1 and E::G2 are supported!"));
-            },
-            global_work_size,
-            None,
-        );
-
-        call_kernel!(
-            kernel,
-            &base_buffer,
-            &bucket_buffer,
-            &result_buffer,
-            &exp_buffer,
-            n as u32,
-            num_groups as u32,
-            num_windows as u32,
-            window_size as u32
+        // Each thread will use `digits.num_buckets()` buckets.
+        let mut bucket_buffer = BufferBackend::<<G as CurveAffine>::Projective>::create_buffer(
+            backend,
+            2 * core_count * digits.num_buckets(),
         )?;
+        // Bases buffers are only allocated for the chunks that aren't resident
+        let mut base_buffers = (0..slots).map(|_| None).collect::<Vec<_>>();
+        let mut exp_buffers = Vec::with_capacity(slots);
+        let mut result_buffers = Vec::with_capacity(slots);
+        for _ in 0..slots {
+            exp_buffers.push(BufferBackend::<Exponent<G>>::create_buffer(backend, len)?);
+            result_buffers.push(
+                BufferBackend::<<G as CurveAffine>::Projective>::create_buffer(
+                    backend,
+                    2 * core_count,
+                )?,
+            );
+        }
 
-        let mut results = vec![<G as CurveAffine>::Projective::zero(); num_groups * num_windows];
-        result_buffer.read_into(0, &mut results)?;
-
-        // Using the algorithm below, we can calculate the final result by accumulating the results
-        // of those `NUM_GROUPS` * `NUM_WINDOWS` threads.
         let mut acc = <G as CurveAffine>::Projective::zero();
-        let mut bits = 0;
-        for i in 0..num_windows {
-            let w = std::cmp::min(window_size, exp_bits - bits);
-            for _ in 0..w {
-                acc.double();
-            }
-            for g in 0..num_groups {
-                acc.add_assign(&results[g * num_windows + i]);
+        for step in self.scheduler.schedule(chunks.len()) {
+            match step {
+                Step::Upload { chunk, slot } => {
+                    if locks::PriorityLock::should_break(self.priority) {
+                        return Err(GPUError::GPUTaken);
+                    }
+
+                    let range = chunks[chunk].clone();
+                    if resident_buffer(chunk).is_none() {
+                        if base_buffers[slot].is_none() {
+                            base_buffers[slot] =
+                                Some(BufferBackend::<G>::create_buffer(backend, len)?);
+                        }
+                        let base_buffer = base_buffers[slot].as_mut().unwrap();
+                        backend.write_from(base_buffer, 0, &bases[range.clone()])?;
+                    }
+                    let (digits, _) = params(range.len());
+                    let recoded = exps[range]
+                        .iter()
+                        .map(|exp| digits.recode(exp))
+                        .collect::<Vec<_>>();
+                    backend.write_from(&mut exp_buffers[slot], 0, &recoded)?;
+                }
+                Step::Compute { chunk, slot } => {
+                    let n = chunks[chunk].len();
+                    let (digits, num_groups) = params(n);
+                    let num_windows = digits.num_windows() as usize;
+                    let base_buffer = match resident_buffer(chunk) {
+                        Some(buffer) => buffer,
+                        None => base_buffers[slot].as_ref().unwrap(),
+                    };
+
+                    // Make global work size divisible by `LOCAL_WORK_SIZE`
+                    let mut global_work_size = num_windows * num_groups;
+                    global_work_size +=
+                        (LOCAL_WORK_SIZE - (global_work_size % LOCAL_WORK_SIZE)) % LOCAL_WORK_SIZE;
+
+                    backend.multiexp::<G>(
+                        global_work_size,
+                        base_buffer,
+                        &mut bucket_buffer,
+                        &mut result_buffers[slot],
+                        &exp_buffers[slot],
+                        n as u32,
+                        num_groups as u32,
+                        num_windows as u32,
+                        digits.window_size(),
+                    )?;
+                }
+                Step::Read { chunk, slot } => {
+                    let (digits, num_groups) = params(chunks[chunk].len());
+                    let num_windows = digits.num_windows() as usize;
+                    let mut results =
+                        vec![<G as CurveAffine>::Projective::zero(); num_groups * num_windows];
+                    backend.read_into(&result_buffers[slot], 0, &mut results)?;
+
+                    // Using the algorithm below, we can calculate the final result by accumulating the results
+                    // of those `NUM_GROUPS` * `NUM_WINDOWS` threads, from the most significant window.
+                    let mut chunk_acc = <G as CurveAffine>::Projective::zero();
+                    for i in (0..num_windows).rev() {
+                        for _ in 0..digits.window_size() {
+                            chunk_acc.double();
+                        }
+                        for g in 0..num_groups {
+                            chunk_acc.add_assign(&results[g * num_windows + i]);
+                        }
+                    }
+                    acc.add_assign(&chunk_acc);
+                }
             }
-            bits += w; // Process the next window
         }
 
         Ok(acc)
@@ -208,22 +397,35 @@ where
 }
 
 // A struct that containts several multiexp kernels for different devices
-pub struct MultiexpKernel<E>
+pub struct MultiexpKernel<E, B = OpenCLBackend>
 where
     E: Engine,
+    B: ComputeBackend<E>,
 {
-    kernels: Vec<SingleMultiexpKernel<E>>,
-    _lock: locks::GPULock, // RFC 1857: struct fields are dropped in the same order as they are declared.
+    kernels: Vec<SingleMultiexpKernel<E, B>>,
+    _lock: Option<locks::GPULock>, // RFC 1857: struct fields are dropped in the same order as they are declared.
 }
 
 impl<E> MultiexpKernel<E>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
 {
+    /// Runs on the devices selected by `BELLMAN_GPU_DEVICES`
     pub fn create(priority: bool) -> GPUResult<MultiexpKernel<E>> {
+        Self::create_with_selector(priority, &DeviceSelector::from_env()?)
+    }
+
+    /// Runs on the devices selected by `selector`
+    pub fn create_with_selector(
+        priority: bool,
+        selector: &DeviceSelector,
+    ) -> GPUResult<MultiexpKernel<E>> {
         let lock = locks::GPULock::lock();
 
-        let devices = opencl::Device::all()?;
+        let devices = selector.select(opencl::Device::all()?);
 
         let kernels: Vec<_> = devices
             .into_iter()
@@ -240,25 +442,41 @@ where
             })
             .collect();
 
-        if kernels.is_empty() {
-            return Err(GPUError::Simple("No working GPUs found!"));
-        }
+        let mut kernel = Self::with_kernels(kernels)?;
+        kernel._lock = Some(lock);
         info!(
-            "Multiexp: {} working device(s) selected. (CPU utilization: {})",
-            kernels.len(),
+            "Multiexp: {} working device(s) selected. (Selector: {}, CPU utilization: {})",
+            kernel.kernels.len(),
+            selector,
             get_cpu_utilization()
         );
-        for (i, k) in kernels.iter().enumerate() {
+        for (i, k) in kernel.kernels.iter().enumerate() {
             info!(
                 "Multiexp: Device {}: {} (Chunk-size: {})",
                 i,
-                k.program.device().name(),
+                ComputeBackend::<E>::device_name(&k.backend),
                 k.n
             );
         }
-        Ok(MultiexpKernel::<E> {
+        Ok(kernel)
+    }
+}
+
+impl<E, B> MultiexpKernel<E, B>
+where
+    E: Engine,
+    B: ComputeBackend<E>,
+{
+    /// Splits the multiexps between `kernels`, without taking the GPU lock
+    pub fn with_kernels(
+        kernels: Vec<SingleMultiexpKernel<E, B>>,
+    ) -> GPUResult<MultiexpKernel<E, B>> {
+        if kernels.is_empty() {
+            return Err(GPUError::Simple("No working GPUs found!"));
+        }
+        Ok(MultiexpKernel {
             kernels,
-            _lock: lock,
+            _lock: None,
         })
     }
 
@@ -271,10 +489,50 @@ where
         n: usize,
     ) -> GPUResult<<G as CurveAffine>::Projective>
     where
-        G: CurveAffine,
+        G: GpuCurve,
         <G as groupy::CurveAffine>::Engine: crate::bls::Engine,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: Send + 'static,
+    {
+        self.multiexp_with(pool, bases, exps, skip, n, false)
+    }
+
+    /// Like `multiexp`, but the bases stay in device memory, so that later multiexps on the same
+    /// range of `bases` only upload the exponents
+    pub fn multiexp_resident<G>(
+        &mut self,
+        pool: &Worker,
+        bases: Arc<Vec<G>>,
+        exps: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+        skip: usize,
+        n: usize,
+    ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: GpuCurve,
+        <G as groupy::CurveAffine>::Engine: crate::bls::Engine,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: Send + 'static,
+    {
+        self.multiexp_with(pool, bases, exps, skip, n, true)
+    }
+
+    fn multiexp_with<G>(
+        &mut self,
+        pool: &Worker,
+        bases: Arc<Vec<G>>,
+        exps: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+        skip: usize,
+        n: usize,
+        resident: bool,
+    ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: GpuCurve,
+        <G as groupy::CurveAffine>::Engine: crate::bls::Engine,
+        B: BufferBackend<G> + BufferBackend<G::Projective> + BufferBackend<Exponent<G>>,
+        Buffer<B, G>: Send + 'static,
     {
         let num_devices = self.kernels.len();
+        let all_bases = &bases;
         // Bases are skipped by `self.1` elements, when converted from (Arc<Vec<G>>, usize) to Source
         // https://github.com/zkcrypto/bellman/blob/10c5010fd9c2ca69442dc9775ea271e286e776d8/src/multiexp.rs#L38
         let bases = &bases[skip..(skip + n)];
@@ -297,14 +555,15 @@ where
                     .par_chunks(chunk_size)
                     .zip(exps.par_chunks(chunk_size))
                     .zip(self.kernels.par_iter_mut())
-                    .map(|((bases, exps), kern)| -> Result<<G as CurveAffine>::Projective, GPUError> {
-                        let mut acc = <G as CurveAffine>::Projective::zero();
-                        for (bases, exps) in bases.chunks(kern.n).zip(exps.chunks(kern.n)) {
-                            let result = kern.multiexp(bases, exps, bases.len())?;
-                            acc.add_assign(&result);
+                    .enumerate()
+                    .map(|(i, ((bases, exps), kern))| -> Result<<G as CurveAffine>::Projective, GPUError> {
+                        if resident {
+                            // Position of the chunk in `all_bases`
+                            let offset = skip + cpu_n + i * chunk_size;
+                            kern.multiexp_resident(all_bases, offset, exps, bases.len())
+                        } else {
+                            kern.multiexp(bases, exps, bases.len())
                         }
-
-                        Ok(acc)
                     })
                     .collect::<Vec<_>>()
             } else {
@@ -328,3 +587,310 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr, FrRepr, G1Affine, G1Projective};
+    use crate::gpu::{CpuBackend, HostBuffer, Sequential};
+    use ff::Field;
+    use std::collections::HashMap;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Op {
+        // Buffer and number of elements
+        Write(usize, usize),
+        Read(usize, usize),
+        // Buffers and number of bases
+        Kernel([usize; 4], usize),
+    }
+
+    struct SimBuffer<T> {
+        id: usize,
+        host: HostBuffer<T>,
+    }
+
+    // Runs the kernels on the CPU, and records the operations on its buffers to simulate their
+    // timeline on a device
+    struct SimBackend {
+        cpu: CpuBackend,
+        buffers: usize,
+        ops: Vec<Op>,
+    }
+
+    impl SimBackend {
+        fn new() -> Self {
+            SimBackend {
+                cpu: CpuBackend::new("Simulated"),
+                buffers: 0,
+                ops: Vec::new(),
+            }
+        }
+    }
+
+    impl<T: Copy> BufferBackend<T> for SimBackend {
+        type Buffer = SimBuffer<T>;
+
+        fn create_buffer(&mut self, length: usize) -> GPUResult<SimBuffer<T>> {
+            self.buffers += 1;
+            Ok(SimBuffer {
+                id: self.buffers,
+                host: BufferBackend::<T>::create_buffer(&mut self.cpu, length)?,
+            })
+        }
+        fn write_from(
+            &mut self,
+            buffer: &mut SimBuffer<T>,
+            offset: usize,
+            data: &[T],
+        ) -> GPUResult<()> {
+            self.ops.push(Op::Write(buffer.id, data.len()));
+            self.cpu.write_from(&mut buffer.host, offset, data)
+        }
+        fn read_into(
+            &mut self,
+            buffer: &SimBuffer<T>,
+            offset: usize,
+            data: &mut [T],
+        ) -> GPUResult<()> {
+            self.ops.push(Op::Read(buffer.id, data.len()));
+            self.cpu.read_into(&buffer.host, offset, data)
+        }
+    }
+
+    impl ComputeBackend<Bls12> for SimBackend {
+        fn device_name(&self) -> String {
+            ComputeBackend::<Bls12>::device_name(&self.cpu)
+        }
+
+        fn radix_fft(
+            &mut self,
+            _: usize,
+            _: usize,
+            _: &SimBuffer<Fr>,
+            _: &mut SimBuffer<Fr>,
+            _: &SimBuffer<Fr>,
+            _: &SimBuffer<Fr>,
+            _: u32,
+            _: u32,
+            _: u32,
+            _: u32,
+        ) -> GPUResult<()> {
+            Err(GPUError::Simple("not supported by the test backend"))
+        }
+
+        fn multiexp<G>(
+            &mut self,
+            global_work_size: usize,
+            bases: &Buffer<Self, G>,
+            buckets: &mut Buffer<Self, G::Projective>,
+            results: &mut Buffer<Self, G::Projective>,
+            exps: &Buffer<Self, Exponent<G>>,
+            n: u32,
+            num_groups: u32,
+            num_windows: u32,
+            window_size: u32,
+        ) -> GPUResult<()>
+        where
+            G: GpuCurve,
+        {
+            self.ops.push(Op::Kernel(
+                [bases.id, buckets.id, results.id, exps.id],
+                n as usize,
+            ));
+            ComputeBackend::<Bls12>::multiexp::<G>(
+                &mut self.cpu,
+                global_work_size,
+                &bases.host,
+                &mut buckets.host,
+                &mut results.host,
+                &exps.host,
+                n,
+                num_groups,
+                num_windows,
+                window_size,
+            )
+        }
+    }
+
+    // Time `ops` take on a device with a queue for transfers and one for kernels, where operations
+    // wait for the previous ones on the same buffers. Transfers take a unit of time per element,
+    // kernels four per base.
+    fn makespan(ops: &[Op]) -> usize {
+        let mut queues = [0, 0];
+        let mut ready = HashMap::new();
+        for op in ops {
+            let (queue, buffers, cost) = match *op {
+                Op::Write(id, len) | Op::Read(id, len) => (0, vec![id], len),
+                Op::Kernel(ids, n) => (1, ids.to_vec(), 4 * n),
+            };
+            let start = buffers
+                .iter()
+                .map(|id| ready.get(id).copied().unwrap_or(0))
+                .fold(queues[queue], std::cmp::max);
+            queues[queue] = start + cost;
+            for id in buffers {
+                ready.insert(id, start + cost);
+            }
+        }
+        std::cmp::max(queues[0], queues[1])
+    }
+
+    fn kernel(name: &str) -> SingleMultiexpKernel<Bls12, CpuBackend> {
+        SingleMultiexpKernel::with_backend(CpuBackend::new(name), 64, 1 << 30, false).unwrap()
+    }
+
+    fn cpu(bases: &Arc<Vec<G1Affine>>, exps: &Arc<Vec<FrRepr>>, skip: usize) -> G1Projective {
+        cpu_multiexp(
+            &Worker::new(),
+            (bases.clone(), skip),
+            FullDensity,
+            exps.clone(),
+            &mut None,
+        )
+        .wait()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resident_bases() {
+        let rng = &mut rand::thread_rng();
+        let bases = Arc::new(
+            (0..300)
+                .map(|_| G1Projective::random(rng).into_affine())
+                .collect::<Vec<_>>(),
+        );
+        let mut kern = kernel("CPU");
+
+        // Different exponents, the bases are only uploaded once
+        for _ in 0..2 {
+            let exps = Arc::new(
+                (0..200)
+                    .map(|_| Fr::random(rng).into_repr())
+                    .collect::<Vec<_>>(),
+            );
+            let gpu = kern.multiexp_resident(&bases, 50, &exps, 200).unwrap();
+            assert_eq!(gpu, cpu(&bases, &exps, 50));
+            assert_eq!(kern.resident.len(), 1);
+        }
+
+        // Only the least recently used range is evicted when there's no room left
+        let exps = Arc::new(
+            (0..100)
+                .map(|_| Fr::random(rng).into_repr())
+                .collect::<Vec<_>>(),
+        );
+        kern.resident = ResidentBases::new(200 * std::mem::size_of::<G1Affine>());
+        for &offset in &[0, 100, 0, 200] {
+            let gpu = kern.multiexp_resident(&bases, offset, &exps, 100).unwrap();
+            assert_eq!(gpu, cpu(&bases, &exps, offset));
+        }
+        assert_eq!(kern.resident.len(), 2);
+        assert!(kern.resident.contains(&BasesKey::new(&bases, 0, 100)));
+        assert!(!kern.resident.contains(&BasesKey::new(&bases, 100, 100)));
+        assert!(kern.resident.contains(&BasesKey::new(&bases, 200, 100)));
+
+        // Bases that never fit are uploaded every time
+        let exps = Arc::new(exps.repeat(3));
+        let gpu = kern.multiexp_resident(&bases, 0, &exps, 300).unwrap();
+        assert_eq!(gpu, cpu(&bases, &exps, 0));
+        assert_eq!(kern.resident.len(), 2);
+
+        // The buffers are released once the bases are dropped
+        drop(bases);
+        kern.resident.release_dropped();
+        assert!(kern.resident.is_empty());
+    }
+
+    #[test]
+    fn test_resident_chunks() {
+        let rng = &mut rand::thread_rng();
+        let bases = Arc::new(
+            (0..1000)
+                .map(|_| G1Projective::random(rng).into_affine())
+                .collect::<Vec<_>>(),
+        );
+        let exps = Arc::new(
+            (0..900)
+                .map(|_| Fr::random(rng).into_repr())
+                .collect::<Vec<_>>(),
+        );
+
+        // Small chunks, so that each device multiplies several ranges of the bases
+        let kernels = (0..2)
+            .map(|i| {
+                let mut kern = kernel(&format!("CPU {}", i));
+                kern.n = 128;
+                kern
+            })
+            .collect();
+        let mut kern = MultiexpKernel::with_kernels(kernels).unwrap();
+        let pool = Worker::new();
+
+        let expected = cpu(&bases, &exps, 100);
+        for _ in 0..2 {
+            let gpu = kern
+                .multiexp_resident(&pool, bases.clone(), exps.clone(), 100, 900)
+                .unwrap();
+            assert_eq!(gpu, expected);
+        }
+        for k in &kern.kernels {
+            assert_eq!(k.resident.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let rng = &mut rand::thread_rng();
+        let bases = Arc::new(
+            (0..1000)
+                .map(|_| G1Projective::random(rng).into_affine())
+                .collect::<Vec<_>>(),
+        );
+        let exps = Arc::new(
+            (0..1000)
+                .map(|_| Fr::random(rng).into_repr())
+                .collect::<Vec<_>>(),
+        );
+        let expected = cpu(&bases, &exps, 0);
+
+        let schedulers: Vec<Box<dyn Scheduler>> =
+            vec![Box::new(Sequential), Box::new(DoubleBuffered)];
+        let mut makespans = Vec::new();
+        for scheduler in schedulers {
+            let slots = scheduler.slots();
+            let mut kern = SingleMultiexpKernel::<Bls12, _>::with_scheduler(
+                SimBackend::new(),
+                64,
+                1 << 30,
+                false,
+                scheduler,
+            )
+            .unwrap();
+            kern.n = 128;
+
+            assert_eq!(kern.multiexp(&bases, &exps, 1000).unwrap(), expected);
+            // Buckets, and bases, exponents and results for each slot, whatever the number of
+            // chunks
+            assert_eq!(kern.backend.buffers, 1 + 3 * slots);
+            makespans.push(makespan(&kern.backend.ops));
+
+            // Once the bases are resident, only the exponents of the 8 chunks are uploaded
+            for _ in 0..2 {
+                kern.backend.ops.clear();
+                let gpu = kern.multiexp_resident(&bases, 0, &exps, 1000).unwrap();
+                assert_eq!(gpu, expected);
+            }
+            let writes = kern
+                .backend
+                .ops
+                .iter()
+                .filter(|op| matches!(op, Op::Write(..)))
+                .count();
+            assert_eq!(writes, 8);
+        }
+
+        // Uploads overlap the kernels
+        assert!(makespans[1] < makespans[0]);
+    }
+}
@@ -9,9 +9,11 @@ typedef struct {
   FIELD y;
   #ifndef BLSTRS
     bool inf;
-  #endif
-  #if FIELD_LIMB_BITS == 32
-    uint _padding;
+    // The Rust side uses 64-bit limbs, so the point is 8-byte aligned. Two coordinates have a
+    // multiple of 8 bytes with any number of limbs, only the flag needs padding.
+    #if FIELD_LIMB_BITS == 32
+      uint _padding;
+    #endif
   #endif
 } POINT_affine;
 
@@ -95,6 +97,13 @@ POINT_projective POINT_add_mixed(POINT_projective a, POINT_affine b) {
   return ret;
 }
 
+// Subtracting `b` is adding -b = (X, -Y)
+POINT_projective POINT_sub_mixed(POINT_projective a, POINT_affine b) {
+  const FIELD local_zero = FIELD_ZERO;
+  b.y = FIELD_sub(local_zero, b.y);
+  return POINT_add_mixed(a, b);
+}
+
 // http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-0.html#addition-add-2007-bl
 POINT_projective POINT_add(POINT_projective a, POINT_projective b) {
 
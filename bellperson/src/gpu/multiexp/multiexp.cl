@@ -22,8 +22,9 @@ __kernel void POINT_bellman_multiexp(
   const uint gid = get_global_id(0);
   if(gid >= num_windows * num_groups) return;
 
-  // We have (2^window_size - 1) buckets.
-  const uint bucket_len = ((1 << window_size) - 1);
+  // Exponents are recoded to signed digits in [-2^(window_size - 1), 2^(window_size - 1)] (See
+  // `SignedDigits`), so we have 2^(window_size - 1) buckets, one per magnitude.
+  const uint bucket_len = 1 << (window_size - 1);
 
   // Each thread has its own set of buckets in global memory.
   buckets += bucket_len * gid;
@@ -34,26 +35,32 @@ __kernel void POINT_bellman_multiexp(
   const uint len = (uint)ceil(n / (float)num_groups); // Num of elements in each group
 
   // This thread runs the multiexp algorithm on elements from `nstart` to `nened`
-  // on the window [`bits`, `bits` + `w`)
+  // on the window [`bits`, `bits` + `w`), counted from the least significant bit
   const uint nstart = len * (gid / num_windows);
   const uint nend = min(nstart + len, n);
   const uint bits = (gid % num_windows) * window_size;
   const ushort w = min((ushort)window_size, (ushort)(EXPONENT_BITS - bits));
+  // The digits of all windows but the last one are offset by 2^(window_size - 1)
+  const int offset = (gid % num_windows == num_windows - 1) ? 0 : bucket_len;
 
   POINT_projective res = POINT_ZERO;
   for(uint i = nstart; i < nend; i++) {
-    uint ind = EXPONENT_get_bits(exps[i], bits, w);
+    int digit = (int)EXPONENT_get_bits(exps[i], EXPONENT_BITS - bits - w, w) - offset;
 
-    #ifdef NVIDIA
-      // O_o, weird optimization, having a single special case makes it
-      // tremendously faster!
-      // 511 is chosen because it's half of the maximum bucket len, but
-      // any other number works... Bigger indices seems to be better...
-      if(ind == 511) buckets[510] = POINT_add_mixed(buckets[510], bases[i]);
-      else if(ind--) buckets[ind] = POINT_add_mixed(buckets[ind], bases[i]);
-    #else
-      if(ind--) buckets[ind] = POINT_add_mixed(buckets[ind], bases[i]);
-    #endif
+    if(digit > 0) {
+      #ifdef NVIDIA
+        // O_o, weird optimization, having a single special case makes it
+        // tremendously faster!
+        // 512 is chosen because it's half of the maximum bucket len, but
+        // any other number works... Bigger indices seems to be better...
+        if(digit == 512) buckets[511] = POINT_add_mixed(buckets[511], bases[i]);
+        else buckets[digit - 1] = POINT_add_mixed(buckets[digit - 1], bases[i]);
+      #else
+        buckets[digit - 1] = POINT_add_mixed(buckets[digit - 1], bases[i]);
+      #endif
+    } else if(digit < 0) {
+      buckets[-digit - 1] = POINT_sub_mixed(buckets[-digit - 1], bases[i]);
+    }
   }
 
   // Summation by parts
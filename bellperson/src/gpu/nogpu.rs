@@ -1,4 +1,5 @@
 use super::error::{GPUError, GPUResult};
+use super::selector::DeviceSelector;
 use crate::multicore::Worker;
 use ff::{PrimeField, ScalarEngine};
 use groupy::CurveAffine;
@@ -7,6 +8,16 @@ use std::sync::Arc;
 
 // This module is compiled instead of `fft.rs` and `multiexp.rs` if `gpu` feature is disabled.
 
+/// Without the kernels, every field is supported
+pub trait GpuField: PrimeField {}
+
+impl<F: PrimeField> GpuField for F {}
+
+/// Without the kernels, every group is supported
+pub trait GpuCurve: CurveAffine {}
+
+impl<G: CurveAffine> GpuCurve for G {}
+
 pub struct FFTKernel<E>(PhantomData<E>)
 where
     E: ScalarEngine;
@@ -19,9 +30,23 @@ where
         return Err(GPUError::GPUDisabled);
     }
 
+    pub fn create_with_selector(_: bool, _: &DeviceSelector) -> GPUResult<FFTKernel<E>> {
+        return Err(GPUError::GPUDisabled);
+    }
+
     pub fn radix_fft(&mut self, _: &mut [E::Fr], _: &E::Fr, _: u32) -> GPUResult<()> {
         return Err(GPUError::GPUDisabled);
     }
+
+    pub fn radix_fft_many(
+        &mut self,
+        _: &mut [&mut [E::Fr]],
+        _: &[E::Fr],
+        _: &[u32],
+        _: &mut [bool],
+    ) -> GPUResult<()> {
+        return Err(GPUError::GPUDisabled);
+    }
 }
 
 pub struct MultiexpKernel<E>(PhantomData<E>)
@@ -36,6 +61,10 @@ where
         return Err(GPUError::GPUDisabled);
     }
 
+    pub fn create_with_selector(_: bool, _: &DeviceSelector) -> GPUResult<MultiexpKernel<E>> {
+        return Err(GPUError::GPUDisabled);
+    }
+
     pub fn multiexp<G>(
         &mut self,
         _: &Worker,
@@ -49,6 +78,20 @@ where
     {
         return Err(GPUError::GPUDisabled);
     }
+
+    pub fn multiexp_resident<G>(
+        &mut self,
+        _: &Worker,
+        _: Arc<Vec<G>>,
+        _: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+        _: usize,
+        _: usize,
+    ) -> GPUResult<<G as CurveAffine>::Projective>
+    where
+        G: CurveAffine,
+    {
+        return Err(GPUError::GPUDisabled);
+    }
 }
 
 use crate::bls::Engine;
@@ -65,6 +108,10 @@ macro_rules! locked_kernel {
                 $class::<E>(PhantomData)
             }
 
+            pub fn with_selector(_: usize, _: bool, _: DeviceSelector) -> $class<E> {
+                $class::<E>(PhantomData)
+            }
+
             pub fn with<F, R, K>(&mut self, _: F) -> GPUResult<R>
             where
                 F: FnMut(&mut K) -> GPUResult<R>,
@@ -0,0 +1,301 @@
+use super::backend::{Buffer, BufferBackend, ComputeBackend, Exponent};
+use super::cache::ProgramCache;
+use super::error::{GPUError, GPUResult};
+use super::sources::{self, GpuCurve, GpuField};
+use crate::bls::Engine;
+use rust_gpu_tools::*;
+use std::marker::PhantomData;
+use std::mem;
+
+/// Program built for a single device, in a context of its own
+pub struct OpenCLProgram {
+    context: ocl::Context,
+    program: ocl::Program,
+}
+
+impl OpenCLProgram {
+    fn context(device: &opencl::Device) -> GPUResult<ocl::Context> {
+        let platform = match device.device.info(ocl::enums::DeviceInfo::Platform)? {
+            ocl::enums::DeviceInfoResult::Platform(platform) => ocl::Platform::new(platform),
+            _ => return Err(GPUError::Simple("Cannot find the platform of the device!")),
+        };
+        Ok(ocl::Context::builder()
+            .platform(platform)
+            .devices(device.device)
+            .build()?)
+    }
+
+    pub fn from_source(device: &opencl::Device, src: &str) -> GPUResult<OpenCLProgram> {
+        let context = Self::context(device)?;
+        let program = ocl::Program::builder()
+            .src(src)
+            .devices(device.device)
+            .build(&context)?;
+        Ok(OpenCLProgram { context, program })
+    }
+
+    pub fn from_binary(device: &opencl::Device, binary: &[u8]) -> GPUResult<OpenCLProgram> {
+        let context = Self::context(device)?;
+        let program = ocl::Program::builder()
+            .binaries(&[binary])
+            .devices(device.device)
+            .build(&context)?;
+        Ok(OpenCLProgram { context, program })
+    }
+
+    pub fn binary(&self) -> GPUResult<Vec<u8>> {
+        match self.program.info(ocl::enums::ProgramInfo::Binaries)? {
+            ocl::enums::ProgramInfoResult::Binaries(binaries) => Ok(binaries[0].clone()),
+            _ => Err(GPUError::Simple("Cannot get the binary of the program!")),
+        }
+    }
+}
+
+/// Buffer of `T`s in the memory of an OpenCL device
+pub struct OpenCLBuffer<T> {
+    buffer: ocl::Buffer<u8>,
+    // Last upload into the buffer, with the copy of the data it reads from
+    upload: Option<(ocl::Event, Vec<u8>)>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> OpenCLBuffer<T> {
+    // Commands using the buffer wait for its upload
+    fn uploaded(&self) -> Option<&ocl::Event> {
+        self.upload.as_ref().map(|(event, _)| event)
+    }
+}
+
+impl<T> Drop for OpenCLBuffer<T> {
+    // The data of an upload has to outlive it
+    fn drop(&mut self) {
+        if let Some((event, _)) = &self.upload {
+            let _ = event.wait_for();
+        }
+    }
+}
+
+fn events<'a>(events: impl IntoIterator<Item = Option<&'a ocl::Event>>) -> ocl::EventList {
+    events
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// The kernels built with OpenCL for a single device. Uploads are issued on a queue of their own,
+/// so that the next chunk of a multiexp is uploaded while the kernel runs on the current one, and
+/// the kernels wait for the uploads of their buffers.
+pub struct OpenCLBackend {
+    device: opencl::Device,
+    program: OpenCLProgram,
+    // Runs the kernels and the reads
+    queue: ocl::Queue,
+    transfer_queue: ocl::Queue,
+    // Uploads wait for the last kernel, so that they never overwrite a buffer in use
+    last_kernel: Option<ocl::Event>,
+    fft_kernel: String,
+}
+
+impl OpenCLBackend {
+    /// Builds the kernels of engine `E` for `device`, through the cache of `ProgramCache::from_env`
+    pub fn create<E>(device: opencl::Device) -> GPUResult<OpenCLBackend>
+    where
+        E: Engine,
+        E::Fr: GpuField,
+        E::G1Affine: GpuCurve,
+        E::G2Affine: GpuCurve,
+    {
+        Self::create_with_cache::<E>(device, &ProgramCache::from_env())
+    }
+
+    pub fn create_with_cache<E>(
+        device: opencl::Device,
+        cache: &ProgramCache,
+    ) -> GPUResult<OpenCLBackend>
+    where
+        E: Engine,
+        E::Fr: GpuField,
+        E::G1Affine: GpuCurve,
+        E::G2Affine: GpuCurve,
+    {
+        let src = sources::kernel::<E>(device.brand() == opencl::Brand::Nvidia);
+        let program = cache.build(&device, &src)?;
+        let queue = ocl::Queue::new(&program.context, device.device, None)?;
+        let transfer_queue = ocl::Queue::new(&program.context, device.device, None)?;
+        Ok(OpenCLBackend {
+            device,
+            program,
+            queue,
+            transfer_queue,
+            last_kernel: None,
+            fft_kernel: format!("{}_radix_fft", E::Fr::name()),
+        })
+    }
+
+    // Runs `kernel` once the uploads in `uploads` are done
+    fn run(&mut self, kernel: ocl::Kernel, uploads: ocl::EventList) -> GPUResult<()> {
+        let mut event = ocl::Event::empty();
+        unsafe {
+            kernel.cmd().ewait(&uploads).enew(&mut event).enq()?;
+        }
+        self.queue.flush()?;
+        self.last_kernel = Some(event);
+        Ok(())
+    }
+}
+
+impl<T> BufferBackend<T> for OpenCLBackend {
+    type Buffer = OpenCLBuffer<T>;
+
+    fn create_buffer(&mut self, length: usize) -> GPUResult<OpenCLBuffer<T>> {
+        assert!(length > 0);
+        let buffer = ocl::Buffer::<u8>::builder()
+            .queue(self.queue.clone())
+            .flags(ocl::MemFlags::new().read_write())
+            .len(length * mem::size_of::<T>())
+            .build()?;
+        // The memory is only allocated on first use, so that running out of it fails here
+        buffer.write(&[0u8][..]).enq()?;
+        Ok(OpenCLBuffer {
+            buffer,
+            upload: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn write_from(
+        &mut self,
+        buffer: &mut OpenCLBuffer<T>,
+        offset: usize,
+        data: &[T],
+    ) -> GPUResult<()> {
+        if let Some((event, _)) = buffer.upload.take() {
+            event.wait_for()?;
+        }
+        let data = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data))
+        }
+        .to_vec();
+        let mut event = ocl::Event::empty();
+        unsafe {
+            buffer
+                .buffer
+                .write(&data[..])
+                .queue(&self.transfer_queue)
+                .offset(offset * mem::size_of::<T>())
+                .ewait(&events(vec![self.last_kernel.as_ref()]))
+                .enew(&mut event)
+                .block(false)
+                .enq()?;
+        }
+        self.transfer_queue.flush()?;
+        buffer.upload = Some((event, data));
+        Ok(())
+    }
+
+    fn read_into(
+        &mut self,
+        buffer: &OpenCLBuffer<T>,
+        offset: usize,
+        data: &mut [T],
+    ) -> GPUResult<()> {
+        let data = unsafe {
+            std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, mem::size_of_val(data))
+        };
+        buffer
+            .buffer
+            .read(data)
+            .queue(&self.queue)
+            .offset(offset * mem::size_of::<T>())
+            .ewait(&events(vec![buffer.uploaded()]))
+            .enq()?;
+        Ok(())
+    }
+}
+
+impl<E> ComputeBackend<E> for OpenCLBackend
+where
+    E: Engine,
+{
+    fn device_name(&self) -> String {
+        self.device.name()
+    }
+
+    fn radix_fft(
+        &mut self,
+        global_work_size: usize,
+        local_work_size: usize,
+        src: &OpenCLBuffer<E::Fr>,
+        dst: &mut OpenCLBuffer<E::Fr>,
+        pq: &OpenCLBuffer<E::Fr>,
+        omegas: &OpenCLBuffer<E::Fr>,
+        n: u32,
+        log_p: u32,
+        deg: u32,
+        max_deg: u32,
+    ) -> GPUResult<()> {
+        let kernel = ocl::Kernel::builder()
+            .name(&self.fft_kernel)
+            .program(&self.program.program)
+            .queue(self.queue.clone())
+            .global_work_size(global_work_size)
+            .local_work_size(local_work_size)
+            .arg(&src.buffer)
+            .arg(&dst.buffer)
+            .arg(&pq.buffer)
+            .arg(&omegas.buffer)
+            .arg_local::<u8>((1 << deg) * mem::size_of::<E::Fr>())
+            .arg(n)
+            .arg(log_p)
+            .arg(deg)
+            .arg(max_deg)
+            .build()?;
+        let uploads = events(vec![
+            src.uploaded(),
+            dst.uploaded(),
+            pq.uploaded(),
+            omegas.uploaded(),
+        ]);
+        self.run(kernel, uploads)
+    }
+
+    fn multiexp<G>(
+        &mut self,
+        global_work_size: usize,
+        bases: &Buffer<Self, G>,
+        buckets: &mut Buffer<Self, G::Projective>,
+        results: &mut Buffer<Self, G::Projective>,
+        exps: &Buffer<Self, Exponent<G>>,
+        n: u32,
+        num_groups: u32,
+        num_windows: u32,
+        window_size: u32,
+    ) -> GPUResult<()>
+    where
+        G: GpuCurve,
+    {
+        let kernel = ocl::Kernel::builder()
+            .name(format!("{}_bellman_multiexp", G::name()))
+            .program(&self.program.program)
+            .queue(self.queue.clone())
+            .global_work_size(global_work_size)
+            .arg(&bases.buffer)
+            .arg(&buckets.buffer)
+            .arg(&results.buffer)
+            .arg(&exps.buffer)
+            .arg(n)
+            .arg(num_groups)
+            .arg(num_windows)
+            .arg(window_size)
+            .build()?;
+        let uploads = events(vec![
+            bases.uploaded(),
+            buckets.uploaded(),
+            results.uploaded(),
+            exps.uploaded(),
+        ]);
+        self.run(kernel, uploads)
+    }
+}
@@ -0,0 +1,204 @@
+use std::any::{Any, TypeId};
+use std::sync::{Arc, Weak};
+
+/// Identifies the range `offset..offset + len` of a vector of bases. The vector is identified by
+/// the address of its `Arc`, which isn't reused as long as the cache holds a weak reference to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BasesKey {
+    ty: TypeId,
+    ptr: usize,
+    offset: usize,
+    len: usize,
+}
+
+impl BasesKey {
+    pub fn new<G: 'static>(bases: &Arc<Vec<G>>, offset: usize, len: usize) -> BasesKey {
+        BasesKey {
+            ty: TypeId::of::<G>(),
+            ptr: &**bases as *const Vec<G> as usize,
+            offset,
+            len,
+        }
+    }
+}
+
+struct Entry {
+    key: BasesKey,
+    buffer: Box<dyn Any + Send>,
+    bases: Weak<dyn Any + Send + Sync>,
+    size: usize,
+    last_use: u64,
+}
+
+/// Buffers of bases kept in the memory of a device across multiexps, up to `capacity` bytes. The
+/// least recently used ones are evicted first.
+///
+/// The bases aren't kept alive on the host: once their last `Arc` is dropped, their buffer is
+/// released on the next `reserve`. Keeping buffers only pays off if the same `Arc`s are passed
+/// from one multiexp to the next, sources building new vectors every time get no hits.
+pub struct ResidentBases {
+    entries: Vec<Entry>,
+    capacity: usize,
+    used: usize,
+    clock: u64,
+}
+
+impl ResidentBases {
+    pub fn new(capacity: usize) -> ResidentBases {
+        ResidentBases {
+            entries: Vec::new(),
+            capacity,
+            used: 0,
+            clock: 0,
+        }
+    }
+
+    /// Bytes taken by the buffers
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, key: &BasesKey) -> bool {
+        self.entries.iter().any(|e| e.key == *key)
+    }
+
+    /// The buffer of `key`, which becomes the most recently used one
+    pub fn get<T: 'static>(&mut self, key: &BasesKey) -> Option<&T> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.iter_mut().find(|e| e.key == *key)?;
+        entry.last_use = clock;
+        entry.buffer.downcast_ref()
+    }
+
+    /// The buffer of `key`, without making it the most recently used one
+    pub fn peek<T: 'static>(&self, key: &BasesKey) -> Option<&T> {
+        let entry = self.entries.iter().find(|e| e.key == *key)?;
+        entry.buffer.downcast_ref()
+    }
+
+    /// Evicts the least recently used buffer, returns false if there was none
+    pub fn evict(&mut self) -> bool {
+        match (0..self.entries.len()).min_by_key(|&i| self.entries[i].last_use) {
+            Some(i) => {
+                self.used -= self.entries.swap_remove(i).size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Releases the buffers of the bases that were dropped
+    pub fn release_dropped(&mut self) {
+        let used = &mut self.used;
+        self.entries.retain(|e| {
+            let alive = e.bases.strong_count() > 0;
+            if !alive {
+                *used -= e.size;
+            }
+            alive
+        });
+    }
+
+    /// Evicts buffers until `size` bytes fit, the ones of dropped bases first. Returns false if
+    /// they never would fit.
+    pub fn reserve(&mut self, size: usize) -> bool {
+        self.release_dropped();
+        if size > self.capacity {
+            return false;
+        }
+        while self.used + size > self.capacity {
+            self.evict();
+        }
+        true
+    }
+
+    /// Inserts the buffer of `size` bytes holding the range `key` of `bases`. Space must have been
+    /// reserved first.
+    pub fn insert<G, T>(&mut self, key: BasesKey, bases: &Arc<Vec<G>>, buffer: T, size: usize)
+    where
+        G: Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        assert!(self.used + size <= self.capacity);
+        self.clock += 1;
+        self.used += size;
+        self.entries.push(Entry {
+            key,
+            buffer: Box::new(buffer),
+            bases: Arc::downgrade(bases) as Weak<dyn Any + Send + Sync>,
+            size,
+            last_use: self.clock,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        let bases = Arc::new(vec![1u32, 2, 3, 4]);
+        let copy = Arc::new(vec![1u32, 2, 3, 4]);
+        assert_eq!(
+            BasesKey::new(&bases, 1, 2),
+            BasesKey::new(&bases.clone(), 1, 2)
+        );
+        assert_ne!(BasesKey::new(&bases, 1, 2), BasesKey::new(&copy, 1, 2));
+        assert_ne!(BasesKey::new(&bases, 1, 2), BasesKey::new(&bases, 0, 2));
+        assert_ne!(BasesKey::new(&bases, 1, 2), BasesKey::new(&bases, 1, 3));
+    }
+
+    #[test]
+    fn test_lru() {
+        let bases = Arc::new(vec![0u64; 16]);
+        let key = |offset| BasesKey::new(&bases, offset, 4);
+        let mut resident = ResidentBases::new(100);
+
+        for offset in 0..3 {
+            assert!(resident.reserve(40));
+            resident.insert(key(offset), &bases, offset, 40);
+        }
+        // The first buffer was evicted to make room for the third
+        assert_eq!(resident.len(), 2);
+        assert_eq!(resident.used(), 80);
+        assert!(!resident.contains(&key(0)));
+
+        // The second buffer becomes the most recently used one
+        assert_eq!(resident.get::<usize>(&key(1)), Some(&1));
+        assert_eq!(resident.get::<u32>(&key(1)), None);
+        assert_eq!(resident.peek::<usize>(&key(2)), Some(&2));
+        assert!(resident.reserve(40));
+        resident.insert(key(3), &bases, 3, 40);
+        assert!(resident.contains(&key(1)));
+        assert!(!resident.contains(&key(2)));
+
+        assert!(!resident.reserve(101));
+        assert!(resident.reserve(100));
+        assert!(resident.is_empty());
+        assert_eq!(resident.used(), 0);
+        assert!(!resident.evict());
+
+        // The cache doesn't keep the bases alive, the buffers of dropped bases are released first
+        resident.insert(key(0), &bases, 0, 60);
+        assert_eq!(Arc::strong_count(&bases), 1);
+        let dropped = Arc::new(vec![0u64; 16]);
+        let dropped_key = BasesKey::new(&dropped, 0, 4);
+        assert!(resident.reserve(40));
+        resident.insert(dropped_key, &dropped, 1, 40);
+        drop(dropped);
+        assert!(resident.reserve(40));
+        assert!(resident.contains(&key(0)));
+        assert!(!resident.contains(&dropped_key));
+        assert_eq!(resident.used(), 60);
+    }
+}
@@ -0,0 +1,137 @@
+/// Operation on a chunk of a multiexp, using the buffers of `slot` on the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Uploads the bases and the exponents of the chunk
+    Upload { chunk: usize, slot: usize },
+    /// Runs the kernel on the chunk
+    Compute { chunk: usize, slot: usize },
+    /// Reads the results of the chunk back
+    Read { chunk: usize, slot: usize },
+}
+
+/// Decides the order the chunks of a multiexp are processed in, and which set of buffers each
+/// chunk uses. A chunk must be uploaded, computed and read in that order, with the same slot, and
+/// a slot can only be uploaded to once the previous chunk using it is read.
+///
+/// The steps are issued to the device in order. Backends that have a queue for transfers next to
+/// the one for kernels may run an upload while a kernel that doesn't use its slot is running.
+pub trait Scheduler: Send + Sync {
+    /// Number of sets of buffers the steps use
+    fn slots(&self) -> usize;
+
+    /// Steps to process `num_chunks` chunks
+    fn schedule(&self, num_chunks: usize) -> Vec<Step>;
+}
+
+/// Processes the chunks one after the other, with a single set of buffers
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sequential;
+
+impl Scheduler for Sequential {
+    fn slots(&self) -> usize {
+        1
+    }
+
+    fn schedule(&self, num_chunks: usize) -> Vec<Step> {
+        (0..num_chunks)
+            .flat_map(|chunk| {
+                vec![
+                    Step::Upload { chunk, slot: 0 },
+                    Step::Compute { chunk, slot: 0 },
+                    Step::Read { chunk, slot: 0 },
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Uploads the next chunk into a second set of buffers before running the kernel on the current
+/// one, so that the transfer overlaps the computation on backends with a transfer queue, like
+/// `OpenCLBackend`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DoubleBuffered;
+
+impl Scheduler for DoubleBuffered {
+    fn slots(&self) -> usize {
+        2
+    }
+
+    fn schedule(&self, num_chunks: usize) -> Vec<Step> {
+        let mut steps = Vec::with_capacity(3 * num_chunks);
+        if num_chunks > 0 {
+            steps.push(Step::Upload { chunk: 0, slot: 0 });
+        }
+        for chunk in 0..num_chunks {
+            let slot = chunk % 2;
+            if chunk + 1 < num_chunks {
+                steps.push(Step::Upload {
+                    chunk: chunk + 1,
+                    slot: 1 - slot,
+                });
+            }
+            steps.push(Step::Compute { chunk, slot });
+            steps.push(Step::Read { chunk, slot });
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Panics if `steps` don't follow the rules of `Scheduler`
+    fn check(scheduler: &dyn Scheduler, num_chunks: usize) {
+        let steps = scheduler.schedule(num_chunks);
+        assert_eq!(steps.len(), 3 * num_chunks);
+
+        // Stage reached by each chunk, and chunk using each slot
+        let mut stages = vec![0; num_chunks];
+        let mut slots = vec![None; scheduler.slots()];
+        for step in steps {
+            match step {
+                Step::Upload { chunk, slot } => {
+                    assert_eq!(stages[chunk], 0);
+                    assert_eq!(slots[slot], None);
+                    slots[slot] = Some(chunk);
+                    stages[chunk] = 1;
+                }
+                Step::Compute { chunk, slot } => {
+                    assert_eq!(stages[chunk], 1);
+                    assert_eq!(slots[slot], Some(chunk));
+                    stages[chunk] = 2;
+                }
+                Step::Read { chunk, slot } => {
+                    assert_eq!(stages[chunk], 2);
+                    assert_eq!(slots[slot], Some(chunk));
+                    slots[slot] = None;
+                    stages[chunk] = 3;
+                }
+            }
+        }
+        assert!(stages.iter().all(|&s| s == 3));
+    }
+
+    #[test]
+    fn test_schedules() {
+        for num_chunks in 0..10 {
+            check(&Sequential, num_chunks);
+            check(&DoubleBuffered, num_chunks);
+        }
+
+        assert_eq!(
+            DoubleBuffered.schedule(3),
+            vec![
+                Step::Upload { chunk: 0, slot: 0 },
+                Step::Upload { chunk: 1, slot: 1 },
+                Step::Compute { chunk: 0, slot: 0 },
+                Step::Read { chunk: 0, slot: 0 },
+                Step::Upload { chunk: 2, slot: 0 },
+                Step::Compute { chunk: 1, slot: 1 },
+                Step::Read { chunk: 1, slot: 1 },
+                Step::Compute { chunk: 2, slot: 0 },
+                Step::Read { chunk: 2, slot: 0 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use super::error::{GPUError, GPUResult};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+const DEVICES_ENV: &str = "BELLMAN_GPU_DEVICES";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Brand {
+    Nvidia,
+    Amd,
+}
+
+/// Properties of a device, on which `DeviceSelector` filters
+pub trait DeviceInfo {
+    fn name(&self) -> String;
+    /// `None` for brands other than `Brand`'s
+    fn brand(&self) -> Option<Brand>;
+    /// PCI bus id, if the driver exposes it
+    fn bus_id(&self) -> Option<u32>;
+    /// Global memory, in bytes
+    fn memory(&self) -> u64;
+}
+
+#[cfg(feature = "gpu")]
+impl DeviceInfo for rust_gpu_tools::opencl::Device {
+    fn name(&self) -> String {
+        rust_gpu_tools::opencl::Device::name(self)
+    }
+    fn brand(&self) -> Option<Brand> {
+        let brand = rust_gpu_tools::opencl::Device::brand(self);
+        if brand == rust_gpu_tools::opencl::Brand::Nvidia {
+            Some(Brand::Nvidia)
+        } else if brand == rust_gpu_tools::opencl::Brand::Amd {
+            Some(Brand::Amd)
+        } else {
+            None
+        }
+    }
+    fn bus_id(&self) -> Option<u32> {
+        Some(rust_gpu_tools::opencl::Device::bus_id(self))
+    }
+    fn memory(&self) -> u64 {
+        rust_gpu_tools::opencl::Device::memory(self)
+    }
+}
+
+/// Selects the devices the kernels run on. A device is selected if it matches every kind of
+/// criterion that is set, and any of the values given for a kind, e.g. devices 0 or 2 that are
+/// made by NVIDIA:
+///
+/// ```ignore
+/// let selector = DeviceSelector::all()
+///     .index(0)
+///     .index(2)
+///     .brand(Brand::Nvidia);
+/// let kernel = MultiexpKernel::<Bls12>::create_with_selector(false, &selector)?;
+/// ```
+///
+/// The same selector is parsed from `index:0,index:2,brand:nvidia` (See `from_env`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceSelector {
+    indices: Vec<usize>,
+    names: Vec<String>,
+    bus_ids: Vec<u32>,
+    brands: Vec<Brand>,
+    min_memory: Option<u64>,
+}
+
+impl DeviceSelector {
+    /// Selects every device
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Device at position `index` of the device list (As printed by `dump_device_list`)
+    pub fn index(mut self, index: usize) -> Self {
+        self.indices.push(index);
+        self
+    }
+
+    /// Devices whose name contains `name`, ignoring case
+    pub fn name(mut self, name: &str) -> Self {
+        self.names.push(name.to_string());
+        self
+    }
+
+    /// Device on PCI bus `bus_id`
+    pub fn bus_id(mut self, bus_id: u32) -> Self {
+        self.bus_ids.push(bus_id);
+        self
+    }
+
+    pub fn brand(mut self, brand: Brand) -> Self {
+        self.brands.push(brand);
+        self
+    }
+
+    /// Devices with at least `bytes` of memory
+    pub fn min_memory(mut self, bytes: u64) -> Self {
+        self.min_memory = Some(bytes);
+        self
+    }
+
+    /// Parses `BELLMAN_GPU_DEVICES`, a comma-separated list of `kind:value` criteria, where the
+    /// kinds are `index`, `name`, `bus_id`, `brand` (`nvidia` or `amd`) and `min_memory` (In
+    /// bytes, or with a `K`/`M`/`G` suffix). Every device is selected if it's not set.
+    pub fn from_env() -> GPUResult<Self> {
+        match env::var(DEVICES_ENV) {
+            Ok(var) => var.parse(),
+            Err(_) => Ok(Self::all()),
+        }
+    }
+
+    fn matches<D: DeviceInfo>(&self, index: usize, device: &D) -> bool {
+        let name = device.name().to_lowercase();
+        (self.indices.is_empty() || self.indices.contains(&index))
+            && (self.names.is_empty()
+                || self.names.iter().any(|n| name.contains(&n.to_lowercase())))
+            && (self.bus_ids.is_empty()
+                || device
+                    .bus_id()
+                    .map_or(false, |id| self.bus_ids.contains(&id)))
+            && (self.brands.is_empty()
+                || device
+                    .brand()
+                    .map_or(false, |brand| self.brands.contains(&brand)))
+            && self.min_memory.map_or(true, |min| device.memory() >= min)
+    }
+
+    /// The selected devices, in the order of `devices`
+    pub fn select<D: DeviceInfo>(&self, devices: Vec<D>) -> Vec<D> {
+        devices
+            .into_iter()
+            .enumerate()
+            .filter(|(i, d)| self.matches(*i, d))
+            .map(|(_, d)| d)
+            .collect()
+    }
+}
+
+fn parse_memory(value: &str) -> Option<u64> {
+    let (digits, shift) = match value.chars().last()?.to_ascii_uppercase() {
+        'K' => (&value[..value.len() - 1], 10),
+        'M' => (&value[..value.len() - 1], 20),
+        'G' => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+    digits.trim().parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+impl FromStr for DeviceSelector {
+    type Err = GPUError;
+
+    fn from_str(s: &str) -> GPUResult<Self> {
+        let invalid = |criterion: &str| GPUError::InvalidDeviceSelector(criterion.to_string());
+        let mut selector = Self::all();
+        for criterion in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let mut splitted = criterion.splitn(2, ':');
+            let kind = splitted.next().unwrap_or_default().trim();
+            let value = splitted.next().ok_or_else(|| invalid(criterion))?.trim();
+            selector = match kind {
+                "index" => selector.index(value.parse().map_err(|_| invalid(criterion))?),
+                "name" if !value.is_empty() => selector.name(value),
+                "bus_id" => selector.bus_id(value.parse().map_err(|_| invalid(criterion))?),
+                "brand" => match value.to_lowercase().as_str() {
+                    "nvidia" => selector.brand(Brand::Nvidia),
+                    "amd" => selector.brand(Brand::Amd),
+                    _ => return Err(invalid(criterion)),
+                },
+                "min_memory" => {
+                    selector.min_memory(parse_memory(value).ok_or_else(|| invalid(criterion))?)
+                }
+                _ => return Err(invalid(criterion)),
+            };
+        }
+        Ok(selector)
+    }
+}
+
+/// Same format as `from_env`, "all" if nothing is filtered
+impl fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut criteria = Vec::new();
+        criteria.extend(self.indices.iter().map(|i| format!("index:{}", i)));
+        criteria.extend(self.names.iter().map(|n| format!("name:{}", n)));
+        criteria.extend(self.bus_ids.iter().map(|id| format!("bus_id:{}", id)));
+        criteria.extend(self.brands.iter().map(|b| match b {
+            Brand::Nvidia => "brand:nvidia".to_string(),
+            Brand::Amd => "brand:amd".to_string(),
+        }));
+        criteria.extend(self.min_memory.iter().map(|m| format!("min_memory:{}", m)));
+        if criteria.is_empty() {
+            write!(f, "all")
+        } else {
+            write!(f, "{}", criteria.join(","))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockDevice {
+        name: &'static str,
+        brand: Option<Brand>,
+        bus_id: Option<u32>,
+        memory: u64,
+    }
+
+    impl DeviceInfo for MockDevice {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+        fn brand(&self) -> Option<Brand> {
+            self.brand
+        }
+        fn bus_id(&self) -> Option<u32> {
+            self.bus_id
+        }
+        fn memory(&self) -> u64 {
+            self.memory
+        }
+    }
+
+    fn devices() -> Vec<MockDevice> {
+        vec![
+            MockDevice {
+                name: "GeForce RTX 2080 Ti",
+                brand: Some(Brand::Nvidia),
+                bus_id: Some(1),
+                memory: 11 << 30,
+            },
+            MockDevice {
+                name: "gfx906",
+                brand: Some(Brand::Amd),
+                bus_id: None,
+                memory: 16 << 30,
+            },
+            MockDevice {
+                name: "GeForce GTX 1060",
+                brand: Some(Brand::Nvidia),
+                bus_id: Some(3),
+                memory: 6 << 30,
+            },
+            MockDevice {
+                name: "Unknown accelerator",
+                brand: None,
+                bus_id: Some(4),
+                memory: 32 << 30,
+            },
+        ]
+    }
+
+    fn selected(selector: &DeviceSelector) -> Vec<&'static str> {
+        selector
+            .select(devices())
+            .into_iter()
+            .map(|d| d.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(selected(&DeviceSelector::all()).len(), 4);
+        assert_eq!(
+            selected(&DeviceSelector::all().index(2).index(0)),
+            vec!["GeForce RTX 2080 Ti", "GeForce GTX 1060"]
+        );
+        assert_eq!(
+            selected(&DeviceSelector::all().name("geforce")),
+            vec!["GeForce RTX 2080 Ti", "GeForce GTX 1060"]
+        );
+        assert_eq!(
+            selected(&DeviceSelector::all().bus_id(3).bus_id(2)),
+            vec!["GeForce GTX 1060"]
+        );
+        assert_eq!(
+            selected(&DeviceSelector::all().brand(Brand::Amd)),
+            vec!["gfx906"]
+        );
+        assert_eq!(
+            selected(&DeviceSelector::all().min_memory(12 << 30)),
+            vec!["gfx906", "Unknown accelerator"]
+        );
+        assert_eq!(
+            selected(
+                &DeviceSelector::all()
+                    .brand(Brand::Nvidia)
+                    .min_memory(8 << 30)
+            ),
+            vec!["GeForce RTX 2080 Ti"]
+        );
+        assert!(selected(&DeviceSelector::all().index(1).brand(Brand::Nvidia)).is_empty());
+        assert!(selected(&DeviceSelector::all().index(7)).is_empty());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("".parse::<DeviceSelector>().unwrap(), DeviceSelector::all());
+        let selector = DeviceSelector::all()
+            .index(0)
+            .index(2)
+            .name("RTX 2080")
+            .bus_id(3)
+            .brand(Brand::Nvidia)
+            .min_memory(8 << 30);
+        let parsed = "index:0, index:2,name:RTX 2080,bus_id:3,brand:NVIDIA,min_memory:8G"
+            .parse::<DeviceSelector>()
+            .unwrap();
+        assert_eq!(parsed, selector);
+        assert_eq!(
+            selector.to_string().parse::<DeviceSelector>().unwrap(),
+            selector
+        );
+        assert_eq!(DeviceSelector::all().to_string(), "all");
+        assert_eq!(
+            "min_memory:512m".parse::<DeviceSelector>().unwrap(),
+            DeviceSelector::all().min_memory(512 << 20)
+        );
+
+        for invalid in &[
+            "index",
+            "index:first",
+            "name:",
+            "bus_id:-1",
+            "brand:intel",
+            "min_memory:8T",
+            "memory:8G",
+        ] {
+            match invalid.parse::<DeviceSelector>() {
+                Err(GPUError::InvalidDeviceSelector(criterion)) => {
+                    assert_eq!(criterion, *invalid)
+                }
+                res => panic!("`{}` was parsed as {:?}", invalid, res),
+            }
+        }
+    }
+}
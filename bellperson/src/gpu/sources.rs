@@ -1,21 +1,16 @@
-use crate::bls::Engine;
+use crate::bls::{Engine, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ff::{Field, PrimeField};
 use ff_cl_gen as ffgen;
+use groupy::CurveAffine;
 
 // Instead of having a very large OpenCL program written for a specific curve, with a lot of
 // rudandant codes (As OpenCL doesn't have generic types or templates), this module will dynamically
 // generate OpenCL codes given different PrimeFields and curves.
 
 static FFT_SRC: &str = include_str!("fft/fft.cl");
-static FIELD2_SRC: &str = include_str!("multiexp/field2.cl");
 static EC_SRC: &str = include_str!("multiexp/ec.cl");
 static MULTIEXP_SRC: &str = include_str!("multiexp/multiexp.cl");
 
-fn field2(field2: &str, field: &str) -> String {
-    String::from(FIELD2_SRC)
-        .replace("FIELD2", field2)
-        .replace("FIELD", field)
-}
-
 fn fft(field: &str) -> String {
     String::from(FFT_SRC).replace("FIELD", field)
 }
@@ -38,28 +33,228 @@ fn multiexp(point: &str, exp: &str) -> String {
         .replace("EXPONENT", exp)
 }
 
-// WARNING: This function works only with Short Weierstrass Jacobian curves with Fq2 extension field.
+fn field<F: ff::PrimeField>(name: &str, limb64: bool) -> ffgen::GeneratedField {
+    ffgen::FieldGenerator::<F>::new(name)
+        .limb_size(if limb64 {
+            ffgen::LimbSize::Limb64
+        } else {
+            ffgen::LimbSize::Limb32
+        })
+        .debug(false)
+        .common_helpers(ffgen::CommonHelpers::Omitted)
+        .generate()
+}
+
+/// Source of a program, made of named parts. A part is only included once, so that the fields
+/// shared by several groups can be added by each of them.
+pub struct KernelSource {
+    limb64: bool,
+    names: Vec<String>,
+    parts: Vec<String>,
+}
+
+impl KernelSource {
+    pub fn new(limb64: bool) -> KernelSource {
+        KernelSource {
+            limb64,
+            names: Vec::new(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Whether the fields are generated with 64-bit limbs
+    pub fn limb64(&self) -> bool {
+        self.limb64
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+
+    /// Appends the part `name`, generated by `source`, unless it was added before
+    pub fn add<S: FnOnce() -> String>(&mut self, name: &str, source: S) {
+        if !self.contains(name) {
+            self.names.push(name.to_string());
+            self.parts.push(source());
+        }
+    }
+
+    /// Appends the arithmetic of the prime field `F`, with prefix `name`
+    pub fn add_field<F: PrimeField>(&mut self, name: &str) {
+        if !self.contains(name) {
+            let ffgen::GeneratedField { common, source, .. } = field::<F>(name, self.limb64);
+            self.add("common", || common);
+            self.add(name, || source);
+        }
+    }
+
+    /// Appends the extension `name` = `base[u] / (u^2 - non_residue)` of the prime field `F`,
+    /// after `base`
+    pub fn add_quadratic_extension<F: PrimeField>(
+        &mut self,
+        name: &str,
+        base: &str,
+        non_residue: F,
+    ) {
+        self.add_field::<F>(base);
+        let limb64 = self.limb64;
+        self.add(name, || {
+            ffgen::quadratic_extension(name, &field::<F>(base, limb64), &[non_residue]).source
+        });
+    }
+
+    /// Appends `FIELD_radix_fft`, where `FIELD` is `F::name()`
+    pub fn add_fft<F: GpuField>(&mut self) {
+        F::source(self);
+        self.add(&format!("{}_fft", F::name()), || fft(F::name()));
+    }
+
+    /// Appends the operations on the short Weierstrass points `point`, with coordinates in the
+    /// field `field`, and `POINT_bellman_multiexp` with exponents of `exp`. The fields have to be
+    /// added before.
+    pub fn add_curve(&mut self, point: &str, field: &str, exp: &str) {
+        self.add(point, || {
+            [ec(field, point), multiexp(point, exp)].join("\n\n")
+        });
+    }
+
+    pub fn build(self) -> String {
+        self.parts.join("\n\n")
+    }
+}
+
+/// A prime field whose arithmetic runs on the GPU, like the scalars of the FFT kernel
+pub trait GpuField: PrimeField {
+    /// Prefix of the functions of the field in the program, e.g. `Fr` of `Fr_mul`
+    fn name() -> &'static str;
+
+    /// Adds the arithmetic of the field to `src`
+    fn source(src: &mut KernelSource);
+}
+
+/// A group whose points the multiexp kernel runs on
+pub trait GpuCurve: CurveAffine {
+    /// Prefix of the point operations and of the multiexp kernel in the program, e.g. `G1` of
+    /// `G1_bellman_multiexp`
+    fn name() -> &'static str;
+
+    /// Adds the point operations and the multiexp kernel of the group to `src`, after the fields
+    /// they use
+    fn source(src: &mut KernelSource);
+}
+
+impl GpuField for Fr {
+    fn name() -> &'static str {
+        "Fr"
+    }
+
+    fn source(src: &mut KernelSource) {
+        src.add_field::<Fr>("Fr");
+    }
+}
+
+impl GpuField for Fq {
+    fn name() -> &'static str {
+        "Fq"
+    }
+
+    fn source(src: &mut KernelSource) {
+        src.add_field::<Fq>("Fq");
+    }
+}
+
+impl GpuCurve for G1Affine {
+    fn name() -> &'static str {
+        "G1"
+    }
+
+    fn source(src: &mut KernelSource) {
+        Fr::source(src);
+        Fq::source(src);
+        src.add_curve("G1", "Fq", "Fr");
+    }
+}
+
+// Fq2 = Fq[u] / (u^2 - non_residue), so the non-residue is the square of `u`
+#[cfg(feature = "pairing")]
+fn fq2_non_residue() -> Fq {
+    let mut u = Fq2 {
+        c0: Fq::zero(),
+        c1: Fq::one(),
+    };
+    u.square();
+    u.c0
+}
+
+#[cfg(feature = "blst")]
+fn fq2_non_residue() -> Fq {
+    let mut u = Fq2::new(Fq::zero(), Fq::one());
+    u.square();
+    u.c0()
+}
+
+impl GpuCurve for G2Affine {
+    fn name() -> &'static str {
+        "G2"
+    }
+
+    fn source(src: &mut KernelSource) {
+        Fr::source(src);
+        src.add_quadratic_extension::<Fq>("Fq2", "Fq", fq2_non_residue());
+        src.add_curve("G2", "Fq2", "Fr");
+    }
+}
+
+/// Program with the FFT kernel of `E::Fr` and the multiexp kernels of `E::G1` and `E::G2`
 pub fn kernel<E>(limb64: bool) -> String
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
 {
-    vec![
-        if limb64 {
-            ffgen::field::<E::Fr, ffgen::Limb64>("Fr")
-        } else {
-            ffgen::field::<E::Fr, ffgen::Limb32>("Fr")
-        },
-        fft("Fr"),
-        if limb64 {
-            ffgen::field::<E::Fq, ffgen::Limb64>("Fq")
-        } else {
-            ffgen::field::<E::Fq, ffgen::Limb32>("Fq")
-        },
-        ec("Fq", "G1"),
-        multiexp("G1", "Fr"),
-        field2("Fq2", "Fq"),
-        ec("Fq2", "G2"),
-        multiexp("G2", "Fr"),
-    ]
-    .join("\n\n")
+    let mut src = KernelSource::new(limb64);
+    src.add_fft::<E::Fr>();
+    E::G1Affine::source(&mut src);
+    E::G2Affine::source(&mut src);
+    src.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::Bls12;
+
+    #[test]
+    fn test_kernel_source() {
+        let mut src = KernelSource::new(false);
+        src.add("a", || "A".to_string());
+        src.add("b", || "B".to_string());
+        src.add("a", || panic!("Parts are only generated once"));
+        assert!(src.contains("b"));
+        assert_eq!(src.build(), "A\n\nB");
+
+        // Both groups use Fq, which is only included once
+        let src = kernel::<Bls12>(false);
+        for def in &[
+            "} Fr;",
+            "} Fq;",
+            "} Fq2;",
+            "} G1_affine;",
+            "} G2_affine;",
+            "void Fr_radix_fft(",
+            "void G1_bellman_multiexp(",
+            "void G2_bellman_multiexp(",
+        ] {
+            assert_eq!(src.matches(def).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_fq2_non_residue() {
+        // Fq2 = Fq[u] / (u^2 + 1)
+        let mut minus_one = Fq::one();
+        minus_one.negate();
+        assert_eq!(fq2_non_residue(), minus_one);
+    }
 }
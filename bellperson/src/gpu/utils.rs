@@ -74,8 +74,9 @@ pub fn get_core_count(d: &opencl::Device) -> usize {
 }
 
 pub fn dump_device_list() {
-    for d in opencl::Device::all().unwrap() {
-        info!("Device: {:?}", d);
+    // Indices are the ones of `DeviceSelector::index`
+    for (i, d) in opencl::Device::all().unwrap().iter().enumerate() {
+        info!("Device {}: {:?}", i, d);
     }
 }
 
@@ -1,6 +1,7 @@
 use super::{create_proof_batch_priority, create_random_proof_batch_priority};
 use super::{ParameterSource, Proof};
 use crate::bls::Engine;
+use crate::gpu::{GpuCurve, GpuField};
 use crate::{Circuit, SynthesisError};
 use rand_core::RngCore;
 
@@ -12,6 +13,9 @@ pub fn create_proof<E, C, P: ParameterSource<E>>(
 ) -> Result<Proof<E>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     let proofs =
@@ -26,6 +30,9 @@ pub fn create_random_proof<E, C, R, P: ParameterSource<E>>(
 ) -> Result<Proof<E>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
     R: RngCore,
 {
@@ -42,6 +49,9 @@ pub fn create_proof_batch<E, C, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     create_proof_batch_priority::<E, C, P>(circuits, params, r, s, false)
@@ -54,6 +64,9 @@ pub fn create_random_proof_batch<E, C, R, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
     R: RngCore,
 {
@@ -68,6 +81,9 @@ pub fn create_proof_in_priority<E, C, P: ParameterSource<E>>(
 ) -> Result<Proof<E>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     let proofs =
@@ -82,6 +98,9 @@ pub fn create_random_proof_in_priority<E, C, R, P: ParameterSource<E>>(
 ) -> Result<Proof<E>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
     R: RngCore,
 {
@@ -98,6 +117,9 @@ pub fn create_proof_batch_in_priority<E, C, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     create_proof_batch_priority::<E, C, P>(circuits, params, r, s, true)
@@ -110,6 +132,9 @@ pub fn create_random_proof_batch_in_priority<E, C, R, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
     R: RngCore,
 {
@@ -1,7 +1,7 @@
 use crate::bls::Engine;
 use groupy::{CurveAffine, EncodedPoint};
 
-use crate::multiexp::SourceBuilder;
+use crate::multiexp::{Resident, SourceBuilder};
 use crate::SynthesisError;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -443,3 +443,53 @@ impl<'a, E: Engine> ParameterSource<E> for &'a Parameters<E> {
         Ok(((self.b_g2.clone(), 0), (self.b_g2.clone(), num_inputs)))
     }
 }
+
+/// Parameters whose query vectors are kept in GPU memory, keyed by their address and range, when
+/// proving with the `gpu` feature. Only worth it when the source hands out the same long-lived
+/// `Arc`s from one proof to the next, like `Parameters`. The vectors `MappedParameters` reads for
+/// each proof are dropped after it, and their device memory is released.
+pub struct ResidentParameters<P>(pub P);
+
+impl<E: Engine, P: ParameterSource<E>> ParameterSource<E> for ResidentParameters<P> {
+    type G1Builder = Resident<P::G1Builder>;
+    type G2Builder = Resident<P::G2Builder>;
+
+    fn get_vk(&self, num_ic: usize) -> Result<&VerifyingKey<E>, SynthesisError> {
+        self.0.get_vk(num_ic)
+    }
+
+    fn get_h(&self, num_h: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok(Resident(self.0.get_h(num_h)?))
+    }
+
+    fn get_l(&self, num_l: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok(Resident(self.0.get_l(num_l)?))
+    }
+
+    fn get_a(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let (inputs, aux) = self.0.get_a(num_inputs, num_aux)?;
+        Ok((Resident(inputs), Resident(aux)))
+    }
+
+    fn get_b_g1(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let (inputs, aux) = self.0.get_b_g1(num_inputs, num_aux)?;
+        Ok((Resident(inputs), Resident(aux)))
+    }
+
+    fn get_b_g2(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        let (inputs, aux) = self.0.get_b_g2(num_inputs, num_aux)?;
+        Ok((Resident(inputs), Resident(aux)))
+    }
+}
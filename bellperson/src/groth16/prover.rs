@@ -9,7 +9,7 @@ use rayon::prelude::*;
 
 use super::{ParameterSource, Proof};
 use crate::domain::{EvaluationDomain, Scalar};
-use crate::gpu::{LockedFFTKernel, LockedMultiexpKernel};
+use crate::gpu::{GpuCurve, GpuField, LockedFFTKernel, LockedMultiexpKernel};
 use crate::multicore::{Worker, THREAD_POOL};
 use crate::multiexp::{multiexp, DensityTracker, FullDensity};
 use crate::{
@@ -251,6 +251,9 @@ pub fn create_random_proof_batch_priority<E, C, R, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
     R: RngCore,
 {
@@ -269,6 +272,9 @@ pub fn create_proof_batch_priority<E, C, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     info!("Bellperson {} is being used!", BELLMAN_VERSION);
@@ -285,6 +291,9 @@ fn create_proof_batch_priority_inner<E, C, P: ParameterSource<E>>(
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
+    E::Fr: GpuField,
+    E::G1Affine: GpuCurve,
+    E::G2Affine: GpuCurve,
     C: Circuit<E> + Send,
 {
     let mut provers = circuits
@@ -346,12 +355,14 @@ where
             let mut c =
                 EvaluationDomain::from_coeffs(std::mem::replace(&mut prover.c, Vec::new()))?;
 
-            a.ifft(&worker, &mut fft_kern)?;
-            a.coset_fft(&worker, &mut fft_kern)?;
-            b.ifft(&worker, &mut fft_kern)?;
-            b.coset_fft(&worker, &mut fft_kern)?;
-            c.ifft(&worker, &mut fft_kern)?;
-            c.coset_fft(&worker, &mut fft_kern)?;
+            // The FFTs of `a`, `b` and `c` are independent, they run on different GPUs if there
+            // are several
+            EvaluationDomain::ifft_many(&mut [&mut a, &mut b, &mut c], &worker, &mut fft_kern)?;
+            EvaluationDomain::coset_fft_many(
+                &mut [&mut a, &mut b, &mut c],
+                &worker,
+                &mut fft_kern,
+            )?;
 
             a.mul_assign(&worker, &b);
             drop(b);
@@ -456,3 +456,28 @@ impl PairingCurveAffine for Fr {
         self.mul(*other)
     }
 }
+
+// The groups of the dummy engine are its scalar field, whose elements the kernels would treat as
+// curve points. The program builds, but only the CPU code gives meaningful results.
+#[cfg(feature = "gpu")]
+impl crate::gpu::GpuField for Fr {
+    fn name() -> &'static str {
+        "Fr"
+    }
+
+    fn source(src: &mut crate::gpu::KernelSource) {
+        src.add_field::<Fr>("Fr");
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl crate::gpu::GpuCurve for Fr {
+    fn name() -> &'static str {
+        "G"
+    }
+
+    fn source(src: &mut crate::gpu::KernelSource) {
+        <Fr as crate::gpu::GpuField>::source(src);
+        src.add_curve("G", "Fr", "Fr");
+    }
+}
@@ -17,6 +17,11 @@ pub trait SourceBuilder<G: CurveAffine>: Send + Sync + 'static + Clone {
 
     fn new(self) -> Self::Source;
     fn get(self) -> (Arc<Vec<G>>, usize);
+
+    /// Whether the bases may be kept in device memory, for the next multiexps on the same bases.
+    fn resident(&self) -> bool {
+        false
+    }
 }
 
 /// A source of bases, like an iterator.
@@ -27,6 +32,18 @@ pub trait Source<G: CurveAffine> {
         to: &mut <G as CurveAffine>::Projective,
     ) -> Result<(), SynthesisError>;
 
+    /// Parses the element from the source and subtracts it. Fails if the point is at infinity.
+    fn sub_assign_mixed(
+        &mut self,
+        to: &mut <G as CurveAffine>::Projective,
+    ) -> Result<(), SynthesisError> {
+        let mut point = <G as CurveAffine>::Projective::zero();
+        self.add_assign_mixed(&mut point)?;
+        point.negate();
+        to.add_assign(&point);
+        Ok(())
+    }
+
     /// Skips `amt` elements from the source, avoiding deserialization.
     fn skip(&mut self, amt: usize) -> Result<(), SynthesisError>;
 }
@@ -43,6 +60,28 @@ impl<G: CurveAffine> SourceBuilder<G> for (Arc<Vec<G>>, usize) {
     }
 }
 
+/// Builds the same source as `S`, but lets the GPU keep its bases in device memory. The bases stay
+/// there as long as the kernel lives, so that the multiexps of a batch of proofs only upload the
+/// exponents.
+#[derive(Clone)]
+pub struct Resident<S>(pub S);
+
+impl<G: CurveAffine, S: SourceBuilder<G>> SourceBuilder<G> for Resident<S> {
+    type Source = S::Source;
+
+    fn new(self) -> S::Source {
+        self.0.new()
+    }
+
+    fn get(self) -> (Arc<Vec<G>>, usize) {
+        self.0.get()
+    }
+
+    fn resident(&self) -> bool {
+        true
+    }
+}
+
 impl<G: CurveAffine> Source<G> for (Arc<Vec<G>>, usize) {
     fn add_assign_mixed(
         &mut self,
@@ -67,6 +106,31 @@ impl<G: CurveAffine> Source<G> for (Arc<Vec<G>>, usize) {
         Ok(())
     }
 
+    fn sub_assign_mixed(
+        &mut self,
+        to: &mut <G as CurveAffine>::Projective,
+    ) -> Result<(), SynthesisError> {
+        if self.0.len() <= self.1 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "expected more bases from source",
+            )
+            .into());
+        }
+
+        if self.0[self.1].is_zero() {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+
+        let mut base = self.0[self.1];
+        base.negate();
+        to.add_assign_mixed(&base);
+
+        self.1 += 1;
+
+        Ok(())
+    }
+
     fn skip(&mut self, amt: usize) -> Result<(), SynthesisError> {
         if self.0.len() <= self.1 {
             return Err(io::Error::new(
@@ -192,6 +256,74 @@ impl DensityTracker {
     }
 }
 
+/// Signed-digit recoding of exponents, for bucket multiexps with windows of `window_size` bits.
+///
+/// Window `k` covers the bits `[k * window_size, (k + 1) * window_size)`, the windows cover one bit
+/// more than the field for the carry of the last digit. Exponents are offset by
+/// `2^(window_size - 1)` in every window but the last, so that window `k` of a recoded exponent,
+/// minus that offset, is its `k`-th digit, in `[-2^(window_size - 1), 2^(window_size - 1)]`. As
+/// negating a point is cheap, the buckets are only needed for the magnitudes of the digits, half
+/// as many as with unsigned digits. The GPU kernels use the same recoding.
+#[derive(Clone, Debug)]
+pub struct SignedDigits<F: PrimeField> {
+    window_size: u32,
+    num_windows: u32,
+    offset: F::Repr,
+}
+
+impl<F: PrimeField> SignedDigits<F> {
+    pub fn new(window_size: u32) -> SignedDigits<F> {
+        assert!(window_size > 0 && window_size < 64);
+        // Recoded exponents take one more bit than the field
+        assert!((F::NUM_BITS as usize) < F::Repr::default().as_ref().len() * 64);
+
+        let num_windows = (F::NUM_BITS + window_size) / window_size;
+        let mut offset = F::Repr::default();
+        for k in 0..num_windows - 1 {
+            let mut half = F::Repr::from(1);
+            half.shl(k * window_size + window_size - 1);
+            offset.add_nocarry(&half);
+        }
+
+        SignedDigits {
+            window_size,
+            num_windows,
+            offset,
+        }
+    }
+
+    pub fn window_size(&self) -> u32 {
+        self.window_size
+    }
+
+    pub fn num_windows(&self) -> u32 {
+        self.num_windows
+    }
+
+    /// Buckets needed by a window, one per magnitude of the digits
+    pub fn num_buckets(&self) -> usize {
+        1 << (self.window_size - 1)
+    }
+
+    pub fn recode(&self, exp: &F::Repr) -> F::Repr {
+        let mut recoded = *exp;
+        recoded.add_nocarry(&self.offset);
+        recoded
+    }
+
+    /// Digit `window` of `recoded`, an exponent recoded by `recode`
+    pub fn digit(&self, recoded: &F::Repr, window: u32) -> i64 {
+        let mut bits = *recoded;
+        bits.shr(window * self.window_size);
+        let bits = (bits.as_ref()[0] % (1 << self.window_size)) as i64;
+        if window + 1 < self.num_windows {
+            bits - self.num_buckets() as i64
+        } else {
+            bits
+        }
+    }
+}
+
 fn multiexp_inner<Q, D, G, S>(
     bases: S,
     density_map: D,
@@ -204,12 +336,14 @@ where
     G: CurveAffine,
     S: SourceBuilder<G>,
 {
+    let digits = SignedDigits::<<G::Engine as ScalarEngine>::Fr>::new(c);
+
     // Perform this region of the multiexp
-    let this = move |bases: S,
-                     density_map: D,
-                     exponents: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
-                     skip: u32|
-          -> Result<_, SynthesisError> {
+    let this = |bases: S,
+                density_map: D,
+                exponents: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+                window: u32|
+     -> Result<_, SynthesisError> {
         // Accumulate the result
         let mut acc = G::Projective::zero();
 
@@ -217,13 +351,13 @@ where
         let mut bases = bases.new();
 
         // Create space for the buckets
-        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
+        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); digits.num_buckets()];
 
         let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
         let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
 
         // only the first round uses this
-        let handle_trivial = skip == 0;
+        let handle_trivial = window == 0;
 
         // Sort the bases into buckets
         for (&exp, density) in exponents.iter().zip(density_map.as_ref().iter()) {
@@ -237,12 +371,13 @@ where
                         bases.skip(1)?;
                     }
                 } else {
-                    let mut exp = exp;
-                    exp.shr(skip);
-                    let exp = exp.as_ref()[0] % (1 << c);
+                    // Recoded in every window, instead of keeping a recoded copy of the exponents
+                    let digit = digits.digit(&digits.recode(&exp), window);
 
-                    if exp != 0 {
-                        bases.add_assign_mixed(&mut buckets[(exp - 1) as usize])?;
+                    if digit > 0 {
+                        bases.add_assign_mixed(&mut buckets[(digit - 1) as usize])?;
+                    } else if digit < 0 {
+                        bases.sub_assign_mixed(&mut buckets[(-digit - 1) as usize])?;
                     } else {
                         bases.skip(1)?;
                     }
@@ -263,10 +398,16 @@ where
         Ok(acc)
     };
 
-    let parts = (0..<G::Engine as ScalarEngine>::Fr::NUM_BITS)
+    let parts = (0..digits.num_windows())
         .into_par_iter()
-        .step_by(c as usize)
-        .map(|skip| this(bases.clone(), density_map.clone(), exponents.clone(), skip))
+        .map(|window| {
+            this(
+                bases.clone(),
+                density_map.clone(),
+                exponents.clone(),
+                window,
+            )
+        })
         .collect::<Vec<Result<_, _>>>();
 
     parts
@@ -294,7 +435,7 @@ pub fn multiexp<Q, D, G, S>(
 where
     for<'a> &'a Q: QueryDensity,
     D: Send + Sync + 'static + Clone + AsRef<Q>,
-    G: CurveAffine,
+    G: gpu::GpuCurve,
     G::Engine: crate::bls::Engine,
     S: SourceBuilder<G>,
 {
@@ -309,8 +450,13 @@ where
                 }
             }
 
+            let resident = bases.resident();
             let (bss, skip) = bases.clone().get();
-            k.multiexp(pool, bss, Arc::new(exps.clone()), skip, n)
+            if resident {
+                k.multiexp_resident(pool, bss, Arc::new(exps.clone()), skip, n)
+            } else {
+                k.multiexp(pool, bss, Arc::new(exps.clone()), skip, n)
+            }
         }) {
             return Waiter::done(Ok(p));
         }
@@ -393,11 +539,22 @@ fn test_with_bls12() {
     assert_eq!(naive, fast);
 }
 
-pub fn create_multiexp_kernel<E>(_log_d: usize, priority: bool) -> Option<gpu::MultiexpKernel<E>>
+pub fn create_multiexp_kernel<E>(
+    _log_d: usize,
+    priority: bool,
+    selector: Option<&gpu::DeviceSelector>,
+) -> Option<gpu::MultiexpKernel<E>>
 where
     E: crate::bls::Engine,
+    E::Fr: gpu::GpuField,
+    E::G1Affine: gpu::GpuCurve,
+    E::G2Affine: gpu::GpuCurve,
 {
-    match gpu::MultiexpKernel::<E>::create(priority) {
+    let kernel = match selector {
+        Some(selector) => gpu::MultiexpKernel::<E>::create_with_selector(priority, selector),
+        None => gpu::MultiexpKernel::<E>::create(priority),
+    };
+    match kernel {
         Ok(k) => {
             info!("GPU Multiexp kernel instantiated!");
             Some(k)
@@ -468,14 +625,109 @@ pub fn gpu_multiexp_consistency() {
     }
 }
 
+#[cfg(feature = "gpu")]
+#[test]
+pub fn cpu_backend_multiexp_consistency() {
+    use crate::bls::{Bls12, Engine};
+
+    // Two devices, with enough cores for a few groups per window
+    let kernels = (0..2)
+        .map(|i| {
+            let backend = gpu::CpuBackend::new(&format!("CPU {}", i));
+            gpu::SingleMultiexpKernel::<Bls12, _>::with_backend(backend, 64, 1 << 30, false)
+                .unwrap()
+        })
+        .collect();
+    let mut kern = gpu::MultiexpKernel::with_kernels(kernels).unwrap();
+    let pool = Worker::new();
+
+    let rng = &mut rand::thread_rng();
+
+    let samples = 1000;
+    let v = Arc::new(
+        (0..samples)
+            .map(|_| <Bls12 as ScalarEngine>::Fr::random(rng).into_repr())
+            .collect::<Vec<_>>(),
+    );
+
+    let g1 = Arc::new(
+        (0..samples)
+            .map(|_| <Bls12 as Engine>::G1::random(rng).into_affine())
+            .collect::<Vec<_>>(),
+    );
+    let gpu = kern
+        .multiexp(&pool, g1.clone(), v.clone(), 0, samples)
+        .unwrap();
+    let cpu = multiexp(&pool, (g1, 0), FullDensity, v.clone(), &mut None)
+        .wait()
+        .unwrap();
+    assert_eq!(cpu, gpu);
+
+    let g2 = Arc::new(
+        (0..samples)
+            .map(|_| <Bls12 as Engine>::G2::random(rng).into_affine())
+            .collect::<Vec<_>>(),
+    );
+    let gpu = kern
+        .multiexp(&pool, g2.clone(), v.clone(), 0, samples)
+        .unwrap();
+    let cpu = multiexp(&pool, (g2, 0), FullDensity, v, &mut None)
+        .wait()
+        .unwrap();
+    assert_eq!(cpu, gpu);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bls::{Fr, FrRepr};
 
     use rand::Rng;
     use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
 
+    #[test]
+    fn test_signed_digits() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let mut max = Fr::zero();
+        max.sub_assign(&Fr::one());
+        let mut exps = vec![Fr::zero(), Fr::one(), max];
+        exps.extend((0..100).map(|_| Fr::random(&mut rng)));
+
+        for window_size in 1..20 {
+            let digits = SignedDigits::<Fr>::new(window_size);
+            let num_windows = digits.num_windows();
+            assert!(num_windows * window_size > Fr::NUM_BITS);
+            assert!((num_windows - 1) * window_size <= Fr::NUM_BITS);
+
+            let mut radix = Fr::one();
+            for _ in 0..window_size {
+                radix.double();
+            }
+            for exp in &exps {
+                let recoded = digits.recode(&exp.into_repr());
+                // Horner's rule, from the last digit
+                let mut acc = Fr::zero();
+                for window in (0..num_windows).rev() {
+                    let digit = digits.digit(&recoded, window);
+                    assert!(digit.abs() as usize <= digits.num_buckets());
+                    assert!(window + 1 < num_windows || digit >= 0);
+
+                    let mut magnitude = Fr::from_repr(FrRepr::from(digit.abs() as u64)).unwrap();
+                    if digit < 0 {
+                        magnitude.negate();
+                    }
+                    acc.mul_assign(&radix);
+                    acc.add_assign(&magnitude);
+                }
+                assert_eq!(acc, *exp);
+            }
+        }
+    }
+
     #[test]
     fn test_extend_density_regular() {
         let mut rng = XorShiftRng::from_seed([
@@ -5,6 +5,17 @@
 #define NVIDIA
 #endif
 
+// Vendor-specific assembly of the helpers is disabled by defining COMMON_NO_ASM
+#if defined(NVIDIA) && !defined(COMMON_NO_ASM)
+#define COMMON_ASM_NVIDIA
+#endif
+
+// Functions are declared with DEVICE, so that the code can also be built as
+// CUDA (Where it's defined as `__device__`)
+#ifndef DEVICE
+#define DEVICE
+#endif
+
 #if defined(__WinterPark__) || defined(__BeaverCreek__) || defined(__Turks__) || \
     defined(__Caicos__) || defined(__Tahiti__) || defined(__Pitcairn__) || \
     defined(__Capeverde__) || defined(__Cayman__) || defined(__Barts__) || \
@@ -16,8 +27,8 @@
 #endif
 
 // Returns a * b + c + d, puts the carry in d
-ulong mac_with_carry_64(ulong a, ulong b, ulong c, ulong *d) {
-  #ifdef NVIDIA
+DEVICE ulong mac_with_carry_64(ulong a, ulong b, ulong c, ulong *d) {
+  #ifdef COMMON_ASM_NVIDIA
     ulong lo, hi;
     asm("mad.lo.cc.u64 %0, %2, %3, %4;\r\n"
         "madc.hi.u64 %1, %2, %3, 0;\r\n"
@@ -38,8 +49,8 @@ ulong mac_with_carry_64(ulong a, ulong b, ulong c, ulong *d) {
 }
 
 // Returns a + b, puts the carry in d
-ulong add_with_carry_64(ulong a, ulong *b) {
-  #ifdef NVIDIA
+DEVICE ulong add_with_carry_64(ulong a, ulong *b) {
+  #ifdef COMMON_ASM_NVIDIA
     ulong lo, hi;
     asm("add.cc.u64 %0, %2, %3;\r\n"
         "addc.u64 %1, 0, 0;\r\n"
@@ -54,15 +65,15 @@ ulong add_with_carry_64(ulong a, ulong *b) {
 }
 
 // Returns a * b + c + d, puts the carry in d
-uint mac_with_carry_32(uint a, uint b, uint c, uint *d) {
+DEVICE uint mac_with_carry_32(uint a, uint b, uint c, uint *d) {
   ulong res = (ulong)a * b + c + *d;
   *d = res >> 32;
   return res;
 }
 
 // Returns a + b, puts the carry in b
-uint add_with_carry_32(uint a, uint *b) {
-  #ifdef NVIDIA
+DEVICE uint add_with_carry_32(uint a, uint *b) {
+  #ifdef COMMON_ASM_NVIDIA
     uint lo, hi;
     asm("add.cc.u32 %0, %2, %3;\r\n"
         "addc.u32 %1, 0, 0;\r\n"
@@ -0,0 +1,42 @@
+// Maps the OpenCL C builtins used by the generated code to CUDA, so that the
+// same sources can be built with NVRTC/nvcc.
+
+#ifndef CUDA_CU
+#define CUDA_CU
+
+#ifdef __CUDA_ARCH__
+#define NVIDIA
+#endif
+
+#define DEVICE __device__
+#define __kernel extern "C" __global__
+#define __global
+
+// Same as the definitions of glibc (If included), `ulong` needs to be 64-bit
+typedef unsigned char uchar;
+typedef unsigned short ushort;
+typedef unsigned int uint;
+#ifdef _WIN32
+typedef unsigned long long ulong;
+#else
+typedef unsigned long ulong;
+#endif
+
+DEVICE uint get_global_id(uint dim) {
+  switch(dim) {
+    case 0: return blockIdx.x * blockDim.x + threadIdx.x;
+    case 1: return blockIdx.y * blockDim.y + threadIdx.y;
+    case 2: return blockIdx.z * blockDim.z + threadIdx.z;
+  }
+  return 0;
+}
+
+DEVICE uint mad_hi(uint a, uint b, uint c) {
+  return __umulhi(a, b) + c;
+}
+
+DEVICE ulong mad_hi(ulong a, ulong b, ulong c) {
+  return __umul64hi(a, b) + c;
+}
+
+#endif
@@ -0,0 +1,30 @@
+// Stand-ins for the CUDA builtins used by the generated code, so that the
+// output of `field_cuda()` can be built with the system C++ compiler and
+// tested without a GPU.
+
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+
+#define __device__
+#define __global__
+
+struct uint3 { unsigned int x, y, z; };
+static uint3 threadIdx = {0, 0, 0};
+static uint3 blockIdx = {0, 0, 0};
+static const uint3 blockDim = {1, 1, 1};
+
+// Kernels are executed sequentially, one thread at a time.
+#define host_global_id blockIdx.x
+
+unsigned int __umulhi(unsigned int a, unsigned int b) {
+  return (unsigned int)(((uint64_t)a * b) >> 32);
+}
+
+unsigned long long __umul64hi(unsigned long long a, unsigned long long b) {
+  return (unsigned long long)(((unsigned __int128)a * b) >> 64);
+}
+
+template <typename T> T min(T a, T b) { return a < b ? a : b; }
+template <typename T> T max(T a, T b) { return a > b ? a : b; }
@@ -0,0 +1,214 @@
+// Elliptic curve operations (Short Weierstrass Jacobian form)
+// POINT_affine, POINT_scalar and the coefficient `a` (POINT_A_ZERO, POINT_A_MINUS_3 or POINT_A)
+// are defined by the generator.
+
+typedef struct {
+  FIELD x;
+  FIELD y;
+  FIELD z;
+} POINT_projective; // Represents: (x / z^2, y / z^3)
+
+// Affine points without an infinity flag represent the point at infinity as (0, 0)
+DEVICE bool POINT_affine_is_zero(POINT_affine a) {
+  #ifdef POINT_AFFINE_INF
+    return a.inf;
+  #else
+    return FIELD_is_zero(a.x) && FIELD_is_zero(a.y);
+  #endif
+}
+
+DEVICE POINT_projective POINT_from_affine(POINT_affine a) {
+  POINT_projective res = POINT_ZERO;
+  if(POINT_affine_is_zero(a)) return res;
+  res.x = a.x;
+  res.y = a.y;
+  res.z = FIELD_ONE;
+  return res;
+}
+
+DEVICE POINT_affine POINT_to_affine(POINT_projective a) {
+  POINT_affine res = POINT_AFFINE_ZERO;
+  if(FIELD_is_zero(a.z)) return res;
+  const FIELD z_inv = FIELD_inv(a.z);
+  const FIELD z_inv2 = FIELD_sqr(z_inv);
+  res.x = FIELD_mul(a.x, z_inv2);
+  res.y = FIELD_mul(FIELD_mul(a.y, z_inv2), z_inv);
+  #ifdef POINT_AFFINE_INF
+    res.inf = false;
+  #endif
+  return res;
+}
+
+DEVICE POINT_projective POINT_neg(POINT_projective a) {
+  a.y = FIELD_neg(a.y);
+  return a;
+}
+
+DEVICE POINT_affine POINT_neg_affine(POINT_affine a) {
+  if(!POINT_affine_is_zero(a)) a.y = FIELD_neg(a.y);
+  return a;
+}
+
+#if defined(POINT_A_ZERO)
+  // http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-0.html#doubling-dbl-2009-l
+  DEVICE POINT_projective POINT_double(POINT_projective inp) {
+    if(FIELD_is_zero(inp.z)) return inp;
+
+    const FIELD a = FIELD_sqr(inp.x); // A = X1^2
+    const FIELD b = FIELD_sqr(inp.y); // B = Y1^2
+    FIELD c = FIELD_sqr(b); // C = B^2
+
+    // D = 2*((X1+B)2-A-C)
+    FIELD d = FIELD_add(inp.x, b);
+    d = FIELD_sqr(d); d = FIELD_sub(FIELD_sub(d, a), c); d = FIELD_double(d);
+
+    const FIELD e = FIELD_add(FIELD_double(a), a); // E = 3*A
+    const FIELD f = FIELD_sqr(e);
+
+    inp.z = FIELD_mul(inp.y, inp.z); inp.z = FIELD_double(inp.z); // Z3 = 2*Y1*Z1
+    inp.x = FIELD_sub(FIELD_sub(f, d), d); // X3 = F-2*D
+
+    // Y3 = E*(D-X3)-8*C
+    c = FIELD_double(c); c = FIELD_double(c); c = FIELD_double(c);
+    inp.y = FIELD_sub(FIELD_mul(FIELD_sub(d, inp.x), e), c);
+
+    return inp;
+  }
+#elif defined(POINT_A_MINUS_3)
+  // http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-3.html#doubling-dbl-2001-b
+  DEVICE POINT_projective POINT_double(POINT_projective inp) {
+    if(FIELD_is_zero(inp.z)) return inp;
+
+    const FIELD delta = FIELD_sqr(inp.z); // delta = Z1^2
+    const FIELD gamma = FIELD_sqr(inp.y); // gamma = Y1^2
+    FIELD beta = FIELD_mul(inp.x, gamma); // beta = X1*gamma
+
+    // alpha = 3*(X1-delta)*(X1+delta)
+    FIELD alpha = FIELD_mul(FIELD_sub(inp.x, delta), FIELD_add(inp.x, delta));
+    alpha = FIELD_add(FIELD_double(alpha), alpha);
+
+    // Z3 = (Y1+Z1)^2-gamma-delta
+    inp.z = FIELD_sub(FIELD_sub(FIELD_sqr(FIELD_add(inp.y, inp.z)), gamma), delta);
+
+    // X3 = alpha^2-8*beta
+    beta = FIELD_double(FIELD_double(beta));
+    inp.x = FIELD_sub(FIELD_sqr(alpha), FIELD_double(beta));
+
+    // Y3 = alpha*(4*beta-X3)-8*gamma^2
+    FIELD gamma2 = FIELD_sqr(gamma);
+    gamma2 = FIELD_double(FIELD_double(FIELD_double(gamma2)));
+    inp.y = FIELD_sub(FIELD_mul(alpha, FIELD_sub(beta, inp.x)), gamma2);
+
+    return inp;
+  }
+#else
+  // http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#doubling-dbl-2007-bl
+  DEVICE POINT_projective POINT_double(POINT_projective inp) {
+    if(FIELD_is_zero(inp.z)) return inp;
+
+    const FIELD xx = FIELD_sqr(inp.x); // XX = X1^2
+    const FIELD yy = FIELD_sqr(inp.y); // YY = Y1^2
+    const FIELD yyyy = FIELD_sqr(yy); // YYYY = YY^2
+    const FIELD zz = FIELD_sqr(inp.z); // ZZ = Z1^2
+
+    // S = 2*((X1+YY)^2-XX-YYYY)
+    FIELD s = FIELD_sqr(FIELD_add(inp.x, yy));
+    s = FIELD_double(FIELD_sub(FIELD_sub(s, xx), yyyy));
+
+    // M = 3*XX+a*ZZ^2
+    const FIELD a = POINT_A;
+    const FIELD m = FIELD_add(FIELD_add(FIELD_double(xx), xx), FIELD_mul(a, FIELD_sqr(zz)));
+
+    // Z3 = (Y1+Z1)^2-YY-ZZ
+    inp.z = FIELD_sub(FIELD_sub(FIELD_sqr(FIELD_add(inp.y, inp.z)), yy), zz);
+
+    // X3 = T = M^2-2*S
+    inp.x = FIELD_sub(FIELD_sqr(m), FIELD_double(s));
+
+    // Y3 = M*(S-T)-8*YYYY
+    const FIELD yyyy8 = FIELD_double(FIELD_double(FIELD_double(yyyy)));
+    inp.y = FIELD_sub(FIELD_mul(m, FIELD_sub(s, inp.x)), yyyy8);
+
+    return inp;
+  }
+#endif
+
+// http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#addition-madd-2007-bl
+DEVICE POINT_projective POINT_add_mixed(POINT_projective a, POINT_affine b) {
+  if(POINT_affine_is_zero(b)) return a;
+  if(FIELD_is_zero(a.z)) return POINT_from_affine(b);
+
+  const FIELD z1z1 = FIELD_sqr(a.z);
+  const FIELD u2 = FIELD_mul(b.x, z1z1);
+  const FIELD s2 = FIELD_mul(FIELD_mul(b.y, a.z), z1z1);
+
+  if(FIELD_eq(a.x, u2) && FIELD_eq(a.y, s2)) {
+      return POINT_double(a);
+  }
+
+  const FIELD h = FIELD_sub(u2, a.x); // H = U2-X1
+  const FIELD hh = FIELD_sqr(h); // HH = H^2
+  FIELD i = FIELD_double(hh); i = FIELD_double(i); // I = 4*HH
+  FIELD j = FIELD_mul(h, i); // J = H*I
+  FIELD r = FIELD_sub(s2, a.y); r = FIELD_double(r); // r = 2*(S2-Y1)
+  const FIELD v = FIELD_mul(a.x, i);
+
+  POINT_projective ret;
+
+  // X3 = r^2 - J - 2*V
+  ret.x = FIELD_sub(FIELD_sub(FIELD_sqr(r), j), FIELD_double(v));
+
+  // Y3 = r*(V-X3)-2*Y1*J
+  j = FIELD_mul(a.y, j); j = FIELD_double(j);
+  ret.y = FIELD_sub(FIELD_mul(FIELD_sub(v, ret.x), r), j);
+
+  // Z3 = (Z1+H)^2-Z1Z1-HH
+  ret.z = FIELD_add(a.z, h); ret.z = FIELD_sub(FIELD_sub(FIELD_sqr(ret.z), z1z1), hh);
+  return ret;
+}
+
+// http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#addition-add-2007-bl
+DEVICE POINT_projective POINT_add(POINT_projective a, POINT_projective b) {
+  if(FIELD_is_zero(a.z)) return b;
+  if(FIELD_is_zero(b.z)) return a;
+
+  const FIELD z1z1 = FIELD_sqr(a.z); // Z1Z1 = Z1^2
+  const FIELD z2z2 = FIELD_sqr(b.z); // Z2Z2 = Z2^2
+  const FIELD u1 = FIELD_mul(a.x, z2z2); // U1 = X1*Z2Z2
+  const FIELD u2 = FIELD_mul(b.x, z1z1); // U2 = X2*Z1Z1
+  FIELD s1 = FIELD_mul(FIELD_mul(a.y, b.z), z2z2); // S1 = Y1*Z2*Z2Z2
+  const FIELD s2 = FIELD_mul(FIELD_mul(b.y, a.z), z1z1); // S2 = Y2*Z1*Z1Z1
+
+  if(FIELD_eq(u1, u2) && FIELD_eq(s1, s2))
+    return POINT_double(a);
+  else {
+    const FIELD h = FIELD_sub(u2, u1); // H = U2-U1
+    FIELD i = FIELD_double(h); i = FIELD_sqr(i); // I = (2*H)^2
+    const FIELD j = FIELD_mul(h, i); // J = H*I
+    FIELD r = FIELD_sub(s2, s1); r = FIELD_double(r); // r = 2*(S2-S1)
+    const FIELD v = FIELD_mul(u1, i); // V = U1*I
+    a.x = FIELD_sub(FIELD_sub(FIELD_sub(FIELD_sqr(r), j), v), v); // X3 = r^2 - J - 2*V
+
+    // Y3 = r*(V - X3) - 2*S1*J
+    a.y = FIELD_mul(FIELD_sub(v, a.x), r);
+    s1 = FIELD_mul(s1, j); s1 = FIELD_double(s1); // S1 = S1 * J * 2
+    a.y = FIELD_sub(a.y, s1);
+
+    // Z3 = ((Z1+Z2)^2 - Z1Z1 - Z2Z2)*H
+    a.z = FIELD_add(a.z, b.z); a.z = FIELD_sqr(a.z);
+    a.z = FIELD_sub(FIELD_sub(a.z, z1z1), z2z2);
+    a.z = FIELD_mul(a.z, h);
+
+    return a;
+  }
+}
+
+// Scalar multiplication (Double-and-add), `scalar` is in regular form
+DEVICE POINT_projective POINT_mul(POINT_projective base, POINT_scalar scalar) {
+  POINT_projective res = POINT_ZERO;
+  for(int i = POINT_SCALAR_BITS - 1; i >= 0; i--) {
+    res = POINT_double(res);
+    if((scalar.val[i / 32] >> (i % 32)) & 1) res = POINT_add(res, base);
+  }
+  return res;
+}
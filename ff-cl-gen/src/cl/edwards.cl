@@ -0,0 +1,119 @@
+// Elliptic curve operations (Twisted Edwards extended form)
+// POINT_scalar, the coefficient `a` (POINT_A_MINUS_1 or POINT_A) and POINT_D
+// are defined by the generator. The formulas are complete when `a` is a square
+// and `d` is not, so the neutral element and doublings need no special cases.
+
+typedef struct {
+  FIELD x;
+  FIELD y;
+} POINT_affine; // The neutral element is (0, 1)
+
+typedef struct {
+  FIELD x;
+  FIELD y;
+  FIELD t;
+  FIELD z;
+} POINT_projective; // Represents: (x / z, y / z), with t = x * y / z
+
+DEVICE FIELD POINT_mul_by_a(FIELD x) {
+  #ifdef POINT_A_MINUS_1
+    return FIELD_neg(x);
+  #else
+    const FIELD a = POINT_A;
+    return FIELD_mul(a, x);
+  #endif
+}
+
+DEVICE bool POINT_affine_is_zero(POINT_affine a) {
+  const FIELD one = FIELD_ONE;
+  return FIELD_is_zero(a.x) && FIELD_eq(a.y, one);
+}
+
+DEVICE POINT_projective POINT_from_affine(POINT_affine a) {
+  POINT_projective res;
+  res.x = a.x;
+  res.y = a.y;
+  res.t = FIELD_mul(a.x, a.y);
+  res.z = FIELD_ONE;
+  return res;
+}
+
+DEVICE POINT_affine POINT_to_affine(POINT_projective a) {
+  const FIELD z_inv = FIELD_inv(a.z);
+  POINT_affine res;
+  res.x = FIELD_mul(a.x, z_inv);
+  res.y = FIELD_mul(a.y, z_inv);
+  return res;
+}
+
+DEVICE POINT_projective POINT_neg(POINT_projective a) {
+  a.x = FIELD_neg(a.x);
+  a.t = FIELD_neg(a.t);
+  return a;
+}
+
+DEVICE POINT_affine POINT_neg_affine(POINT_affine a) {
+  a.x = FIELD_neg(a.x);
+  return a;
+}
+
+// http://www.hyperelliptic.org/EFD/g1p/auto-twisted-extended.html#doubling-dbl-2008-hwcd
+DEVICE POINT_projective POINT_double(POINT_projective inp) {
+  const FIELD a = FIELD_sqr(inp.x); // A = X1^2
+  const FIELD b = FIELD_sqr(inp.y); // B = Y1^2
+  const FIELD c = FIELD_double(FIELD_sqr(inp.z)); // C = 2*Z1^2
+  const FIELD d = POINT_mul_by_a(a); // D = a*A
+
+  // E = (X1+Y1)^2-A-B
+  const FIELD e = FIELD_sub(FIELD_sub(FIELD_sqr(FIELD_add(inp.x, inp.y)), a), b);
+  const FIELD g = FIELD_add(d, b); // G = D+B
+  const FIELD f = FIELD_sub(g, c); // F = G-C
+  const FIELD h = FIELD_sub(d, b); // H = D-B
+
+  inp.x = FIELD_mul(e, f); // X3 = E*F
+  inp.y = FIELD_mul(g, h); // Y3 = G*H
+  inp.t = FIELD_mul(e, h); // T3 = E*H
+  inp.z = FIELD_mul(f, g); // Z3 = F*G
+  return inp;
+}
+
+// http://www.hyperelliptic.org/EFD/g1p/auto-twisted-extended.html#addition-add-2008-hwcd
+// `d` is the product Z1*Z2, which is Z1 for mixed additions
+DEVICE POINT_projective POINT_add_with_z(POINT_projective a,
+                                         FIELD x2, FIELD y2, FIELD t2, FIELD d) {
+  const FIELD coeff_d = POINT_D;
+  const FIELD aa = FIELD_mul(a.x, x2); // A = X1*X2
+  const FIELD b = FIELD_mul(a.y, y2); // B = Y1*Y2
+  const FIELD c = FIELD_mul(FIELD_mul(a.t, coeff_d), t2); // C = T1*d*T2
+
+  // E = (X1+Y1)*(X2+Y2)-A-B
+  const FIELD e = FIELD_sub(FIELD_sub(FIELD_mul(FIELD_add(a.x, a.y), FIELD_add(x2, y2)), aa), b);
+  const FIELD f = FIELD_sub(d, c); // F = D-C
+  const FIELD g = FIELD_add(d, c); // G = D+C
+  const FIELD h = FIELD_sub(b, POINT_mul_by_a(aa)); // H = B-a*A
+
+  a.x = FIELD_mul(e, f); // X3 = E*F
+  a.y = FIELD_mul(g, h); // Y3 = G*H
+  a.t = FIELD_mul(e, h); // T3 = E*H
+  a.z = FIELD_mul(f, g); // Z3 = F*G
+  return a;
+}
+
+DEVICE POINT_projective POINT_add(POINT_projective a, POINT_projective b) {
+  return POINT_add_with_z(a, b.x, b.y, b.t, FIELD_mul(a.z, b.z));
+}
+
+// http://www.hyperelliptic.org/EFD/g1p/auto-twisted-extended.html#addition-madd-2008-hwcd
+DEVICE POINT_projective POINT_add_mixed(POINT_projective a, POINT_affine b) {
+  return POINT_add_with_z(a, b.x, b.y, FIELD_mul(b.x, b.y), a.z);
+}
+
+// Scalar multiplication (Double-and-add), `scalar` is in regular form
+DEVICE POINT_projective POINT_mul(POINT_projective base, POINT_scalar scalar) {
+  POINT_projective res = POINT_ZERO;
+  for(int i = POINT_SCALAR_BITS - 1; i >= 0; i--) {
+    res = POINT_double(res);
+    if((scalar.val[i / 32] >> (i % 32)) & 1) res = POINT_add(res, base);
+  }
+  return res;
+}
@@ -2,6 +2,11 @@
 // Arbitrary size prime-field arithmetic library (add, sub, mul, pow)
 
 #define FIELD_BITS (FIELD_LIMBS * FIELD_LIMB_BITS)
+
+// Vendor-specific assembly is disabled by defining FIELD_NO_ASM
+#if defined(NVIDIA) && !defined(FIELD_NO_ASM)
+  #define FIELD_ASM_NVIDIA
+#endif
 #if FIELD_LIMB_BITS == 32
   #define FIELD_mac_with_carry mac_with_carry_32
   #define FIELD_add_with_carry add_with_carry_32
@@ -10,130 +15,408 @@
   #define FIELD_add_with_carry add_with_carry_64
 #endif
 
-// Greater than or equal
-bool FIELD_gte(FIELD a, FIELD b) {
-  for(char i = FIELD_LIMBS - 1; i >= 0; i--){
-    if(a.val[i] > b.val[i])
-      return true;
-    if(a.val[i] < b.val[i])
-      return false;
+// With FIELD_CONSTANT_TIME, the arithmetic doesn't branch on (Or index memory with) the values of
+// the elements: conditional subtractions of the modulus are done with FIELD_select, the borrows
+// (Including the ones of the Karatsuba multiplication) are computed without branches, and
+// exponentiations take a fixed number of steps. FIELD_sqrt, FIELD_from_repr and
+// FIELD_batch_inv (Which skips zeros) are still variable-time.
+#ifdef FIELD_CONSTANT_TIME
+  // Branch-free `c ? b : a`
+  DEVICE FIELD FIELD_select(FIELD a, FIELD b, bool c) {
+    const FIELD_limb mask = -(FIELD_limb)c;
+    for(uint i = 0; i < FIELD_LIMBS; i++)
+      a.val[i] ^= (a.val[i] ^ b.val[i]) & mask;
+    return a;
   }
-  return true;
+#endif
+
+// Greater than or equal
+DEVICE bool FIELD_gte(FIELD a, FIELD b) {
+  #ifdef FIELD_CONSTANT_TIME
+    // No borrow out of a - b
+    bool borrow = 0;
+    for(uint i = 0; i < FIELD_LIMBS; i++)
+      borrow = (a.val[i] < b.val[i]) | (borrow & (a.val[i] == b.val[i]));
+    return !borrow;
+  #else
+    for(int i = FIELD_LIMBS - 1; i >= 0; i--){
+      if(a.val[i] > b.val[i])
+        return true;
+      if(a.val[i] < b.val[i])
+        return false;
+    }
+    return true;
+  #endif
 }
 
 // Equals
-bool FIELD_eq(FIELD a, FIELD b) {
-  for(uchar i = 0; i < FIELD_LIMBS; i++)
-    if(a.val[i] != b.val[i])
-      return false;
-  return true;
+DEVICE bool FIELD_eq(FIELD a, FIELD b) {
+  #ifdef FIELD_CONSTANT_TIME
+    FIELD_limb diff = 0;
+    for(uint i = 0; i < FIELD_LIMBS; i++) diff |= a.val[i] ^ b.val[i];
+    return !diff;
+  #else
+    for(uint i = 0; i < FIELD_LIMBS; i++)
+      if(a.val[i] != b.val[i])
+        return false;
+    return true;
+  #endif
+}
+
+// Zero has the same Montgomery and regular form
+DEVICE bool FIELD_is_zero(FIELD a) {
+  #ifdef FIELD_CONSTANT_TIME
+    FIELD_limb bits = 0;
+    for(uint i = 0; i < FIELD_LIMBS; i++) bits |= a.val[i];
+    return !bits;
+  #else
+    for(uint i = 0; i < FIELD_LIMBS; i++)
+      if(a.val[i])
+        return false;
+    return true;
+  #endif
 }
 
 // Normal addition
-#ifdef NVIDIA
+#ifdef FIELD_ASM_NVIDIA
   #define FIELD_add_ FIELD_add_nvidia
   #define FIELD_sub_ FIELD_sub_nvidia
 #else
-  FIELD FIELD_add_(FIELD a, FIELD b) {
+  DEVICE FIELD FIELD_add_(FIELD a, FIELD b) {
     bool carry = 0;
-    for(uchar i = 0; i < FIELD_LIMBS; i++) {
+    for(uint i = 0; i < FIELD_LIMBS; i++) {
       FIELD_limb old = a.val[i];
       a.val[i] += b.val[i] + carry;
-      carry = carry ? old >= a.val[i] : old > a.val[i];
+      #ifdef FIELD_CONSTANT_TIME
+        carry = (old > a.val[i]) | (carry & (old == a.val[i]));
+      #else
+        carry = carry ? old >= a.val[i] : old > a.val[i];
+      #endif
     }
     return a;
   }
-  FIELD FIELD_sub_(FIELD a, FIELD b) {
+  DEVICE FIELD FIELD_sub_(FIELD a, FIELD b) {
     bool borrow = 0;
-    for(uchar i = 0; i < FIELD_LIMBS; i++) {
+    for(uint i = 0; i < FIELD_LIMBS; i++) {
       FIELD_limb old = a.val[i];
       a.val[i] -= b.val[i] + borrow;
-      borrow = borrow ? old <= a.val[i] : old < a.val[i];
+      #ifdef FIELD_CONSTANT_TIME
+        borrow = (old < a.val[i]) | (borrow & (old == a.val[i]));
+      #else
+        borrow = borrow ? old <= a.val[i] : old < a.val[i];
+      #endif
     }
     return a;
   }
 #endif
 
 // Modular subtraction
-FIELD FIELD_sub(FIELD a, FIELD b) {
+DEVICE FIELD FIELD_sub(FIELD a, FIELD b) {
   FIELD res = FIELD_sub_(a, b);
-  if(!FIELD_gte(a, b)) res = FIELD_add_(res, FIELD_P);
+  #ifdef FIELD_CONSTANT_TIME
+    res = FIELD_select(res, FIELD_add_(res, FIELD_P), !FIELD_gte(a, b));
+  #else
+    if(!FIELD_gte(a, b)) res = FIELD_add_(res, FIELD_P);
+  #endif
   return res;
 }
 
 // Modular addition
-FIELD FIELD_add(FIELD a, FIELD b) {
+DEVICE FIELD FIELD_add(FIELD a, FIELD b) {
   FIELD res = FIELD_add_(a, b);
-  if(FIELD_gte(res, FIELD_P)) res = FIELD_sub_(res, FIELD_P);
+  #if defined(FIELD_CONSTANT_TIME) && defined(FIELD_NO_SPARE_BIT)
+    res = FIELD_select(res, FIELD_sub_(res, FIELD_P), !FIELD_gte(res, a) | FIELD_gte(res, FIELD_P));
+  #elif defined(FIELD_CONSTANT_TIME)
+    res = FIELD_select(res, FIELD_sub_(res, FIELD_P), FIELD_gte(res, FIELD_P));
+  #elif defined(FIELD_NO_SPARE_BIT)
+    // The addition overflowed iff res < a
+    if(!FIELD_gte(res, a) || FIELD_gte(res, FIELD_P)) res = FIELD_sub_(res, FIELD_P);
+  #else
+    if(FIELD_gte(res, FIELD_P)) res = FIELD_sub_(res, FIELD_P);
+  #endif
   return res;
 }
 
-// Modular multiplication
-FIELD FIELD_mul(FIELD a, FIELD b) {
-  /* CIOS Montgomery multiplication, inspired from Tolga Acar's thesis:
-   * https://www.microsoft.com/en-us/research/wp-content/uploads/1998/06/97Acar.pdf
-   * Learn more:
-   * https://en.wikipedia.org/wiki/Montgomery_modular_multiplication
-   * https://alicebob.cryptoland.net/understanding-the-montgomery-reduction-algorithm/
-   */
-  FIELD_limb t[FIELD_LIMBS + 2] = {0};
-  for(uchar i = 0; i < FIELD_LIMBS; i++) {
+// Modular negation, negation of zero is zero
+DEVICE FIELD FIELD_neg(FIELD a) {
+  const FIELD zero = FIELD_ZERO;
+  return FIELD_sub(zero, a);
+}
+
+// Montgomery reduction of a double-width (2 * FIELD_LIMBS limbs) number.
+// https://en.wikipedia.org/wiki/Montgomery_modular_multiplication#The_REDC_algorithm
+DEVICE FIELD FIELD_reduce(FIELD_limb *t) {
+  FIELD_limb carry2 = 0;
+  for(uint i = 0; i < FIELD_LIMBS; i++) {
+    const FIELD_limb m = FIELD_INV * t[i];
     FIELD_limb carry = 0;
-    for(uchar j = 0; j < FIELD_LIMBS; j++)
-      t[j] = FIELD_mac_with_carry(a.val[j], b.val[i], t[j], &carry);
-    t[FIELD_LIMBS] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
-    t[FIELD_LIMBS + 1] = carry;
+    FIELD_mac_with_carry(m, FIELD_P.val[0], t[i], &carry);
+    for(uint j = 1; j < FIELD_LIMBS; j++)
+      t[i + j] = FIELD_mac_with_carry(m, FIELD_P.val[j], t[i + j], &carry);
+    t[i + FIELD_LIMBS] = FIELD_add_with_carry(t[i + FIELD_LIMBS], &carry2);
+    t[i + FIELD_LIMBS] = FIELD_add_with_carry(t[i + FIELD_LIMBS], &carry);
+    carry2 += carry;
+  }
 
-    carry = 0;
-    FIELD_limb m = FIELD_INV * t[0];
-    FIELD_mac_with_carry(m, FIELD_P.val[0], t[0], &carry);
-    for(uchar j = 1; j < FIELD_LIMBS; j++)
-      t[j - 1] = FIELD_mac_with_carry(m, FIELD_P.val[j], t[j], &carry);
+  FIELD result;
+  for(uint i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[i + FIELD_LIMBS];
+
+  #if defined(FIELD_CONSTANT_TIME) && defined(FIELD_NO_SPARE_BIT)
+    result = FIELD_select(result, FIELD_sub_(result, FIELD_P), carry2 | FIELD_gte(result, FIELD_P));
+  #elif defined(FIELD_CONSTANT_TIME)
+    result = FIELD_select(result, FIELD_sub_(result, FIELD_P), FIELD_gte(result, FIELD_P));
+  #elif defined(FIELD_NO_SPARE_BIT)
+    if(carry2 || FIELD_gte(result, FIELD_P)) result = FIELD_sub_(result, FIELD_P);
+  #else
+    if(FIELD_gte(result, FIELD_P)) result = FIELD_sub_(result, FIELD_P);
+  #endif
 
-    t[FIELD_LIMBS - 1] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
-    t[FIELD_LIMBS] = t[FIELD_LIMBS + 1] + carry;
+  return result;
+}
+
+// Montgomery multiplication, without the final subtraction (Result is in [0, 2p))
+#if defined(FIELD_KARATSUBA)
+  // Sizes of the lower and the upper half of the limbs
+  #define FIELD_KARATSUBA_LO ((FIELD_LIMBS + 1) / 2)
+  #define FIELD_KARATSUBA_HI (FIELD_LIMBS / 2)
+
+  // Product of the `n`-limb numbers `a` and `b`, stored in the `2 * n` limbs of `t`
+  DEVICE void FIELD_mul_schoolbook(const FIELD_limb *a, const FIELD_limb *b, FIELD_limb *t, uint n) {
+    for(uint i = 0; i < n; i++) t[i] = 0;
+    for(uint i = 0; i < n; i++) {
+      FIELD_limb carry = 0;
+      for(uint j = 0; j < n; j++)
+        t[i + j] = FIELD_mac_with_carry(a[j], b[i], t[i + j], &carry);
+      t[i + n] = carry;
+    }
   }
 
-  FIELD result;
-  for(uchar i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[i];
+  // Adds `c * a` (`c` is 0 or 1) to the `len` limbs of `t`, `a` has `n <= len` limbs.
+  // Returns the carry.
+  DEVICE FIELD_limb FIELD_add_limbs(FIELD_limb *t, const FIELD_limb *a, FIELD_limb c, uint n, uint len) {
+    FIELD_limb carry = 0;
+    for(uint i = 0; i < n; i++) t[i] = FIELD_mac_with_carry(a[i], c, t[i], &carry);
+    for(uint i = n; i < len; i++) t[i] = FIELD_add_with_carry(t[i], &carry);
+    return carry;
+  }
+
+  // Subtracts `a` from the `len` limbs of `t`, `a` has `n <= len` limbs
+  DEVICE void FIELD_sub_limbs(FIELD_limb *t, const FIELD_limb *a, uint n, uint len) {
+    bool borrow = 0;
+    for(uint i = 0; i < len; i++) {
+      const FIELD_limb old = t[i];
+      t[i] -= (i < n ? a[i] : 0) + borrow;
+      #ifdef FIELD_CONSTANT_TIME
+        borrow = (old < t[i]) | (borrow & (old == t[i]));
+      #else
+        borrow = borrow ? old <= t[i] : old < t[i];
+      #endif
+    }
+  }
+
+  /*
+   * Double-width product with a single level of Karatsuba. With a = a_0 + B^LO * a_1,
+   * b = b_0 + B^LO * b_1, z_0 = a_0 * b_0 and z_2 = a_1 * b_1:
+   * a * b = z_0 + B^LO * ((a_0 + a_1) * (b_0 + b_1) - z_0 - z_2) + B^(2 * LO) * z_2
+   * Three half-size products replace the full one, which is cheaper once there are many limbs,
+   * and keeps less of the operands alive at a time.
+   */
+  DEVICE void FIELD_mul_wide(FIELD a, FIELD b, FIELD_limb *t) {
+    const uint lo = FIELD_KARATSUBA_LO;
+    const uint hi = FIELD_KARATSUBA_HI;
+    FIELD_mul_schoolbook(a.val, b.val, t, lo);
+    FIELD_mul_schoolbook(a.val + lo, b.val + lo, t + 2 * lo, hi);
+
+    // (a_0 + a_1) * (b_0 + b_1), where the sums are `s + c * B^LO`
+    FIELD_limb sa[FIELD_KARATSUBA_LO], sb[FIELD_KARATSUBA_LO];
+    for(uint i = 0; i < lo; i++) {
+      sa[i] = a.val[i];
+      sb[i] = b.val[i];
+    }
+    const FIELD_limb ca = FIELD_add_limbs(sa, a.val + lo, 1, hi, lo);
+    const FIELD_limb cb = FIELD_add_limbs(sb, b.val + lo, 1, hi, lo);
+    FIELD_limb m[2 * FIELD_KARATSUBA_LO + 1];
+    FIELD_mul_schoolbook(sa, sb, m, lo);
+    m[2 * lo] = ca & cb;
+    m[2 * lo] += FIELD_add_limbs(m + lo, sb, ca, lo, lo);
+    m[2 * lo] += FIELD_add_limbs(m + lo, sa, cb, lo, lo);
+
+    // The middle term a_0 * b_1 + a_1 * b_0 is smaller than B^(FIELD_LIMBS + hi), so the
+    // limbs of `m` beyond the end of `t` are zero
+    FIELD_sub_limbs(m, t, 2 * lo, 2 * lo + 1);
+    FIELD_sub_limbs(m, t + 2 * lo, 2 * hi, 2 * lo + 1);
+    const uint len = 2 * FIELD_LIMBS - lo;
+    FIELD_add_limbs(t + lo, m, 1, 2 * lo + 1 < len ? 2 * lo + 1 : len, len);
+  }
+
+  DEVICE FIELD FIELD_mul_(FIELD a, FIELD b) {
+    FIELD_limb t[2 * FIELD_LIMBS];
+    FIELD_mul_wide(a, b, t);
+    return FIELD_reduce(t);
+  }
+#elif defined(FIELD_ASM_NVIDIA)
+  #define FIELD_mul_ FIELD_mul_nvidia
+#else
+  DEVICE FIELD FIELD_mul_(FIELD a, FIELD b) {
+    /* CIOS Montgomery multiplication, inspired from Tolga Acar's thesis:
+     * https://www.microsoft.com/en-us/research/wp-content/uploads/1998/06/97Acar.pdf
+     * Learn more:
+     * https://en.wikipedia.org/wiki/Montgomery_modular_multiplication
+     * https://alicebob.cryptoland.net/understanding-the-montgomery-reduction-algorithm/
+     */
+    FIELD_limb t[FIELD_LIMBS + 2] = {0};
+    for(uint i = 0; i < FIELD_LIMBS; i++) {
+      FIELD_limb carry = 0;
+      for(uint j = 0; j < FIELD_LIMBS; j++)
+        t[j] = FIELD_mac_with_carry(a.val[j], b.val[i], t[j], &carry);
+      t[FIELD_LIMBS] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
+      t[FIELD_LIMBS + 1] = carry;
 
-  if(FIELD_gte(result, FIELD_P)) result = FIELD_sub_(result, FIELD_P);
+      carry = 0;
+      FIELD_limb m = FIELD_INV * t[0];
+      FIELD_mac_with_carry(m, FIELD_P.val[0], t[0], &carry);
+      for(uint j = 1; j < FIELD_LIMBS; j++)
+        t[j - 1] = FIELD_mac_with_carry(m, FIELD_P.val[j], t[j], &carry);
 
+      t[FIELD_LIMBS - 1] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
+      t[FIELD_LIMBS] = t[FIELD_LIMBS + 1] + carry;
+    }
+
+    FIELD result;
+    for(uint i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[i];
+    #if defined(FIELD_CONSTANT_TIME) && defined(FIELD_NO_SPARE_BIT)
+      result = FIELD_select(result, FIELD_sub_(result, FIELD_P), t[FIELD_LIMBS]);
+    #elif defined(FIELD_NO_SPARE_BIT)
+      // Result might not fit in FIELD_LIMBS limbs
+      if(t[FIELD_LIMBS]) result = FIELD_sub_(result, FIELD_P);
+    #endif
+    return result;
+  }
+#endif
+
+// Modular multiplication
+DEVICE FIELD FIELD_mul(FIELD a, FIELD b) {
+  FIELD result = FIELD_mul_(a, b);
+  #ifdef FIELD_CONSTANT_TIME
+    result = FIELD_select(result, FIELD_sub_(result, FIELD_P), FIELD_gte(result, FIELD_P));
+  #else
+    if(FIELD_gte(result, FIELD_P)) result = FIELD_sub_(result, FIELD_P);
+  #endif
   return result;
 }
 
+// Double-width square of `a`, stored in `t`. Every cross product a_i * a_j
+// (i < j) is calculated once and then doubled.
+#ifdef FIELD_ASM_NVIDIA
+  #define FIELD_sqr_wide FIELD_sqr_wide_nvidia
+#else
+  DEVICE void FIELD_sqr_wide(FIELD a, FIELD_limb *t) {
+    for(uint i = 0; i < 2 * FIELD_LIMBS; i++) t[i] = 0;
+
+    // Cross products
+    for(uint i = 0; i < FIELD_LIMBS - 1; i++) {
+      FIELD_limb carry = 0;
+      for(uint j = i + 1; j < FIELD_LIMBS; j++)
+        t[i + j] = FIELD_mac_with_carry(a.val[i], a.val[j], t[i + j], &carry);
+      t[i + FIELD_LIMBS] = carry;
+    }
+
+    // Doubling
+    t[2 * FIELD_LIMBS - 1] = t[2 * FIELD_LIMBS - 2] >> (FIELD_LIMB_BITS - 1);
+    for(uint i = 2 * FIELD_LIMBS - 2; i > 1; i--)
+      t[i] = (t[i] << 1) | (t[i - 1] >> (FIELD_LIMB_BITS - 1));
+    t[1] <<= 1;
+
+    // Squares
+    FIELD_limb carry = 0;
+    for(uint i = 0; i < FIELD_LIMBS; i++) {
+      t[2 * i] = FIELD_mac_with_carry(a.val[i], a.val[i], t[2 * i], &carry);
+      t[2 * i + 1] = FIELD_add_with_carry(t[2 * i + 1], &carry);
+    }
+  }
+#endif
+
 // Squaring is a special case of multiplication which can be done ~1.5x faster.
 // https://stackoverflow.com/a/16388571/1348497
-FIELD FIELD_sqr(FIELD a) {
-  return FIELD_mul(a, a);
+DEVICE FIELD FIELD_sqr(FIELD a) {
+  FIELD_limb t[2 * FIELD_LIMBS];
+  FIELD_sqr_wide(a, t);
+  return FIELD_reduce(t);
 }
 
 // Left-shift the limbs by one bit and subtract by modulus in case of overflow.
 // Faster version of FIELD_add(a, a)
-FIELD FIELD_double(FIELD a) {
-  for(uchar i = FIELD_LIMBS - 1; i >= 1; i--)
+DEVICE FIELD FIELD_double(FIELD a) {
+  #ifdef FIELD_NO_SPARE_BIT
+    const bool carry = a.val[FIELD_LIMBS - 1] >> (FIELD_LIMB_BITS - 1);
+  #endif
+  for(uint i = FIELD_LIMBS - 1; i >= 1; i--)
     a.val[i] = (a.val[i] << 1) | (a.val[i - 1] >> (FIELD_LIMB_BITS - 1));
   a.val[0] <<= 1;
-  if(FIELD_gte(a, FIELD_P)) a = FIELD_sub_(a, FIELD_P);
+  #if defined(FIELD_CONSTANT_TIME) && defined(FIELD_NO_SPARE_BIT)
+    a = FIELD_select(a, FIELD_sub_(a, FIELD_P), carry | FIELD_gte(a, FIELD_P));
+  #elif defined(FIELD_CONSTANT_TIME)
+    a = FIELD_select(a, FIELD_sub_(a, FIELD_P), FIELD_gte(a, FIELD_P));
+  #elif defined(FIELD_NO_SPARE_BIT)
+    if(carry || FIELD_gte(a, FIELD_P)) a = FIELD_sub_(a, FIELD_P);
+  #else
+    if(FIELD_gte(a, FIELD_P)) a = FIELD_sub_(a, FIELD_P);
+  #endif
+  return a;
+}
+
+// Modular halving: a / 2 = a >> 1 if `a` is even, (a + p) >> 1 otherwise.
+// Works on the Montgomery form as well, since (a * R) / 2 = (a / 2) * R
+DEVICE FIELD FIELD_half(FIELD a) {
+  FIELD_limb carry = 0;
+  #ifdef FIELD_CONSTANT_TIME
+    const bool odd = a.val[0] & 1;
+    const FIELD sum = FIELD_add_(a, FIELD_P);
+    #ifdef FIELD_NO_SPARE_BIT
+      carry = odd & !FIELD_gte(sum, a);
+    #endif
+    a = FIELD_select(a, sum, odd);
+  #else
+    if(a.val[0] & 1) {
+      const FIELD sum = FIELD_add_(a, FIELD_P);
+      #ifdef FIELD_NO_SPARE_BIT
+        carry = !FIELD_gte(sum, a);
+      #endif
+      a = sum;
+    }
+  #endif
+  for(uint i = 0; i < FIELD_LIMBS - 1; i++)
+    a.val[i] = (a.val[i] >> 1) | (a.val[i + 1] << (FIELD_LIMB_BITS - 1));
+  a.val[FIELD_LIMBS - 1] = (a.val[FIELD_LIMBS - 1] >> 1) | (carry << (FIELD_LIMB_BITS - 1));
   return a;
 }
 
 // Modular exponentiation (Exponentiation by Squaring)
 // https://en.wikipedia.org/wiki/Exponentiation_by_squaring
-FIELD FIELD_pow(FIELD base, uint exponent) {
+DEVICE FIELD FIELD_pow(FIELD base, uint exponent) {
   FIELD res = FIELD_ONE;
-  while(exponent > 0) {
-    if (exponent & 1)
-      res = FIELD_mul(res, base);
-    exponent = exponent >> 1;
-    base = FIELD_sqr(base);
-  }
+  #ifdef FIELD_CONSTANT_TIME
+    // All the 32 bits of the exponent are processed
+    for(uint i = 0; i < 32; i++) {
+      res = FIELD_select(res, FIELD_mul(res, base), (exponent >> i) & 1);
+      base = FIELD_sqr(base);
+    }
+  #else
+    while(exponent > 0) {
+      if (exponent & 1)
+        res = FIELD_mul(res, base);
+      exponent = exponent >> 1;
+      base = FIELD_sqr(base);
+    }
+  #endif
   return res;
 }
 
 
-// Store squares of the base in a lookup table for faster evaluation.
-FIELD FIELD_pow_lookup(__global FIELD *bases, uint exponent) {
+// Store squares of the base in a lookup table for faster evaluation. The exponent isn't
+// secret (e.g. the twiddle factors of FFTs), so this is variable-time even with
+// FIELD_CONSTANT_TIME.
+DEVICE FIELD FIELD_pow_lookup(__global FIELD *bases, uint exponent) {
   FIELD res = FIELD_ONE;
   uint i = 0;
   while(exponent > 0) {
@@ -145,23 +428,196 @@ FIELD FIELD_pow_lookup(__global FIELD *bases, uint exponent) {
   return res;
 }
 
-FIELD FIELD_mont(FIELD a) {
+// Modular exponentiation by a full-size exponent, given in regular form.
+// Processes the exponent in fixed windows of FIELD_POW_WINDOW bits, from the
+// most significant one, with a table of the first 2^FIELD_POW_WINDOW powers.
+#define FIELD_POW_WINDOW 4
+DEVICE FIELD FIELD_pow_field(FIELD base, FIELD exponent) {
+  FIELD table[1 << FIELD_POW_WINDOW];
+  table[0] = FIELD_ONE;
+  for(uint i = 1; i < (1 << FIELD_POW_WINDOW); i++)
+    table[i] = FIELD_mul(table[i - 1], base);
+
+  FIELD res = FIELD_ONE;
+  #ifndef FIELD_CONSTANT_TIME
+    bool started = false; // Squaring is skipped until the first non-zero window
+  #endif
+  for(int i = FIELD_BITS - FIELD_POW_WINDOW; i >= 0; i -= FIELD_POW_WINDOW) {
+    // Windows never cross limbs, as FIELD_POW_WINDOW divides FIELD_LIMB_BITS
+    const uint window = (exponent.val[i / FIELD_LIMB_BITS] >> (i % FIELD_LIMB_BITS)) & ((1 << FIELD_POW_WINDOW) - 1);
+    #ifdef FIELD_CONSTANT_TIME
+      // Every window is a squaring and a multiplication, the entry of the table is selected
+      // by going through all of them
+      for(uint j = 0; j < FIELD_POW_WINDOW; j++) res = FIELD_sqr(res);
+      FIELD power = table[0];
+      for(uint j = 1; j < (1 << FIELD_POW_WINDOW); j++)
+        power = FIELD_select(power, table[j], j == window);
+      res = FIELD_mul(res, power);
+    #else
+      if(started)
+        for(uint j = 0; j < FIELD_POW_WINDOW; j++) res = FIELD_sqr(res);
+      if(window) {
+        res = FIELD_mul(res, table[window]);
+        started = true;
+      }
+    #endif
+  }
+  return res;
+}
+
+// Modular inversion using Fermat's little theorem: a^-1 = a^(p - 2)
+// Inverse of zero is defined to be zero.
+DEVICE FIELD FIELD_inv(FIELD a) {
+  const FIELD exponent = FIELD_P_MINUS_2;
+  return FIELD_pow_field(a, exponent);
+}
+
+// Legendre symbol using Euler's criterion: a^((p - 1) / 2)
+// Returns 0 for zero, 1 for quadratic residues and -1 otherwise.
+DEVICE int FIELD_legendre(FIELD a) {
+  const FIELD exponent = FIELD_P_MINUS_1_OVER_2;
+  const FIELD s = FIELD_pow_field(a, exponent);
+  if(FIELD_eq(s, FIELD_ZERO)) return 0;
+  return FIELD_eq(s, FIELD_ONE) ? 1 : -1;
+}
+
+DEVICE bool FIELD_is_square(FIELD a) {
+  return FIELD_legendre(a) >= 0;
+}
+
+// Square root using Tonelli-Shanks, where p - 1 = 2^S * t
+// https://en.wikipedia.org/wiki/Tonelli%E2%80%93Shanks_algorithm
+// Returns false if `a` is not a square, otherwise stores one of its roots in `res`.
+DEVICE bool FIELD_sqrt(FIELD a, FIELD *res) {
+  if(FIELD_eq(a, FIELD_ZERO)) {
+    *res = a;
+    return true;
+  }
+  const FIELD exponent = FIELD_T_MINUS_1_OVER_2;
+  FIELD w = FIELD_pow_field(a, exponent); // a^((t - 1) / 2)
+  FIELD x = FIELD_mul(a, w); // a^((t + 1) / 2)
+  FIELD b = FIELD_mul(x, w); // a^t
+  FIELD z = FIELD_ROOT_OF_UNITY;
+  uint v = FIELD_S;
+  while(!FIELD_eq(b, FIELD_ONE)) {
+    // Least `k` such that b^(2^k) = 1, it reaches `v` only for non-squares
+    uint k = 0;
+    for(FIELD b2k = b; !FIELD_eq(b2k, FIELD_ONE); k++)
+      b2k = FIELD_sqr(b2k);
+    if(k == v) return false;
+    w = z;
+    for(uint j = 0; j < v - k - 1; j++)
+      w = FIELD_sqr(w);
+    z = FIELD_sqr(w);
+    b = FIELD_mul(b, z);
+    x = FIELD_mul(x, w);
+    v = k;
+  }
+  *res = x;
+  return true;
+}
+
+// Batch inversion using Montgomery's trick. Each thread inverts `chunk_size`
+// consecutive elements in-place, with a single call to FIELD_inv. `tmp` should
+// be as large as `elements`. Zero elements are left untouched.
+__kernel void FIELD_batch_inv(__global FIELD *elements,
+                              __global FIELD *tmp,
+                              uint n,
+                              uint chunk_size) {
+  const uint start = get_global_id(0) * chunk_size;
+  if(start >= n) return;
+  const uint end = min(start + chunk_size, n);
+
+  const FIELD local_zero = FIELD_ZERO;
+  FIELD acc = FIELD_ONE;
+  for(uint i = start; i < end; i++) {
+    tmp[i] = acc;
+    if(!FIELD_eq(elements[i], local_zero))
+      acc = FIELD_mul(acc, elements[i]);
+  }
+
+  acc = FIELD_inv(acc);
+
+  for(uint i = end; i > start; i--) {
+    const FIELD x = elements[i - 1];
+    if(!FIELD_eq(x, local_zero)) {
+      elements[i - 1] = FIELD_mul(acc, tmp[i - 1]);
+      acc = FIELD_mul(acc, x);
+    }
+  }
+}
+
+DEVICE FIELD FIELD_mont(FIELD a) {
   return FIELD_mul(a, FIELD_R2);
 }
 
-FIELD FIELD_unmont(FIELD a) {
+DEVICE FIELD FIELD_unmont(FIELD a) {
   FIELD one = FIELD_ZERO;
   one.val[0] = 1;
   return FIELD_mul(a, one);
 }
 
+DEVICE FIELD FIELD_from_u32(uint v) {
+  FIELD a = FIELD_ZERO;
+  a.val[0] = v;
+  return FIELD_mont(a);
+}
+
+DEVICE FIELD FIELD_from_u64(ulong v) {
+  FIELD a = FIELD_ZERO;
+  #if FIELD_LIMB_BITS == 32
+    a.val[0] = v;
+    a.val[1] = v >> 32;
+  #else
+    a.val[0] = v;
+  #endif
+  return FIELD_mont(a);
+}
+
+// Montgomery form of `repr`, given in regular form. Returns false if `repr`
+// is not smaller than the modulus.
+DEVICE bool FIELD_from_repr(FIELD repr, FIELD *res) {
+  if(FIELD_gte(repr, FIELD_P)) return false;
+  *res = FIELD_mont(repr);
+  return true;
+}
+
+#define FIELD_BYTES (FIELD_BITS / 8)
+typedef struct { uchar val[FIELD_BYTES]; } FIELD_bytes;
+
+// Bytes of the regular form, least significant first
+DEVICE FIELD_bytes FIELD_to_bytes_le(FIELD a) {
+  a = FIELD_unmont(a);
+  FIELD_bytes res;
+  for(uint i = 0; i < FIELD_BYTES; i++)
+    res.val[i] = a.val[i / (FIELD_LIMB_BITS / 8)] >> (8 * (i % (FIELD_LIMB_BITS / 8)));
+  return res;
+}
+
+// Bytes of the regular form, most significant first
+DEVICE FIELD_bytes FIELD_to_bytes_be(FIELD a) {
+  const FIELD_bytes le = FIELD_to_bytes_le(a);
+  FIELD_bytes res;
+  for(uint i = 0; i < FIELD_BYTES; i++)
+    res.val[i] = le.val[FIELD_BYTES - 1 - i];
+  return res;
+}
+
 // Get `i`th bit (From most significant digit) of the field.
-bool FIELD_get_bit(FIELD l, uint i) {
-  return (l.val[FIELD_LIMBS - 1 - i / FIELD_LIMB_BITS] >> (FIELD_LIMB_BITS - 1 - (i % FIELD_LIMB_BITS))) & 1;
+DEVICE bool FIELD_get_bit(FIELD l, uint i) {
+  #ifdef FIELD_CONSTANT_TIME
+    // The limb is selected by going through all of them
+    const uint index = FIELD_LIMBS - 1 - i / FIELD_LIMB_BITS;
+    FIELD_limb limb = 0;
+    for(uint j = 0; j < FIELD_LIMBS; j++) limb |= l.val[j] & -(FIELD_limb)(j == index);
+    return (limb >> (FIELD_LIMB_BITS - 1 - (i % FIELD_LIMB_BITS))) & 1;
+  #else
+    return (l.val[FIELD_LIMBS - 1 - i / FIELD_LIMB_BITS] >> (FIELD_LIMB_BITS - 1 - (i % FIELD_LIMB_BITS))) & 1;
+  #endif
 }
 
 // Get `window` consecutive bits, (Starting from `skip`th bit) from the field.
-uint FIELD_get_bits(FIELD l, uint skip, uint window) {
+DEVICE uint FIELD_get_bits(FIELD l, uint skip, uint window) {
   uint ret = 0;
   for(uint i = 0; i < window; i++) {
     ret <<= 1;
@@ -170,9 +626,11 @@ uint FIELD_get_bits(FIELD l, uint skip, uint window) {
   return ret;
 }
 
-void FIELD_print(FIELD a) {
+#ifdef FIELD_DEBUG
+DEVICE void FIELD_print(FIELD a) {
   printf("0x");
   for (uint i = 0; i < FIELD_LIMBS; i++) {
     printf("%016lx", a.val[FIELD_LIMBS - i - 1]);
   }
 }
+#endif
@@ -0,0 +1,373 @@
+// Arbitrary size prime-field arithmetic library (add, sub, mul, pow), WGSL port of `field.cl`.
+// WGSL has neither 64-bit integers nor mad_hi, so the high half of a product is calculated from
+// 16-bit halves of the operands.
+
+// Double-width numbers, used by the Montgomery reduction
+alias FIELD_wide = array<u32, FIELD_WIDE_LIMBS>;
+
+// Returns the high 32 bits of a * b
+fn FIELD_mul_hi(a: u32, b: u32) -> u32 {
+  let a_lo = a & 0xffffu;
+  let a_hi = a >> 16u;
+  let b_lo = b & 0xffffu;
+  let b_hi = b >> 16u;
+  let lo = a_lo * b_lo;
+  let mid_a = a_hi * b_lo;
+  let mid_b = a_lo * b_hi;
+  // Carry of the middle 32 bits
+  let mid = (lo >> 16u) + (mid_a & 0xffffu) + (mid_b & 0xffffu);
+  return a_hi * b_hi + (mid_a >> 16u) + (mid_b >> 16u) + (mid >> 16u);
+}
+
+// Returns a * b + c + d, puts the carry in d
+fn FIELD_mac_with_carry(a: u32, b: u32, c: u32, d: ptr<function, u32>) -> u32 {
+  var lo = a * b + c;
+  var hi = FIELD_mul_hi(a, b) + select(0u, 1u, lo < c);
+  lo += *d;
+  hi += select(0u, 1u, lo < *d);
+  *d = hi;
+  return lo;
+}
+
+// Returns a + b, puts the carry in b
+fn FIELD_add_with_carry(a: u32, b: ptr<function, u32>) -> u32 {
+  let lo = a + *b;
+  *b = select(0u, 1u, lo < a);
+  return lo;
+}
+
+// Greater than or equal
+fn FIELD_gte(a: FIELD, b: FIELD) -> bool {
+  for(var i = FIELD_LIMBS; i > 0u; i--) {
+    if(a.val[i - 1u] > b.val[i - 1u]) { return true; }
+    if(a.val[i - 1u] < b.val[i - 1u]) { return false; }
+  }
+  return true;
+}
+
+// Equals
+fn FIELD_eq(a: FIELD, b: FIELD) -> bool {
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    if(a.val[i] != b.val[i]) { return false; }
+  }
+  return true;
+}
+
+// Zero has the same Montgomery and regular form
+fn FIELD_is_zero(a: FIELD) -> bool {
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    if(a.val[i] != 0u) { return false; }
+  }
+  return true;
+}
+
+// Normal addition
+fn FIELD_add_(a: FIELD, b: FIELD) -> FIELD {
+  var res = a;
+  var carry = false;
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    let old = res.val[i];
+    res.val[i] = old + b.val[i] + select(0u, 1u, carry);
+    carry = select(old > res.val[i], old >= res.val[i], carry);
+  }
+  return res;
+}
+
+// Normal subtraction
+fn FIELD_sub_(a: FIELD, b: FIELD) -> FIELD {
+  var res = a;
+  var borrow = false;
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    let old = res.val[i];
+    res.val[i] = old - b.val[i] - select(0u, 1u, borrow);
+    borrow = select(old < res.val[i], old <= res.val[i], borrow);
+  }
+  return res;
+}
+
+// Modular subtraction
+fn FIELD_sub(a: FIELD, b: FIELD) -> FIELD {
+  var res = FIELD_sub_(a, b);
+  if(!FIELD_gte(a, b)) { res = FIELD_add_(res, FIELD_P); }
+  return res;
+}
+
+// Modular addition
+fn FIELD_add(a: FIELD, b: FIELD) -> FIELD {
+  var res = FIELD_add_(a, b);
+  // Without a spare bit, the addition overflowed iff res < a
+  if((FIELD_NO_SPARE_BIT && !FIELD_gte(res, a)) || FIELD_gte(res, FIELD_P)) {
+    res = FIELD_sub_(res, FIELD_P);
+  }
+  return res;
+}
+
+// Modular negation, negation of zero is zero
+fn FIELD_neg(a: FIELD) -> FIELD {
+  return FIELD_sub(FIELD_ZERO, a);
+}
+
+// Montgomery reduction of a double-width number.
+// https://en.wikipedia.org/wiki/Montgomery_modular_multiplication (The REDC algorithm)
+fn FIELD_reduce(t: ptr<function, FIELD_wide>) -> FIELD {
+  let p = FIELD_P;
+  var carry2 = 0u;
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    let m = FIELD_INV * (*t)[i];
+    var carry = 0u;
+    FIELD_mac_with_carry(m, p.val[0], (*t)[i], &carry);
+    for(var j = 1u; j < FIELD_LIMBS; j++) {
+      (*t)[i + j] = FIELD_mac_with_carry(m, p.val[j], (*t)[i + j], &carry);
+    }
+    (*t)[i + FIELD_LIMBS] = FIELD_add_with_carry((*t)[i + FIELD_LIMBS], &carry2);
+    (*t)[i + FIELD_LIMBS] = FIELD_add_with_carry((*t)[i + FIELD_LIMBS], &carry);
+    carry2 += carry;
+  }
+
+  var result: FIELD;
+  for(var i = 0u; i < FIELD_LIMBS; i++) { result.val[i] = (*t)[i + FIELD_LIMBS]; }
+
+  if((FIELD_NO_SPARE_BIT && (carry2 != 0u)) || FIELD_gte(result, p)) {
+    result = FIELD_sub_(result, p);
+  }
+  return result;
+}
+
+// Montgomery multiplication, without the final subtraction (Result is in [0, 2p))
+// CIOS Montgomery multiplication, inspired from Tolga Acar's thesis:
+// https://www.microsoft.com/en-us/research/wp-content/uploads/1998/06/97Acar.pdf
+fn FIELD_mul_(a: FIELD, b: FIELD) -> FIELD {
+  let p = FIELD_P;
+  var t: FIELD_wide; // Only the first FIELD_LIMBS + 2 limbs are used
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    var carry = 0u;
+    for(var j = 0u; j < FIELD_LIMBS; j++) {
+      t[j] = FIELD_mac_with_carry(a.val[j], b.val[i], t[j], &carry);
+    }
+    t[FIELD_LIMBS] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
+    t[FIELD_LIMBS + 1u] = carry;
+
+    carry = 0u;
+    let m = FIELD_INV * t[0];
+    FIELD_mac_with_carry(m, p.val[0], t[0], &carry);
+    for(var j = 1u; j < FIELD_LIMBS; j++) {
+      t[j - 1u] = FIELD_mac_with_carry(m, p.val[j], t[j], &carry);
+    }
+
+    t[FIELD_LIMBS - 1u] = FIELD_add_with_carry(t[FIELD_LIMBS], &carry);
+    t[FIELD_LIMBS] = t[FIELD_LIMBS + 1u] + carry;
+  }
+
+  var result: FIELD;
+  for(var i = 0u; i < FIELD_LIMBS; i++) { result.val[i] = t[i]; }
+  // Without a spare bit, the result might not fit in FIELD_LIMBS limbs
+  if(FIELD_NO_SPARE_BIT && (t[FIELD_LIMBS] != 0u)) { result = FIELD_sub_(result, p); }
+  return result;
+}
+
+// Modular multiplication
+fn FIELD_mul(a: FIELD, b: FIELD) -> FIELD {
+  var result = FIELD_mul_(a, b);
+  if(FIELD_gte(result, FIELD_P)) { result = FIELD_sub_(result, FIELD_P); }
+  return result;
+}
+
+// Double-width square of `a`, stored in `t`. Every cross product a_i * a_j
+// (i < j) is calculated once and then doubled.
+fn FIELD_sqr_wide(a: FIELD, t: ptr<function, FIELD_wide>) {
+  for(var i = 0u; i < FIELD_WIDE_LIMBS; i++) { (*t)[i] = 0u; }
+
+  // Cross products
+  for(var i = 0u; i < FIELD_LIMBS - 1u; i++) {
+    var carry = 0u;
+    for(var j = i + 1u; j < FIELD_LIMBS; j++) {
+      (*t)[i + j] = FIELD_mac_with_carry(a.val[i], a.val[j], (*t)[i + j], &carry);
+    }
+    (*t)[i + FIELD_LIMBS] = carry;
+  }
+
+  // Doubling
+  (*t)[FIELD_WIDE_LIMBS - 1u] = (*t)[FIELD_WIDE_LIMBS - 2u] >> (FIELD_LIMB_BITS - 1u);
+  for(var i = FIELD_WIDE_LIMBS - 2u; i > 1u; i--) {
+    (*t)[i] = ((*t)[i] << 1u) | ((*t)[i - 1u] >> (FIELD_LIMB_BITS - 1u));
+  }
+  (*t)[1] = (*t)[1] << 1u;
+
+  // Squares
+  var carry = 0u;
+  for(var i = 0u; i < FIELD_LIMBS; i++) {
+    (*t)[2u * i] = FIELD_mac_with_carry(a.val[i], a.val[i], (*t)[2u * i], &carry);
+    (*t)[2u * i + 1u] = FIELD_add_with_carry((*t)[2u * i + 1u], &carry);
+  }
+}
+
+// Squaring is a special case of multiplication which can be done ~1.5x faster.
+// https://stackoverflow.com/a/16388571/1348497
+fn FIELD_sqr(a: FIELD) -> FIELD {
+  var t: FIELD_wide;
+  FIELD_sqr_wide(a, &t);
+  return FIELD_reduce(&t);
+}
+
+// Left-shift the limbs by one bit and subtract by modulus in case of overflow.
+// Faster version of FIELD_add(a, a)
+fn FIELD_double(a: FIELD) -> FIELD {
+  var res = a;
+  let carry = (a.val[FIELD_LIMBS - 1u] >> (FIELD_LIMB_BITS - 1u)) != 0u;
+  for(var i = FIELD_LIMBS - 1u; i >= 1u; i--) {
+    res.val[i] = (res.val[i] << 1u) | (res.val[i - 1u] >> (FIELD_LIMB_BITS - 1u));
+  }
+  res.val[0] = res.val[0] << 1u;
+  if((FIELD_NO_SPARE_BIT && carry) || FIELD_gte(res, FIELD_P)) {
+    res = FIELD_sub_(res, FIELD_P);
+  }
+  return res;
+}
+
+// Modular halving: a / 2 = a >> 1 if `a` is even, (a + p) >> 1 otherwise.
+// Works on the Montgomery form as well, since (a * R) / 2 = (a / 2) * R
+fn FIELD_half(a: FIELD) -> FIELD {
+  var res = a;
+  var carry = 0u;
+  if((a.val[0] & 1u) != 0u) {
+    res = FIELD_add_(a, FIELD_P);
+    if(FIELD_NO_SPARE_BIT && !FIELD_gte(res, a)) { carry = 1u; }
+  }
+  for(var i = 0u; i < FIELD_LIMBS - 1u; i++) {
+    res.val[i] = (res.val[i] >> 1u) | (res.val[i + 1u] << (FIELD_LIMB_BITS - 1u));
+  }
+  res.val[FIELD_LIMBS - 1u] =
+    (res.val[FIELD_LIMBS - 1u] >> 1u) | (carry << (FIELD_LIMB_BITS - 1u));
+  return res;
+}
+
+// Modular exponentiation (Exponentiation by Squaring)
+// https://en.wikipedia.org/wiki/Exponentiation_by_squaring
+fn FIELD_pow(base: FIELD, exponent: u32) -> FIELD {
+  var res = FIELD_ONE;
+  var b = base;
+  var e = exponent;
+  while(e > 0u) {
+    if((e & 1u) != 0u) { res = FIELD_mul(res, b); }
+    e = e >> 1u;
+    b = FIELD_sqr(b);
+  }
+  return res;
+}
+
+// Modular exponentiation by a full-size exponent, given in regular form.
+// Processes the exponent in fixed windows of FIELD_POW_WINDOW bits, from the
+// most significant one, with a table of the first 2^FIELD_POW_WINDOW powers.
+const FIELD_POW_WINDOW: u32 = 4u;
+const FIELD_POW_TABLE_SIZE: u32 = 1u << FIELD_POW_WINDOW;
+fn FIELD_pow_field(base: FIELD, exponent: FIELD) -> FIELD {
+  var table: array<FIELD, FIELD_POW_TABLE_SIZE>;
+  table[0] = FIELD_ONE;
+  for(var i = 1u; i < FIELD_POW_TABLE_SIZE; i++) { table[i] = FIELD_mul(table[i - 1u], base); }
+
+  var res = FIELD_ONE;
+  var started = false; // Squaring is skipped until the first non-zero window
+  for(var i = FIELD_BITS; i > 0u; i -= FIELD_POW_WINDOW) {
+    if(started) {
+      for(var j = 0u; j < FIELD_POW_WINDOW; j++) { res = FIELD_sqr(res); }
+    }
+    // Windows never cross limbs, as FIELD_POW_WINDOW divides FIELD_LIMB_BITS
+    let k = i - FIELD_POW_WINDOW;
+    let digit = (exponent.val[k / FIELD_LIMB_BITS] >> (k % FIELD_LIMB_BITS)) & (FIELD_POW_TABLE_SIZE - 1u);
+    if(digit != 0u) {
+      res = FIELD_mul(res, table[digit]);
+      started = true;
+    }
+  }
+  return res;
+}
+
+// Modular inversion using Fermat's little theorem: a^-1 = a^(p - 2)
+// Inverse of zero is defined to be zero.
+fn FIELD_inv(a: FIELD) -> FIELD {
+  return FIELD_pow_field(a, FIELD_P_MINUS_2);
+}
+
+// Legendre symbol using Euler's criterion: a^((p - 1) / 2)
+// Returns 0 for zero, 1 for quadratic residues and -1 otherwise.
+fn FIELD_legendre(a: FIELD) -> i32 {
+  let s = FIELD_pow_field(a, FIELD_P_MINUS_1_OVER_2);
+  if(FIELD_is_zero(s)) { return 0i; }
+  return select(-1i, 1i, FIELD_eq(s, FIELD_ONE));
+}
+
+fn FIELD_is_square(a: FIELD) -> bool {
+  return FIELD_legendre(a) >= 0i;
+}
+
+// Square root using Tonelli-Shanks, where p - 1 = 2^S * t
+// https://en.wikipedia.org/wiki/Tonelli%E2%80%93Shanks_algorithm
+// Returns false if `a` is not a square, otherwise stores one of its roots in `res`.
+fn FIELD_sqrt(a: FIELD, res: ptr<function, FIELD>) -> bool {
+  if(FIELD_is_zero(a)) {
+    *res = a;
+    return true;
+  }
+  var w = FIELD_pow_field(a, FIELD_T_MINUS_1_OVER_2); // a^((t - 1) / 2)
+  var x = FIELD_mul(a, w); // a^((t + 1) / 2)
+  var b = FIELD_mul(x, w); // a^t
+  var z = FIELD_ROOT_OF_UNITY;
+  var v = FIELD_S;
+  while(!FIELD_eq(b, FIELD_ONE)) {
+    // Least `k` such that b^(2^k) = 1, it reaches `v` only for non-squares
+    var k = 0u;
+    var b2k = b;
+    while(!FIELD_eq(b2k, FIELD_ONE)) {
+      b2k = FIELD_sqr(b2k);
+      k++;
+    }
+    if(k == v) { return false; }
+    w = z;
+    for(var j = 0u; j < v - k - 1u; j++) { w = FIELD_sqr(w); }
+    z = FIELD_sqr(w);
+    b = FIELD_mul(b, z);
+    x = FIELD_mul(x, w);
+    v = k;
+  }
+  *res = x;
+  return true;
+}
+
+fn FIELD_mont(a: FIELD) -> FIELD {
+  return FIELD_mul(a, FIELD_R2);
+}
+
+fn FIELD_unmont(a: FIELD) -> FIELD {
+  var one = FIELD_ZERO;
+  one.val[0] = 1u;
+  return FIELD_mul(a, one);
+}
+
+fn FIELD_from_u32(v: u32) -> FIELD {
+  var a = FIELD_ZERO;
+  a.val[0] = v;
+  return FIELD_mont(a);
+}
+
+// Montgomery form of `repr`, given in regular form. Returns false if `repr`
+// is not smaller than the modulus.
+fn FIELD_from_repr(repr: FIELD, res: ptr<function, FIELD>) -> bool {
+  if(FIELD_gte(repr, FIELD_P)) { return false; }
+  *res = FIELD_mont(repr);
+  return true;
+}
+
+// Get `i`th bit (From most significant digit) of the field.
+fn FIELD_get_bit(l: FIELD, i: u32) -> bool {
+  let limb = l.val[FIELD_LIMBS - 1u - i / FIELD_LIMB_BITS];
+  return ((limb >> (FIELD_LIMB_BITS - 1u - (i % FIELD_LIMB_BITS))) & 1u) != 0u;
+}
+
+// Get `window` consecutive bits, (Starting from `skip`th bit) from the field.
+fn FIELD_get_bits(l: FIELD, skip: u32, window: u32) -> u32 {
+  var ret = 0u;
+  for(var i = 0u; i < window; i++) {
+    ret = (ret << 1u) | select(0u, 1u, FIELD_get_bit(l, skip + i));
+  }
+  return ret;
+}
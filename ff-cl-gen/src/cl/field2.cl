@@ -0,0 +1,86 @@
+// Quadratic extension FIELD2 = FIELD[u] / (u^2 - NR)
+// `FIELD2_mul_by_nonresidue` multiplies an element of FIELD by NR.
+
+#define FIELD2_LIMB_BITS FIELD_LIMB_BITS
+
+typedef struct {
+  FIELD c0;
+  FIELD c1;
+} FIELD2; // Represents: c0 + u * c1
+
+DEVICE bool FIELD2_eq(FIELD2 a, FIELD2 b) {
+  return FIELD_eq(a.c0, b.c0) && FIELD_eq(a.c1, b.c1);
+}
+
+DEVICE bool FIELD2_is_zero(FIELD2 a) {
+  return FIELD_is_zero(a.c0) && FIELD_is_zero(a.c1);
+}
+
+DEVICE FIELD2 FIELD2_sub(FIELD2 a, FIELD2 b) {
+  a.c0 = FIELD_sub(a.c0, b.c0);
+  a.c1 = FIELD_sub(a.c1, b.c1);
+  return a;
+}
+
+DEVICE FIELD2 FIELD2_add(FIELD2 a, FIELD2 b) {
+  a.c0 = FIELD_add(a.c0, b.c0);
+  a.c1 = FIELD_add(a.c1, b.c1);
+  return a;
+}
+
+DEVICE FIELD2 FIELD2_double(FIELD2 a) {
+  a.c0 = FIELD_double(a.c0);
+  a.c1 = FIELD_double(a.c1);
+  return a;
+}
+
+DEVICE FIELD2 FIELD2_neg(FIELD2 a) {
+  a.c0 = FIELD_neg(a.c0);
+  a.c1 = FIELD_neg(a.c1);
+  return a;
+}
+
+/*
+ * (a_0 + u * a_1)(b_0 + u * b_1) = a_0 * b_0 + NR * a_1 * b_1 + u * (a_0 * b_1 + a_1 * b_0)
+ * Therefore:
+ * c_0 = a_0 * b_0 + NR * a_1 * b_1
+ * c_1 = (a_0 * b_1 + a_1 * b_0) = (a_0 + a_1) * (b_0 + b_1) - a_0 * b_0 - a_1 * b_1
+ */
+DEVICE FIELD2 FIELD2_mul(FIELD2 a, FIELD2 b) {
+  const FIELD aa = FIELD_mul(a.c0, b.c0);
+  const FIELD bb = FIELD_mul(a.c1, b.c1);
+  const FIELD o = FIELD_add(b.c0, b.c1);
+  a.c1 = FIELD_add(a.c1, a.c0);
+  a.c1 = FIELD_mul(a.c1, o);
+  a.c1 = FIELD_sub(a.c1, aa);
+  a.c1 = FIELD_sub(a.c1, bb);
+  a.c0 = FIELD_add(aa, FIELD2_mul_by_nonresidue(bb));
+  return a;
+}
+
+/*
+ * (a_0 + u * a_1)(a_0 + u * a_1) = a_0 ^ 2 + NR * a_1 ^ 2 + u * 2 * a_0 * a_1
+ * Therefore:
+ * c_0 = (a_0 * a_0 + NR * a_1 * a_1) = (a_0 + a_1)(a_0 + NR * a_1) - a_0 * a_1 - NR * a_0 * a_1
+ * c_1 = 2 * a_0 * a_1
+ */
+DEVICE FIELD2 FIELD2_sqr(FIELD2 a) {
+  const FIELD ab = FIELD_mul(a.c0, a.c1);
+  const FIELD c0c1 = FIELD_add(a.c0, a.c1);
+  a.c0 = FIELD_mul(FIELD_add(a.c0, FIELD2_mul_by_nonresidue(a.c1)), c0c1);
+  a.c0 = FIELD_sub(FIELD_sub(a.c0, ab), FIELD2_mul_by_nonresidue(ab));
+  a.c1 = FIELD_double(ab);
+  return a;
+}
+
+/*
+ * 1 / (a_0 + u * a_1) = (a_0 - u * a_1) / (a_0 ^ 2 - NR * a_1 ^ 2)
+ * Inverse of zero is defined to be zero.
+ */
+DEVICE FIELD2 FIELD2_inv(FIELD2 a) {
+  const FIELD norm = FIELD_sub(FIELD_sqr(a.c0), FIELD2_mul_by_nonresidue(FIELD_sqr(a.c1)));
+  const FIELD t = FIELD_inv(norm);
+  a.c0 = FIELD_mul(a.c0, t);
+  a.c1 = FIELD_neg(FIELD_mul(a.c1, t));
+  return a;
+}
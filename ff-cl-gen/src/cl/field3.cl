@@ -0,0 +1,114 @@
+// Cubic extension FIELD3 = FIELD[v] / (v^3 - NR)
+// `FIELD3_mul_by_nonresidue` multiplies an element of FIELD by NR.
+
+#define FIELD3_LIMB_BITS FIELD_LIMB_BITS
+
+typedef struct {
+  FIELD c0;
+  FIELD c1;
+  FIELD c2;
+} FIELD3; // Represents: c0 + v * c1 + v^2 * c2
+
+DEVICE bool FIELD3_eq(FIELD3 a, FIELD3 b) {
+  return FIELD_eq(a.c0, b.c0) && FIELD_eq(a.c1, b.c1) && FIELD_eq(a.c2, b.c2);
+}
+
+DEVICE bool FIELD3_is_zero(FIELD3 a) {
+  return FIELD_is_zero(a.c0) && FIELD_is_zero(a.c1) && FIELD_is_zero(a.c2);
+}
+
+DEVICE FIELD3 FIELD3_sub(FIELD3 a, FIELD3 b) {
+  a.c0 = FIELD_sub(a.c0, b.c0);
+  a.c1 = FIELD_sub(a.c1, b.c1);
+  a.c2 = FIELD_sub(a.c2, b.c2);
+  return a;
+}
+
+DEVICE FIELD3 FIELD3_add(FIELD3 a, FIELD3 b) {
+  a.c0 = FIELD_add(a.c0, b.c0);
+  a.c1 = FIELD_add(a.c1, b.c1);
+  a.c2 = FIELD_add(a.c2, b.c2);
+  return a;
+}
+
+DEVICE FIELD3 FIELD3_double(FIELD3 a) {
+  a.c0 = FIELD_double(a.c0);
+  a.c1 = FIELD_double(a.c1);
+  a.c2 = FIELD_double(a.c2);
+  return a;
+}
+
+DEVICE FIELD3 FIELD3_neg(FIELD3 a) {
+  a.c0 = FIELD_neg(a.c0);
+  a.c1 = FIELD_neg(a.c1);
+  a.c2 = FIELD_neg(a.c2);
+  return a;
+}
+
+// Multiplication by v: (a_0 + v * a_1 + v^2 * a_2) * v = NR * a_2 + v * a_0 + v^2 * a_1
+DEVICE FIELD3 FIELD3_mul_by_v(FIELD3 a) {
+  const FIELD c2 = a.c2;
+  a.c2 = a.c1;
+  a.c1 = a.c0;
+  a.c0 = FIELD3_mul_by_nonresidue(c2);
+  return a;
+}
+
+/*
+ * Karatsuba, with t_i = a_i * b_i:
+ * c_0 = t_0 + NR * ((a_1 + a_2) * (b_1 + b_2) - t_1 - t_2)
+ * c_1 = (a_0 + a_1) * (b_0 + b_1) - t_0 - t_1 + NR * t_2
+ * c_2 = (a_0 + a_2) * (b_0 + b_2) - t_0 - t_2 + t_1
+ */
+DEVICE FIELD3 FIELD3_mul(FIELD3 a, FIELD3 b) {
+  const FIELD t0 = FIELD_mul(a.c0, b.c0);
+  const FIELD t1 = FIELD_mul(a.c1, b.c1);
+  const FIELD t2 = FIELD_mul(a.c2, b.c2);
+  FIELD3 res;
+  res.c0 = FIELD_mul(FIELD_add(a.c1, a.c2), FIELD_add(b.c1, b.c2));
+  res.c0 = FIELD_sub(FIELD_sub(res.c0, t1), t2);
+  res.c0 = FIELD_add(FIELD3_mul_by_nonresidue(res.c0), t0);
+  res.c1 = FIELD_mul(FIELD_add(a.c0, a.c1), FIELD_add(b.c0, b.c1));
+  res.c1 = FIELD_sub(FIELD_sub(res.c1, t0), t1);
+  res.c1 = FIELD_add(res.c1, FIELD3_mul_by_nonresidue(t2));
+  res.c2 = FIELD_mul(FIELD_add(a.c0, a.c2), FIELD_add(b.c0, b.c2));
+  res.c2 = FIELD_add(FIELD_sub(FIELD_sub(res.c2, t0), t2), t1);
+  return res;
+}
+
+/*
+ * CH-SQR2 from "Multiplication and Squaring on Pairing-Friendly Fields":
+ * s_0 = a_0^2, s_1 = 2 * a_0 * a_1, s_2 = (a_0 - a_1 + a_2)^2, s_3 = 2 * a_1 * a_2, s_4 = a_2^2
+ * c_0 = s_0 + NR * s_3
+ * c_1 = s_1 + NR * s_4
+ * c_2 = s_1 + s_2 + s_3 - s_0 - s_4
+ */
+DEVICE FIELD3 FIELD3_sqr(FIELD3 a) {
+  const FIELD s0 = FIELD_sqr(a.c0);
+  const FIELD s1 = FIELD_double(FIELD_mul(a.c0, a.c1));
+  const FIELD s2 = FIELD_sqr(FIELD_add(FIELD_sub(a.c0, a.c1), a.c2));
+  const FIELD s3 = FIELD_double(FIELD_mul(a.c1, a.c2));
+  const FIELD s4 = FIELD_sqr(a.c2);
+  a.c0 = FIELD_add(s0, FIELD3_mul_by_nonresidue(s3));
+  a.c1 = FIELD_add(s1, FIELD3_mul_by_nonresidue(s4));
+  a.c2 = FIELD_sub(FIELD_sub(FIELD_add(FIELD_add(s1, s2), s3), s0), s4);
+  return a;
+}
+
+/*
+ * With t_0 = a_0^2 - NR * a_1 * a_2, t_1 = NR * a_2^2 - a_0 * a_1 and t_2 = a_1^2 - a_0 * a_2:
+ * 1 / a = (t_0 + v * t_1 + v^2 * t_2) / (a_0 * t_0 + NR * (a_2 * t_1 + a_1 * t_2))
+ * Inverse of zero is defined to be zero.
+ */
+DEVICE FIELD3 FIELD3_inv(FIELD3 a) {
+  const FIELD t0 = FIELD_sub(FIELD_sqr(a.c0), FIELD3_mul_by_nonresidue(FIELD_mul(a.c1, a.c2)));
+  const FIELD t1 = FIELD_sub(FIELD3_mul_by_nonresidue(FIELD_sqr(a.c2)), FIELD_mul(a.c0, a.c1));
+  const FIELD t2 = FIELD_sub(FIELD_sqr(a.c1), FIELD_mul(a.c0, a.c2));
+  FIELD norm = FIELD_add(FIELD_mul(a.c2, t1), FIELD_mul(a.c1, t2));
+  norm = FIELD_add(FIELD_mul(a.c0, t0), FIELD3_mul_by_nonresidue(norm));
+  const FIELD t = FIELD_inv(norm);
+  a.c0 = FIELD_mul(t0, t);
+  a.c1 = FIELD_mul(t1, t);
+  a.c2 = FIELD_mul(t2, t);
+  return a;
+}
@@ -0,0 +1,37 @@
+// Maps the OpenCL C builtins used by the generated code to plain C, so that
+// the generated field arithmetic can be built with the system C compiler and
+// tested without an OpenCL device. Needs a GNU C11 compiler (gcc/clang).
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef uint8_t uchar;
+typedef uint16_t ushort;
+typedef uint32_t uint;
+typedef uint64_t ulong;
+
+#define __kernel
+#define __global
+#define __local
+#define __constant const
+
+// Kernels are executed sequentially, one work-item at a time.
+static uint host_global_id = 0;
+uint get_global_id(uint dim) {
+  return host_global_id;
+}
+
+uint mad_hi_32(uint a, uint b, uint c) {
+  return (uint)(((ulong)a * b) >> 32) + c;
+}
+
+ulong mad_hi_64(ulong a, ulong b, ulong c) {
+  return (ulong)(((unsigned __int128)a * b) >> 64) + c;
+}
+
+#define mad_hi(a, b, c) _Generic((a), uint: mad_hi_32, ulong: mad_hi_64)(a, b, c)
+#define min(a, b) ((a) < (b) ? (a) : (b))
+#define max(a, b) ((a) > (b) ? (a) : (b))
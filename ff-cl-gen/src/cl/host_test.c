@@ -0,0 +1,127 @@
+// Test driver for the host harness. Reads one operation per line from stdin,
+// e.g. `mul <a> <b>`, and writes its result as a line to stdout. Field
+// elements are encoded as the hex-dump of their in-memory representation.
+// Also built as C++, for testing the CUDA output.
+
+void FIELD_read(FIELD *a) {
+  uchar *bytes = (uchar *)a;
+  for(uint i = 0; i < sizeof(FIELD); i++) {
+    uint byte;
+    if(scanf("%2x", &byte) != 1) exit(2);
+    bytes[i] = byte;
+  }
+}
+
+void FIELD_write(FIELD a) {
+  const uchar *bytes = (const uchar *)&a;
+  for(uint i = 0; i < sizeof(FIELD); i++) printf("%02x", bytes[i]);
+}
+
+void FIELD_write_bytes(FIELD_bytes a) {
+  for(uint i = 0; i < FIELD_BYTES; i++) printf("%02x", a.val[i]);
+}
+
+uint FIELD_read_uint() {
+  uint v;
+  if(scanf("%u", &v) != 1) exit(2);
+  return v;
+}
+
+int main() {
+  char op[64];
+  while(scanf("%63s", op) == 1) {
+    FIELD a, b;
+    if(!strcmp(op, "add")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_add(a, b));
+    } else if(!strcmp(op, "sub")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_sub(a, b));
+    } else if(!strcmp(op, "mul")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_mul(a, b));
+    } else if(!strcmp(op, "sqr")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_sqr(a));
+    } else if(!strcmp(op, "double")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_double(a));
+    } else if(!strcmp(op, "neg")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_neg(a));
+    } else if(!strcmp(op, "half")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_half(a));
+    } else if(!strcmp(op, "is_zero")) {
+      FIELD_read(&a);
+      printf("%d", FIELD_is_zero(a));
+    } else if(!strcmp(op, "get_bits")) {
+      // get_bits <a> <skip> <window>
+      FIELD_read(&a);
+      const uint skip = FIELD_read_uint();
+      printf("%u", FIELD_get_bits(a, skip, FIELD_read_uint()));
+    } else if(!strcmp(op, "from_u32")) {
+      FIELD_write(FIELD_from_u32(FIELD_read_uint()));
+    } else if(!strcmp(op, "from_u64")) {
+      unsigned long long v;
+      if(scanf("%llu", &v) != 1) exit(2);
+      FIELD_write(FIELD_from_u64(v));
+    } else if(!strcmp(op, "from_repr")) {
+      FIELD_read(&a);
+      if(FIELD_from_repr(a, &b)) FIELD_write(b);
+      else printf("none");
+    } else if(!strcmp(op, "to_bytes_le")) {
+      FIELD_read(&a);
+      FIELD_write_bytes(FIELD_to_bytes_le(a));
+    } else if(!strcmp(op, "to_bytes_be")) {
+      FIELD_read(&a);
+      FIELD_write_bytes(FIELD_to_bytes_be(a));
+    } else if(!strcmp(op, "pow")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_pow(a, FIELD_read_uint()));
+    } else if(!strcmp(op, "pow_field")) {
+      // pow_field <base> <exponent in regular form>
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_pow_field(a, b));
+#ifdef FIELD_POW_CONSTANT
+    } else if(!strcmp(op, "pow_constant")) {
+      // Function generated through `FieldGenerator::pow_constant`
+      FIELD_read(&a);
+      FIELD_write(FIELD_POW_CONSTANT(a));
+#endif
+    } else if(!strcmp(op, "mont")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_mont(a));
+    } else if(!strcmp(op, "unmont")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_unmont(a));
+    } else if(!strcmp(op, "inv")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_inv(a));
+    } else if(!strcmp(op, "sqrt")) {
+      FIELD_read(&a);
+      if(FIELD_sqrt(a, &b)) FIELD_write(b);
+      else printf("none");
+    } else if(!strcmp(op, "legendre")) {
+      FIELD_read(&a);
+      printf("%d", FIELD_legendre(a));
+    } else if(!strcmp(op, "batch_inv")) {
+      // batch_inv <n> <chunk_size> <elements...>
+      const uint n = FIELD_read_uint();
+      const uint chunk_size = FIELD_read_uint();
+      FIELD *elements = (FIELD *)malloc(n * sizeof(FIELD));
+      FIELD *tmp = (FIELD *)malloc(n * sizeof(FIELD));
+      for(uint i = 0; i < n; i++) FIELD_read(&elements[i]);
+      for(host_global_id = 0; host_global_id * chunk_size < n; host_global_id++)
+        FIELD_batch_inv(elements, tmp, n, chunk_size);
+      for(uint i = 0; i < n; i++) FIELD_write(elements[i]);
+      free(elements);
+      free(tmp);
+    } else {
+      fprintf(stderr, "Unknown operation: %s\n", op);
+      return 1;
+    }
+    printf("\n");
+  }
+  return 0;
+}
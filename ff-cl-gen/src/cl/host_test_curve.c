@@ -0,0 +1,90 @@
+// Test driver for the elliptic curves, see `host_test.c`. Points are encoded
+// the same way as field elements, scalars as the hex-dump of their regular form.
+
+void POINT_read(void *a, uint size) {
+  uchar *bytes = (uchar *)a;
+  for(uint i = 0; i < size; i++) {
+    uint byte;
+    if(scanf("%2x", &byte) != 1) exit(2);
+    bytes[i] = byte;
+  }
+}
+
+void POINT_write(const void *a, uint size) {
+  const uchar *bytes = (const uchar *)a;
+  for(uint i = 0; i < size; i++) printf("%02x", bytes[i]);
+}
+
+uint POINT_read_uint() {
+  uint v;
+  if(scanf("%u", &v) != 1) exit(2);
+  return v;
+}
+
+int main() {
+  char op[64];
+  while(scanf("%63s", op) == 1) {
+    POINT_projective a, b;
+    POINT_affine c;
+    POINT_scalar s;
+    if(!strcmp(op, "add")) {
+      POINT_read(&a, sizeof(a)); POINT_read(&b, sizeof(b));
+      a = POINT_add(a, b);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "add_mixed")) {
+      POINT_read(&a, sizeof(a)); POINT_read(&c, sizeof(c));
+      a = POINT_add_mixed(a, c);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "double")) {
+      POINT_read(&a, sizeof(a));
+      a = POINT_double(a);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "neg")) {
+      POINT_read(&a, sizeof(a));
+      a = POINT_neg(a);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "neg_affine")) {
+      POINT_read(&c, sizeof(c));
+      c = POINT_neg_affine(c);
+      POINT_write(&c, sizeof(c));
+    } else if(!strcmp(op, "mul")) {
+      POINT_read(&a, sizeof(a)); POINT_read(&s, sizeof(s));
+      a = POINT_mul(a, s);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "to_affine")) {
+      POINT_read(&a, sizeof(a));
+      c = POINT_to_affine(a);
+      POINT_write(&c, sizeof(c));
+    } else if(!strcmp(op, "from_affine")) {
+      POINT_read(&c, sizeof(c));
+      a = POINT_from_affine(c);
+      POINT_write(&a, sizeof(a));
+    } else if(!strcmp(op, "multiexp")) {
+      // multiexp <n> <num_groups> <num_windows> <window_size> <bases...> <exps...>
+      const uint n = POINT_read_uint();
+      const uint num_groups = POINT_read_uint();
+      const uint num_windows = POINT_read_uint();
+      const uint window_size = POINT_read_uint();
+      const uint threads = num_groups * num_windows;
+      POINT_affine *bases = (POINT_affine *)malloc(n * sizeof(POINT_affine));
+      POINT_scalar *exps = (POINT_scalar *)malloc(n * sizeof(POINT_scalar));
+      POINT_projective *buckets =
+        (POINT_projective *)malloc(threads * ((1 << window_size) - 1) * sizeof(POINT_projective));
+      POINT_projective *results = (POINT_projective *)malloc(threads * sizeof(POINT_projective));
+      for(uint i = 0; i < n; i++) POINT_read(&bases[i], sizeof(POINT_affine));
+      for(uint i = 0; i < n; i++) POINT_read(&exps[i], sizeof(POINT_scalar));
+      for(host_global_id = 0; host_global_id < threads; host_global_id++)
+        POINT_multiexp(bases, buckets, results, exps, n, num_groups, num_windows, window_size);
+      for(uint i = 0; i < threads; i++) POINT_write(&results[i], sizeof(POINT_projective));
+      free(bases);
+      free(exps);
+      free(buckets);
+      free(results);
+    } else {
+      fprintf(stderr, "Unknown operation: %s\n", op);
+      return 1;
+    }
+    printf("\n");
+  }
+  return 0;
+}
@@ -0,0 +1,51 @@
+// Test driver for the extension fields, see `host_test.c`.
+
+void FIELD_read(FIELD *a) {
+  uchar *bytes = (uchar *)a;
+  for(uint i = 0; i < sizeof(FIELD); i++) {
+    uint byte;
+    if(scanf("%2x", &byte) != 1) exit(2);
+    bytes[i] = byte;
+  }
+}
+
+void FIELD_write(FIELD a) {
+  const uchar *bytes = (const uchar *)&a;
+  for(uint i = 0; i < sizeof(FIELD); i++) printf("%02x", bytes[i]);
+}
+
+int main() {
+  char op[64];
+  while(scanf("%63s", op) == 1) {
+    FIELD a, b;
+    if(!strcmp(op, "add")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_add(a, b));
+    } else if(!strcmp(op, "sub")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_sub(a, b));
+    } else if(!strcmp(op, "mul")) {
+      FIELD_read(&a); FIELD_read(&b);
+      FIELD_write(FIELD_mul(a, b));
+    } else if(!strcmp(op, "sqr")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_sqr(a));
+    } else if(!strcmp(op, "double")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_double(a));
+    } else if(!strcmp(op, "neg")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_neg(a));
+    } else if(!strcmp(op, "inv")) {
+      FIELD_read(&a);
+      FIELD_write(FIELD_inv(a));
+    } else if(!strcmp(op, "one")) {
+      FIELD_write(FIELD_ONE);
+    } else {
+      fprintf(stderr, "Unknown operation: %s\n", op);
+      return 1;
+    }
+    printf("\n");
+  }
+  return 0;
+}
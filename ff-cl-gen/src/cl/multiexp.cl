@@ -0,0 +1,59 @@
+// Multiexp over the points of POINT, works with any curve providing POINT_add,
+// POINT_add_mixed and POINT_ZERO.
+
+// Get `window` consecutive bits of `s`, starting from the `skip`th least
+// significant bit
+DEVICE uint POINT_scalar_get_bits(POINT_scalar s, uint skip, uint window) {
+  uint ret = 0;
+  for(uint i = 0; i < window; i++) {
+    const uint bit = skip + i;
+    ret |= ((s.val[bit / 32] >> (bit % 32)) & 1) << i;
+  }
+  return ret;
+}
+
+// Bucket multiexp, same algorithm as the `bellman_multiexp` kernel of
+// bellperson. Thread `gid` sums the bases of group `gid / num_windows`,
+// weighted by window `gid % num_windows` of their scalars. Window `k` covers
+// the bits `[k * window_size, (k + 1) * window_size)`, so the host gets the
+// result as the sum of `2^(k * window_size) * results[g * num_windows + k]`.
+__kernel void POINT_multiexp(
+    __global POINT_affine *bases,
+    __global POINT_projective *buckets,
+    __global POINT_projective *results,
+    __global POINT_scalar *exps,
+    uint n,
+    uint num_groups,
+    uint num_windows,
+    uint window_size) {
+
+  const uint gid = get_global_id(0);
+  if(gid >= num_windows * num_groups) return;
+
+  // Each thread has its own (2^window_size - 1) buckets in global memory
+  const uint bucket_len = ((1 << window_size) - 1);
+  buckets += bucket_len * gid;
+  const POINT_projective local_zero = POINT_ZERO;
+  for(uint i = 0; i < bucket_len; i++) buckets[i] = local_zero;
+
+  const uint len = (n + num_groups - 1) / num_groups; // Num of elements in each group
+  const uint nstart = min(len * (gid / num_windows), n);
+  const uint nend = min(nstart + len, n);
+  const uint bits = (gid % num_windows) * window_size;
+  const uint w = min(window_size, (uint)(POINT_SCALAR_BITS - bits));
+
+  for(uint i = nstart; i < nend; i++) {
+    uint ind = POINT_scalar_get_bits(exps[i], bits, w);
+    if(ind--) buckets[ind] = POINT_add_mixed(buckets[ind], bases[i]);
+  }
+
+  // Summation by parts, e.g. 3a + 2b + 1c = a + (a + b) + (a + b + c)
+  POINT_projective acc = POINT_ZERO;
+  POINT_projective res = POINT_ZERO;
+  for(int j = bucket_len - 1; j >= 0; j--) {
+    acc = POINT_add(acc, buckets[j]);
+    res = POINT_add(res, acc);
+  }
+
+  results[gid] = res;
+}
@@ -30,6 +30,10 @@ __kernel void test_double_32(Fr32 a, __global Fr32 *result) {
   *result = Fr32_double(a);
 }
 
+__kernel void test_inv_32(Fr32 a, __global Fr32 *result) {
+  *result = Fr32_inv(a);
+}
+
 ////////////
 
 __kernel void test_add_64(Fr64 a, Fr64 b, __global Fr64 *result) {
@@ -63,3 +67,7 @@ __kernel void test_sqr_64(Fr64 a, __global Fr64 *result) {
 __kernel void test_double_64(Fr64 a, __global Fr64 *result) {
   *result = Fr64_double(a);
 }
+
+__kernel void test_inv_64(Fr64 a, __global Fr64 *result) {
+  *result = Fr64_inv(a);
+}
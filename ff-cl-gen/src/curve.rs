@@ -0,0 +1,675 @@
+use crate::*;
+use ff::Field;
+use groupy::{CurveAffine, CurveProjective};
+use std::mem;
+
+static EC_SRC: &str = include_str!("cl/ec.cl");
+pub(crate) static MULTIEXP_SRC: &str = include_str!("cl/multiexp.cl");
+
+/// Coefficient `a` of the curve `y^2 = x^3 + a * x + b`, which selects the doubling formula
+#[derive(Clone)]
+pub(crate) enum CoeffA<L: Limb> {
+    Zero,
+    MinusThree,
+    /// Any other value, as the limbs of its in-memory representation
+    Other(Vec<L>),
+}
+
+/// Layout of the affine points of the backend: Both coordinates, optionally followed by the
+/// infinity flag and some padding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct AffineLayout {
+    pub infinity: bool,
+    pub padding: usize,
+}
+
+impl AffineLayout {
+    fn of<C: CurveAffine>() -> AffineLayout {
+        let coordinates = 2 * mem::size_of::<C::Base>();
+        let size = mem::size_of::<C>();
+        assert!(size >= coordinates, "Unexpected layout of affine points");
+        AffineLayout {
+            infinity: size > coordinates,
+            padding: size.saturating_sub(coordinates + 1),
+        }
+    }
+}
+
+// Coordinates of a (Non-zero) affine point, read from its in-memory representation. The kernels
+// expect them at the beginning of the point, as `[x, y]`.
+fn coordinates<C: CurveAffine>(point: &C) -> (C::Base, C::Base) {
+    let base = point as *const C as *const C::Base;
+    unsafe {
+        (
+            std::ptr::read_unaligned(base),
+            std::ptr::read_unaligned(base.add(1)),
+        )
+    }
+}
+
+// `CurveAffine` doesn't expose the coefficients, so they are recovered from `P`, `2P` and `3P`:
+// The `a` and `b` of the line `y^2 - x^3 = a * x + b` through two points, checked on the third.
+fn coeff_a<C: CurveAffine, L: Limb>() -> CoeffA<L> {
+    let p = C::one();
+    let mut q = p.into_projective();
+    q.double();
+    let p2 = q.into_affine();
+    q.add_assign_mixed(&p);
+    let p3 = q.into_affine();
+    assert!(
+        !p.is_zero() && !p2.is_zero() && !p3.is_zero(),
+        "The generator of the curve has a too small order"
+    );
+
+    let y2_minus_x3 = |(x, y): (C::Base, C::Base)| {
+        let mut x3 = x;
+        x3.square();
+        x3.mul_assign(&x);
+        let mut res = y;
+        res.square();
+        res.sub_assign(&x3);
+        res
+    };
+    let (p, p2, p3) = (coordinates(&p), coordinates(&p2), coordinates(&p3));
+    let mut dx = p.0;
+    dx.sub_assign(&p2.0);
+    let mut a = y2_minus_x3(p);
+    a.sub_assign(&y2_minus_x3(p2));
+    a.mul_assign(
+        &dx.inverse()
+            .expect("The generator of the curve has order 3"),
+    );
+    let mut b = p.0;
+    b.mul_assign(&a);
+    b.negate();
+    b.add_assign(&y2_minus_x3(p));
+    let mut rhs = p3.0;
+    rhs.mul_assign(&a);
+    rhs.add_assign(&b);
+    assert!(
+        rhs == y2_minus_x3(p3),
+        "Unexpected layout of affine points, the coordinates should come first"
+    );
+
+    let mut minus_three = C::Base::one();
+    minus_three.double();
+    minus_three.add_assign(&C::Base::one());
+    minus_three.negate();
+    if a.is_zero() {
+        CoeffA::Zero
+    } else if a == minus_three {
+        CoeffA::MinusThree
+    } else {
+        CoeffA::Other(L::limbs_of(a))
+    }
+}
+
+/// Code of the curve `name` over the field `base`. Scalars have `scalar_bits` bits, stored in
+/// `scalar_words` 32-bit words.
+pub(crate) fn curve_source<L: Limb>(
+    name: &str,
+    base: &str,
+    a: &CoeffA<L>,
+    layout: AffineLayout,
+    scalar_bits: usize,
+    scalar_words: usize,
+    target: Target,
+) -> String {
+    let mut defs = vec![match a {
+        CoeffA::Zero => "#define POINT_A_ZERO".to_string(),
+        CoeffA::MinusThree => "#define POINT_A_MINUS_3".to_string(),
+        CoeffA::Other(limbs) => define_constant("POINT_A", limbs, target),
+    }];
+
+    let mut affine = "typedef struct {\n  FIELD x;\n  FIELD y;\n".to_string();
+    let affine_zero = if layout.infinity {
+        defs.push("#define POINT_AFFINE_INF".to_string());
+        affine.push_str("  bool inf;\n");
+        if layout.padding > 0 {
+            affine.push_str(&format!("  uchar _padding[{}];\n", layout.padding));
+            "FIELD_ZERO, FIELD_ONE, true, { 0 }"
+        } else {
+            "FIELD_ZERO, FIELD_ONE, true"
+        }
+    } else {
+        "FIELD_ZERO, FIELD_ZERO"
+    };
+    affine.push_str("} POINT_affine;");
+    defs.push(affine);
+    defs.push(format!(
+        "#define POINT_AFFINE_ZERO {}",
+        compound("POINT_affine", affine_zero, target)
+    ));
+    defs.push(format!(
+        "#define POINT_ZERO {}",
+        compound(
+            "POINT_projective",
+            "FIELD_ZERO, FIELD_ONE, FIELD_ZERO",
+            target
+        )
+    ));
+
+    defs.extend(define_scalar(scalar_bits, scalar_words));
+
+    join(
+        defs.iter()
+            .map(String::as_str)
+            .chain(vec![EC_SRC, MULTIEXP_SRC]),
+        "\n",
+    )
+    .replace("POINT", name)
+    .replace("FIELD", base)
+}
+
+/// Definition of the constant `name` of type `FIELD`, given the limbs of its in-memory
+/// representation. The limbs of extension fields are given without the braces of the nested
+/// structs.
+pub(crate) fn define_constant<L: Limb>(name: &str, limbs: &[L], target: Target) -> String {
+    format!(
+        "#define {} {}",
+        name,
+        compound(
+            "FIELD",
+            &join(limbs.iter().map(|l| literal(l, target)), ", "),
+            target
+        )
+    )
+}
+
+/// Definitions of `POINT_scalar`, scalars in regular form, least significant word first
+pub(crate) fn define_scalar(scalar_bits: usize, scalar_words: usize) -> Vec<String> {
+    vec![
+        format!("#define POINT_SCALAR_BITS {}", scalar_bits),
+        format!(
+            "typedef struct {{ uint val[{}]; }} POINT_scalar;",
+            scalar_words
+        ),
+    ]
+}
+
+fn generate<C: CurveAffine, L: Limb>(name: &str, base: &str, target: Target) -> String {
+    assert_eq!(
+        mem::size_of::<C::Base>() % mem::size_of::<L>(),
+        0,
+        "Elements of the base field should be made of limbs"
+    );
+    curve_source(
+        name,
+        base,
+        &coeff_a::<C, L>(),
+        AffineLayout::of::<C>(),
+        C::Scalar::NUM_BITS as usize,
+        mem::size_of::<<C::Scalar as PrimeField>::Repr>() / 4,
+        target,
+    )
+}
+
+/// Returns OpenCL source-code of the short Weierstrass curve `C` with name `name`, operating on
+/// the points of `C` (Find details in README.md). The code of the base field has to be
+/// included before, with name `base` and the in-memory representation of `C::Base` (I.e.
+/// generated by `field::<C::Base, L>(base)`, or by `quadratic_extension` and the like for
+/// extension fields).
+pub fn curve<C: CurveAffine, L: Limb>(name: &str, base: &str) -> String {
+    generate::<C, L>(name, base, Target::OpenCL)
+}
+
+/// Returns CUDA source-code of the curve `C`, providing the same interface as `curve`
+pub fn curve_cuda<C: CurveAffine, L: Limb>(name: &str, base: &str) -> String {
+    generate::<C, L>(name, base, Target::Cuda)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{curve_driver, decode, encode, Harness};
+    use lazy_static::lazy_static;
+    use paired::bls12_381::{Fq, Fr, FrRepr, G1Affine, G2Affine};
+    use rand::thread_rng;
+
+    fn fq<L: Limb>() -> GeneratedField {
+        let limb_size = if L::bits() == 32 {
+            LimbSize::Limb32
+        } else {
+            LimbSize::Limb64
+        };
+        FieldGenerator::<Fq>::new("Fq")
+            .limb_size(limb_size)
+            .generate()
+    }
+
+    fn bls12_381<L: Limb>(name: &str) -> [Harness; 2] {
+        let fq = fq::<L>();
+        let fq2 = quadratic_extension(
+            "Fq2",
+            &fq,
+            &[{
+                let mut nr = Fq::one();
+                nr.negate();
+                nr
+            }],
+        );
+        let g1 = curve::<G1Affine, L>("G1", "Fq");
+        let g2 = curve::<G2Affine, L>("G2", "Fq2");
+        let src = join(&[&fq.source, &fq2.source, &g1, &g2], "\n");
+        [
+            Harness::compile(&format!("{}G1", name), &src, &curve_driver("G1")),
+            Harness::compile(&format!("{}G2", name), &src, &curve_driver("G2")),
+        ]
+    }
+
+    lazy_static! {
+        static ref BLS32: [Harness; 2] = bls12_381::<Limb32>("Bls32");
+        static ref BLS64: [Harness; 2] = bls12_381::<Limb64>("Bls64");
+    }
+
+    const SAMPLES: usize = 10;
+
+    /// Checks the generated code of `C` against the implementation of the backend
+    fn check_backend<C: CurveAffine<Scalar = Fr>>(harness: &Harness) {
+        let mut rng = thread_rng();
+        let mut points = (0..SAMPLES)
+            .map(|_| C::Projective::random(&mut rng))
+            .collect::<Vec<_>>();
+        points.push(C::Projective::zero());
+        let scalars = (0..3)
+            .map(|_| Fr::random(&mut rng).into_repr())
+            .chain(vec![FrRepr::from(0), FrRepr::from(1)])
+            .collect::<Vec<_>>();
+        let run = |ops: Vec<String>| harness.run(&ops);
+
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("double {}", encode(p)))
+            .collect()))
+        {
+            let mut expected = *p;
+            expected.double();
+            assert_eq!(decode::<C::Projective>(&res), expected);
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("neg {}", encode(p)))
+            .collect()))
+        {
+            let mut expected = *p;
+            expected.negate();
+            assert_eq!(decode::<C::Projective>(&res), expected);
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("to_affine {}", encode(p)))
+            .collect()))
+        {
+            assert_eq!(decode::<C>(&res), p.into_affine());
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("from_affine {}", encode(&p.into_affine())))
+            .collect()))
+        {
+            assert_eq!(decode::<C::Projective>(&res), *p);
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("neg_affine {}", encode(&p.into_affine())))
+            .collect()))
+        {
+            let mut expected = p.into_affine();
+            expected.negate();
+            assert_eq!(decode::<C>(&res), expected);
+        }
+
+        // Includes the special cases P + P, P + (-P) and P + 0
+        let mut pairs = Vec::new();
+        for p in points.iter() {
+            let mut neg = *p;
+            neg.negate();
+            for q in points.iter().take(3).chain(vec![p, &neg]) {
+                pairs.push((*p, *q));
+            }
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| format!("add {} {}", encode(p), encode(q)))
+            .collect()))
+        {
+            let mut expected = *p;
+            expected.add_assign(q);
+            assert_eq!(decode::<C::Projective>(&res), expected);
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| format!("add_mixed {} {}", encode(p), encode(&q.into_affine())))
+            .collect()))
+        {
+            let mut expected = *p;
+            expected.add_assign(q);
+            assert_eq!(decode::<C::Projective>(&res), expected);
+        }
+
+        let products = points
+            .iter()
+            .flat_map(|p| scalars.iter().map(move |s| (*p, *s)))
+            .collect::<Vec<_>>();
+        for ((p, s), res) in products.iter().zip(run(products
+            .iter()
+            .map(|(p, s)| format!("mul {} {}", encode(p), encode(s)))
+            .collect()))
+        {
+            let mut expected = *p;
+            expected.mul_assign(*s);
+            assert_eq!(decode::<C::Projective>(&res), expected);
+        }
+
+        // The last group is smaller than the others
+        let bases = points.iter().map(|p| p.into_affine()).collect::<Vec<_>>();
+        let exps = (0..bases.len())
+            .map(|_| Fr::random(&mut rng).into_repr())
+            .collect::<Vec<_>>();
+        let (num_groups, window_size) = (4, 7);
+        let num_windows = (Fr::NUM_BITS as usize + window_size - 1) / window_size;
+        let mut expected = C::Projective::zero();
+        for (p, s) in points.iter().zip(exps.iter()) {
+            let mut product = *p;
+            product.mul_assign(*s);
+            expected.add_assign(&product);
+        }
+        let results = harness.multiexp::<_, C::Projective, _>(
+            &bases,
+            &exps,
+            (num_groups, num_windows, window_size),
+        );
+        let mut res = C::Projective::zero();
+        for k in (0..num_windows).rev() {
+            for _ in 0..window_size {
+                res.double();
+            }
+            for g in 0..num_groups {
+                res.add_assign(&results[g * num_windows + k]);
+            }
+        }
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_bls12_381() {
+        for harnesses in [&*BLS32, &*BLS64].iter() {
+            check_backend::<G1Affine>(&harnesses[0]);
+            check_backend::<G2Affine>(&harnesses[1]);
+        }
+    }
+
+    #[test]
+    fn test_coefficients() {
+        let g1 = curve::<G1Affine, Limb64>("G1", "Fq");
+        assert!(g1.contains("#define G1_A_ZERO"));
+        assert!(g1.contains("#define G1_AFFINE_INF"));
+        assert!(g1.contains("uchar _padding[7];"));
+        let g2 = curve_cuda::<G2Affine, Limb32>("G2", "Fq2");
+        assert!(g2.contains("#define G2_A_ZERO"));
+        assert!(g2.contains("(G2_affine{ Fq2_ZERO, Fq2_ONE, true, { 0 } })"));
+        assert!(matches!(coeff_a::<G2Affine, Limb32>(), CoeffA::Zero));
+    }
+
+    // Reference implementation of the curve `y^2 = x^3 + a * x + b` over Fq in affine coordinates,
+    // `b` is implied by the points
+    type Point = Option<(Fq, Fq)>;
+
+    fn double(a: &Fq, p: Point) -> Point {
+        let (x, y) = p?;
+        if y.is_zero() {
+            return None;
+        }
+        let mut x2 = x;
+        x2.square();
+        let mut l = x2;
+        l.double();
+        l.add_assign(&x2);
+        l.add_assign(a);
+        let mut y2 = y;
+        y2.double();
+        l.mul_assign(&y2.inverse().unwrap());
+        line(l, (x, y), x)
+    }
+
+    fn add(a: &Fq, p: Point, q: Point) -> Point {
+        let ((x1, y1), (x2, y2)) = match (p, q) {
+            (None, q) => return q,
+            (p, None) => return p,
+            (Some(p), Some(q)) => (p, q),
+        };
+        if x1 == x2 {
+            return if y1 == y2 { double(a, p) } else { None };
+        }
+        let mut l = y2;
+        l.sub_assign(&y1);
+        let mut dx = x2;
+        dx.sub_assign(&x1);
+        l.mul_assign(&dx.inverse().unwrap());
+        line(l, (x1, y1), x2)
+    }
+
+    // Third intersection of the line with slope `l` through `(x1, y1)` and `x2`, negated
+    fn line(l: Fq, (x1, y1): (Fq, Fq), x2: Fq) -> Point {
+        let mut x3 = l;
+        x3.square();
+        x3.sub_assign(&x1);
+        x3.sub_assign(&x2);
+        let mut y3 = x1;
+        y3.sub_assign(&x3);
+        y3.mul_assign(&l);
+        y3.sub_assign(&y1);
+        Some((x3, y3))
+    }
+
+    fn mul(a: &Fq, p: Point, s: &FrRepr) -> Point {
+        let mut res = None;
+        for i in (0..256).rev() {
+            res = double(a, res);
+            if (s.as_ref()[i / 64] >> (i % 64)) & 1 == 1 {
+                res = add(a, res, p);
+            }
+        }
+        res
+    }
+
+    fn neg(p: Point) -> Point {
+        p.map(|(x, mut y)| {
+            y.negate();
+            (x, y)
+        })
+    }
+
+    // Jacobian coordinates of `p`, with a random `z`
+    fn jacobian(p: Point) -> [Fq; 3] {
+        match p {
+            None => [Fq::zero(), Fq::one(), Fq::zero()],
+            Some((mut x, mut y)) => {
+                let z = Fq::random(&mut thread_rng());
+                let mut z2 = z;
+                z2.square();
+                x.mul_assign(&z2);
+                y.mul_assign(&z2);
+                y.mul_assign(&z);
+                [x, y, z]
+            }
+        }
+    }
+
+    fn normalize([x, y, z]: [Fq; 3]) -> Point {
+        let z_inv = z.inverse()?;
+        let mut z_inv2 = z_inv;
+        z_inv2.square();
+        let (mut x, mut y) = (x, y);
+        x.mul_assign(&z_inv2);
+        y.mul_assign(&z_inv2);
+        y.mul_assign(&z_inv);
+        Some((x, y))
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct FlaggedAffine {
+        x: Fq,
+        y: Fq,
+        inf: bool,
+        _padding: [u8; 7],
+    }
+
+    /// In-memory representation of the affine points, with or without the infinity flag
+    trait Affine: Copy {
+        const LAYOUT: AffineLayout;
+        fn from(p: Point) -> Self;
+        fn into(self) -> Point;
+    }
+
+    impl Affine for FlaggedAffine {
+        const LAYOUT: AffineLayout = AffineLayout {
+            infinity: true,
+            padding: 7,
+        };
+        fn from(p: Point) -> Self {
+            let (x, y, inf) = match p {
+                Some((x, y)) => (x, y, false),
+                None => (Fq::zero(), Fq::one(), true),
+            };
+            FlaggedAffine {
+                x,
+                y,
+                inf,
+                _padding: [0; 7],
+            }
+        }
+        fn into(self) -> Point {
+            if self.inf {
+                None
+            } else {
+                Some((self.x, self.y))
+            }
+        }
+    }
+
+    impl Affine for [Fq; 2] {
+        const LAYOUT: AffineLayout = AffineLayout {
+            infinity: false,
+            padding: 0,
+        };
+        fn from(p: Point) -> Self {
+            p.map(|(x, y)| [x, y]).unwrap_or([Fq::zero(); 2])
+        }
+        fn into(self) -> Point {
+            if self[0].is_zero() && self[1].is_zero() {
+                None
+            } else {
+                Some((self[0], self[1]))
+            }
+        }
+    }
+
+    /// Checks the curve with coefficient `a` over the generated `Fq`
+    fn check_coefficient<A: Affine, L: Limb>(name: &str, a: Fq, coeff: CoeffA<L>) {
+        let fq = fq::<L>();
+        let src = curve_source(name, "Fq", &coeff, A::LAYOUT, 255, 8, Target::OpenCL);
+        let harness = Harness::compile(name, &join(&[&fq.source, &src], "\n"), &curve_driver(name));
+
+        let mut rng = thread_rng();
+        let base = Some((Fq::random(&mut rng), Fq::random(&mut rng)));
+        let mut points = (0..SAMPLES)
+            .map(|_| mul(&a, base, &Fr::random(&mut rng).into_repr()))
+            .collect::<Vec<_>>();
+        points.push(None);
+        let scalars = vec![
+            Fr::random(&mut rng).into_repr(),
+            FrRepr::from(0),
+            FrRepr::from(1),
+        ];
+        let run = |ops: Vec<String>| harness.run(&ops);
+        let projective = |res: &String| normalize(decode(res));
+
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("double {}", encode(&jacobian(*p))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), double(&a, *p));
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("neg {}", encode(&jacobian(*p))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), neg(*p));
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("to_affine {}", encode(&jacobian(*p))))
+            .collect()))
+        {
+            assert_eq!(decode::<A>(&res).into(), *p);
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("from_affine {}", encode(&A::from(*p))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), *p);
+        }
+
+        let mut pairs = Vec::new();
+        for p in points.iter() {
+            for q in points.iter().take(3).chain(vec![p, &neg(*p)]) {
+                pairs.push((*p, *q));
+            }
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| format!("add {} {}", encode(&jacobian(*p)), encode(&jacobian(*q))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), add(&a, *p, *q));
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| {
+                format!(
+                    "add_mixed {} {}",
+                    encode(&jacobian(*p)),
+                    encode(&A::from(*q))
+                )
+            })
+            .collect()))
+        {
+            assert_eq!(projective(&res), add(&a, *p, *q));
+        }
+
+        let products = points
+            .iter()
+            .flat_map(|p| scalars.iter().map(move |s| (*p, *s)))
+            .collect::<Vec<_>>();
+        for ((p, s), res) in products.iter().zip(run(products
+            .iter()
+            .map(|(p, s)| format!("mul {} {}", encode(&jacobian(*p)), encode(s)))
+            .collect()))
+        {
+            assert_eq!(projective(&res), mul(&a, *p, s));
+        }
+    }
+
+    #[test]
+    fn test_a_minus_three() {
+        let mut a = Fq::one();
+        a.double();
+        a.add_assign(&Fq::one());
+        a.negate();
+        check_coefficient::<FlaggedAffine, Limb32>("MinusThree32", a, CoeffA::MinusThree);
+        check_coefficient::<FlaggedAffine, Limb64>("MinusThree64", a, CoeffA::MinusThree);
+    }
+
+    #[test]
+    fn test_generic_a() {
+        let a = Fq::random(&mut thread_rng());
+        check_coefficient::<[Fq; 2], Limb32>("Generic32", a, CoeffA::Other(Limb32::limbs_of(a)));
+        check_coefficient::<[Fq; 2], Limb64>("Generic64", a, CoeffA::Other(Limb64::limbs_of(a)));
+        check_coefficient::<[Fq; 2], Limb64>("Zero64", Fq::zero(), CoeffA::<Limb64>::Zero);
+    }
+}
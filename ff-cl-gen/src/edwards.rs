@@ -0,0 +1,374 @@
+use crate::curve::{define_constant, define_scalar, MULTIEXP_SRC};
+use crate::*;
+use std::mem;
+
+static EDWARDS_SRC: &str = include_str!("cl/edwards.cl");
+
+/// Code of the twisted Edwards curve `name` over the field `base`, `a` and `d` are the limbs of
+/// the in-memory representation of the coefficients (`None` for `a = -1`)
+pub(crate) fn edwards_source<L: Limb>(
+    name: &str,
+    base: &str,
+    a: Option<&[L]>,
+    d: &[L],
+    scalar_bits: usize,
+    scalar_words: usize,
+    target: Target,
+) -> String {
+    let mut defs = vec![
+        match a {
+            None => "#define POINT_A_MINUS_1".to_string(),
+            Some(limbs) => define_constant("POINT_A", limbs, target),
+        },
+        define_constant("POINT_D", d, target),
+        format!(
+            "#define POINT_ZERO {}",
+            compound(
+                "POINT_projective",
+                "FIELD_ZERO, FIELD_ONE, FIELD_ZERO, FIELD_ONE",
+                target
+            )
+        ),
+        format!(
+            "#define POINT_AFFINE_ZERO {}",
+            compound("POINT_affine", "FIELD_ZERO, FIELD_ONE", target)
+        ),
+    ];
+    defs.extend(define_scalar(scalar_bits, scalar_words));
+
+    join(
+        defs.iter()
+            .map(String::as_str)
+            .chain(vec![EDWARDS_SRC, MULTIEXP_SRC]),
+        "\n",
+    )
+    .replace("POINT", name)
+    .replace("FIELD", base)
+}
+
+fn generate<F: PrimeField, S: PrimeField, L: Limb>(
+    name: &str,
+    base: &str,
+    a: F,
+    d: F,
+    target: Target,
+) -> String {
+    let mut minus_one = F::one();
+    minus_one.negate();
+    let a = if a == minus_one {
+        None
+    } else {
+        Some(L::limbs_of(a))
+    };
+    edwards_source(
+        name,
+        base,
+        a.as_ref().map(Vec::as_slice),
+        &L::limbs_of(d),
+        S::NUM_BITS as usize,
+        mem::size_of::<S::Repr>() / 4,
+        target,
+    )
+}
+
+/// Returns OpenCL source-code of the twisted Edwards curve `a * x^2 + y^2 = 1 + d * x^2 * y^2`
+/// with name `name`, in extended coordinates, with scalars of `S` (Find details in README.md).
+/// The code of the base field `F` has to be included before, with name `base`.
+pub fn edwards<F: PrimeField, S: PrimeField, L: Limb>(
+    name: &str,
+    base: &str,
+    a: F,
+    d: F,
+) -> String {
+    generate::<F, S, L>(name, base, a, d, Target::OpenCL)
+}
+
+/// Returns CUDA source-code of the twisted Edwards curve, providing the same interface as
+/// `edwards`
+pub fn edwards_cuda<F: PrimeField, S: PrimeField, L: Limb>(
+    name: &str,
+    base: &str,
+    a: F,
+    d: F,
+) -> String {
+    generate::<F, S, L>(name, base, a, d, Target::Cuda)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{curve_driver, decode, encode, Harness};
+    use ff::{Field, SqrtField};
+    use paired::bls12_381::{Fr, FrRepr};
+    use rand::thread_rng;
+
+    // Reference implementation of the curve `a * x^2 + y^2 = 1 + d * x^2 * y^2` over Fr, in affine
+    // coordinates
+    #[derive(Clone, Copy)]
+    struct Curve {
+        a: Fr,
+        d: Fr,
+    }
+
+    type Point = [Fr; 2];
+
+    fn zero() -> Point {
+        [Fr::zero(), Fr::one()]
+    }
+
+    impl Curve {
+        // Jubjub, over the scalar field of BLS12-381
+        fn jubjub() -> Curve {
+            let mut a = Fr::one();
+            a.negate();
+            // d = -(10240/10241)
+            let mut d = Fr::from_str("10240").unwrap();
+            d.mul_assign(&Fr::from_str("10241").unwrap().inverse().unwrap());
+            d.negate();
+            Curve { a, d }
+        }
+
+        fn random_point(&self) -> Point {
+            let mut rng = thread_rng();
+            loop {
+                // x^2 = (1 - y^2) / (a - d * y^2)
+                let y = Fr::random(&mut rng);
+                let mut y2 = y;
+                y2.square();
+                let mut num = Fr::one();
+                num.sub_assign(&y2);
+                let mut den = y2;
+                den.mul_assign(&self.d);
+                den.negate();
+                den.add_assign(&self.a);
+                let mut x2 = num;
+                x2.mul_assign(&den.inverse().unwrap());
+                if let Some(x) = x2.sqrt() {
+                    return [x, y];
+                }
+            }
+        }
+
+        fn add(&self, [x1, y1]: Point, [x2, y2]: Point) -> Point {
+            let mut x1x2 = x1;
+            x1x2.mul_assign(&x2);
+            let mut y1y2 = y1;
+            y1y2.mul_assign(&y2);
+            let mut dxy = x1x2;
+            dxy.mul_assign(&y1y2);
+            dxy.mul_assign(&self.d);
+
+            // x3 = (x1 * y2 + y1 * x2) / (1 + d * x1 * x2 * y1 * y2)
+            let mut x3 = x1;
+            x3.mul_assign(&y2);
+            let mut y1x2 = y1;
+            y1x2.mul_assign(&x2);
+            x3.add_assign(&y1x2);
+            let mut den = Fr::one();
+            den.add_assign(&dxy);
+            x3.mul_assign(&den.inverse().unwrap());
+
+            // y3 = (y1 * y2 - a * x1 * x2) / (1 - d * x1 * x2 * y1 * y2)
+            let mut y3 = y1y2;
+            let mut ax1x2 = x1x2;
+            ax1x2.mul_assign(&self.a);
+            y3.sub_assign(&ax1x2);
+            let mut den = Fr::one();
+            den.sub_assign(&dxy);
+            y3.mul_assign(&den.inverse().unwrap());
+            [x3, y3]
+        }
+
+        fn mul(&self, p: Point, s: &FrRepr) -> Point {
+            let mut res = zero();
+            for i in (0..256).rev() {
+                res = self.add(res, res);
+                if (s.as_ref()[i / 64] >> (i % 64)) & 1 == 1 {
+                    res = self.add(res, p);
+                }
+            }
+            res
+        }
+    }
+
+    fn neg([mut x, y]: Point) -> Point {
+        x.negate();
+        [x, y]
+    }
+
+    // Extended coordinates of `p`, with a random `z`
+    fn extended([x, y]: Point) -> [Fr; 4] {
+        let z = Fr::random(&mut thread_rng());
+        let mut t = x;
+        t.mul_assign(&y);
+        let mut res = [x, y, t, z];
+        for c in res.iter_mut().take(3) {
+            c.mul_assign(&z);
+        }
+        res
+    }
+
+    fn normalize([x, y, t, z]: [Fr; 4]) -> Point {
+        let mut xy = x;
+        xy.mul_assign(&y);
+        let mut tz = t;
+        tz.mul_assign(&z);
+        assert_eq!(xy, tz, "Inconsistent extended coordinates");
+        let z_inv = z.inverse().unwrap();
+        let (mut x, mut y) = (x, y);
+        x.mul_assign(&z_inv);
+        y.mul_assign(&z_inv);
+        [x, y]
+    }
+
+    const SAMPLES: usize = 10;
+
+    fn check_curve<L: Limb>(name: &str, curve: Curve) {
+        let fr = FieldGenerator::<Fr>::new("Fr")
+            .limb_size(if L::bits() == 32 {
+                LimbSize::Limb32
+            } else {
+                LimbSize::Limb64
+            })
+            .generate();
+        let src = edwards::<Fr, Fr, L>(name, "Fr", curve.a, curve.d);
+        let harness = Harness::compile(name, &join(&[&fr.source, &src], "\n"), &curve_driver(name));
+
+        let mut rng = thread_rng();
+        let mut points = (0..SAMPLES)
+            .map(|_| curve.random_point())
+            .collect::<Vec<_>>();
+        points.push(zero());
+        let scalars = vec![
+            Fr::random(&mut rng).into_repr(),
+            FrRepr::from(0),
+            FrRepr::from(1),
+        ];
+        let run = |ops: Vec<String>| harness.run(&ops);
+        let projective = |res: &String| normalize(decode(res));
+
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("double {}", encode(&extended(*p))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), curve.add(*p, *p));
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("neg {}", encode(&extended(*p))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), neg(*p));
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("neg_affine {}", encode(p)))
+            .collect()))
+        {
+            assert_eq!(decode::<Point>(&res), neg(*p));
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("to_affine {}", encode(&extended(*p))))
+            .collect()))
+        {
+            assert_eq!(decode::<Point>(&res), *p);
+        }
+        for (p, res) in points.iter().zip(run(points
+            .iter()
+            .map(|p| format!("from_affine {}", encode(p)))
+            .collect()))
+        {
+            assert_eq!(projective(&res), *p);
+        }
+
+        // Includes the special cases P + P, P + (-P) and P + 0
+        let mut pairs = Vec::new();
+        for p in points.iter() {
+            let (neg, zero) = (neg(*p), zero());
+            for q in points.iter().take(3).chain(vec![p, &neg, &zero]) {
+                pairs.push((*p, *q));
+            }
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| format!("add {} {}", encode(&extended(*p)), encode(&extended(*q))))
+            .collect()))
+        {
+            assert_eq!(projective(&res), curve.add(*p, *q));
+        }
+        for ((p, q), res) in pairs.iter().zip(run(pairs
+            .iter()
+            .map(|(p, q)| format!("add_mixed {} {}", encode(&extended(*p)), encode(q)))
+            .collect()))
+        {
+            assert_eq!(projective(&res), curve.add(*p, *q));
+        }
+
+        let products = points
+            .iter()
+            .flat_map(|p| scalars.iter().map(move |s| (*p, *s)))
+            .collect::<Vec<_>>();
+        for ((p, s), res) in products.iter().zip(run(products
+            .iter()
+            .map(|(p, s)| format!("mul {} {}", encode(&extended(*p)), encode(s)))
+            .collect()))
+        {
+            assert_eq!(projective(&res), curve.mul(*p, s));
+        }
+
+        // The last group is smaller than the others
+        let exps = (0..points.len())
+            .map(|_| Fr::random(&mut rng).into_repr())
+            .collect::<Vec<_>>();
+        let (num_groups, window_size) = (4, 7);
+        let num_windows = (Fr::NUM_BITS as usize + window_size - 1) / window_size;
+        let mut expected = zero();
+        for (p, s) in points.iter().zip(exps.iter()) {
+            expected = curve.add(expected, curve.mul(*p, s));
+        }
+        let results = harness.multiexp::<_, [Fr; 4], _>(
+            &points,
+            &exps,
+            (num_groups, num_windows, window_size),
+        );
+        let mut res = zero();
+        for k in (0..num_windows).rev() {
+            for _ in 0..window_size {
+                res = curve.add(res, res);
+            }
+            for g in 0..num_groups {
+                res = curve.add(res, normalize(results[g * num_windows + k]));
+            }
+        }
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_jubjub() {
+        check_curve::<Limb32>("Jubjub32", Curve::jubjub());
+        check_curve::<Limb64>("Jubjub64", Curve::jubjub());
+    }
+
+    #[test]
+    fn test_generic_a() {
+        let mut rng = thread_rng();
+        let curve = Curve {
+            a: Fr::random(&mut rng),
+            d: Fr::random(&mut rng),
+        };
+        check_curve::<Limb64>("GenericEdwards64", curve);
+    }
+
+    #[test]
+    fn test_coefficients() {
+        let jubjub = Curve::jubjub();
+        let src = edwards_cuda::<Fr, Fr, Limb64>("Jubjub", "Fr", jubjub.a, jubjub.d);
+        assert!(src.contains("#define Jubjub_A_MINUS_1"));
+        assert!(src.contains("#define Jubjub_D (Fr{ "));
+        assert!(src.contains("#define Jubjub_SCALAR_BITS 255"));
+        let src = edwards::<Fr, Fr, Limb64>("Generic", "Fr", Fr::one(), jubjub.d);
+        assert!(src.contains("#define Generic_A ((Fr){ "));
+    }
+}
@@ -0,0 +1,505 @@
+use crate::*;
+
+static FIELD2_SRC: &str = include_str!("cl/field2.cl");
+static FIELD3_SRC: &str = include_str!("cl/field3.cl");
+
+/// Field extended by `quadratic_extension`/`cubic_extension`, either a prime-field or another
+/// extension (For building towers like Fp2 -> Fp6 -> Fp12)
+#[derive(Clone, Copy, Debug)]
+pub enum ExtensionBase<'a> {
+    Prime(&'a GeneratedField),
+    Extension(&'a GeneratedExtension),
+}
+
+impl<'a> From<&'a GeneratedField> for ExtensionBase<'a> {
+    fn from(field: &'a GeneratedField) -> Self {
+        ExtensionBase::Prime(field)
+    }
+}
+
+impl<'a> From<&'a GeneratedExtension> for ExtensionBase<'a> {
+    fn from(extension: &'a GeneratedExtension) -> Self {
+        ExtensionBase::Extension(extension)
+    }
+}
+
+/// Output of `quadratic_extension`/`cubic_extension`
+#[derive(Clone, Debug)]
+pub struct GeneratedExtension {
+    pub name: String,
+    /// Code of the extension, to be included after the code of its base
+    pub source: String,
+    /// Degree over the prime-field
+    pub degree: usize,
+    prime: Prime,
+    // Name and degree of each extension of the tower, from the prime-field up to this one
+    tower: Vec<(String, usize)>,
+}
+
+// Prime-field at the bottom of a tower
+#[derive(Clone, Debug)]
+struct Prime {
+    name: String,
+    modulus: BigUint,
+    limbs: usize,
+    limb_bits: usize,
+    target: Target,
+}
+
+impl Prime {
+    fn new(field: &GeneratedField) -> Prime {
+        assert!(
+            field.target != Target::Wgsl,
+            "Extensions of WGSL fields are not supported"
+        );
+        let p = &field.constants[&format!("{}_P", field.name)];
+        let modulus = p.iter().rev().fold(BigUint::from(0u32), |acc, limb| {
+            (acc << field.limb_bits) + BigUint::from(*limb)
+        });
+        Prime {
+            name: field.name.clone(),
+            modulus,
+            limbs: field.limbs,
+            limb_bits: field.limb_bits,
+            target: field.target,
+        }
+    }
+
+    // Montgomery form of `value`, as a literal of the generated type
+    fn literal(&self, value: &BigUint) -> String {
+        let r = BigUint::from(1u32) << (self.limbs * self.limb_bits);
+        let value = value * r % &self.modulus;
+        let limbs = if self.limb_bits == 32 {
+            let limbs = Limb32::limbs_of_biguint(&value, self.limbs);
+            join(limbs.iter().map(|l| literal(l, self.target)), ", ")
+        } else {
+            let limbs = Limb64::limbs_of_biguint(&value, self.limbs);
+            join(limbs.iter().map(|l| literal(l, self.target)), ", ")
+        };
+        compound(&self.name, &format!("{{ {} }}", limbs), self.target)
+    }
+}
+
+impl<'a> ExtensionBase<'a> {
+    fn name(&self) -> &str {
+        match self {
+            ExtensionBase::Prime(field) => &field.name,
+            ExtensionBase::Extension(extension) => &extension.name,
+        }
+    }
+
+    fn degree(&self) -> usize {
+        match self {
+            ExtensionBase::Prime(_) => 1,
+            ExtensionBase::Extension(extension) => extension.degree,
+        }
+    }
+
+    fn prime(&self) -> Prime {
+        match self {
+            ExtensionBase::Prime(field) => Prime::new(field),
+            ExtensionBase::Extension(extension) => extension.prime.clone(),
+        }
+    }
+
+    fn tower(&self) -> Vec<(String, usize)> {
+        match self {
+            ExtensionBase::Prime(_) => Vec::new(),
+            ExtensionBase::Extension(extension) => extension.tower.clone(),
+        }
+    }
+}
+
+// Literal of the element of the `tower.len()`-th extension of `prime`, with the coefficients
+// `coeffs` over the prime-field
+fn element(prime: &Prime, tower: &[(String, usize)], coeffs: &[BigUint]) -> String {
+    match tower.split_last() {
+        None => prime.literal(&coeffs[0]),
+        Some(((name, degree), tower)) => {
+            let chunk = coeffs.len() / degree;
+            let fields = coeffs
+                .chunks(chunk)
+                .map(|c| element(prime, tower, c))
+                .collect::<Vec<_>>();
+            compound(name, &join(fields, ", "), prime.target)
+        }
+    }
+}
+
+// Multiplication of an element of `base` by `non_residue`. Non-residues of the common towers
+// (e.g. BLS12-381's `u^2 = -1`, `v^3 = u + 1` and `w^2 = v`) have dedicated code, the others are
+// multiplied as constants.
+fn mul_by_nonresidue(name: &str, base: &ExtensionBase, non_residue: &[BigUint]) -> String {
+    let prime = base.prime();
+    let base_name = base.name();
+    let zero = BigUint::from(0u32);
+    let one = BigUint::from(1u32);
+    let tower = base.tower();
+
+    let mut defs = String::new();
+    let body = match (base, tower.as_slice()) {
+        (ExtensionBase::Prime(_), _) if non_residue[0] == &prime.modulus - 1u32 => {
+            format!("  return {}_neg(a);\n", base_name)
+        }
+        (ExtensionBase::Extension(_), [(_, 2)]) if non_residue.iter().all(|c| *c == one) => {
+            // (a_0 + u * a_1) * (1 + u) = a_0 + NR * a_1 + u * (a_0 + a_1)
+            format!(
+                "  {b} res;\n  res.c0 = {p}_add(a.c0, {b}_mul_by_nonresidue(a.c1));\n  \
+                 res.c1 = {p}_add(a.c0, a.c1);\n  return res;\n",
+                b = base_name,
+                p = prime.name
+            )
+        }
+        (ExtensionBase::Extension(extension), [.., (_, 3)])
+            if non_residue.iter().enumerate().all(|(i, c)| {
+                c == if i == extension.degree / 3 {
+                    &one
+                } else {
+                    &zero
+                }
+            }) =>
+        {
+            format!("  return {}_mul_by_v(a);\n", base_name)
+        }
+        _ => {
+            defs = format!(
+                "#define {}_NON_RESIDUE {}\n",
+                name,
+                element(&prime, &tower, non_residue)
+            );
+            format!("  return {}_mul(a, {}_NON_RESIDUE);\n", base_name, name)
+        }
+    };
+    format!(
+        "{}DEVICE {b} {}_mul_by_nonresidue({b} a) {{\n{}}}\n",
+        defs,
+        name,
+        body,
+        b = base_name
+    )
+}
+
+fn extension<'a, F: PrimeField>(
+    name: &str,
+    base: ExtensionBase<'a>,
+    non_residue: &[F],
+    degree: usize,
+) -> GeneratedExtension {
+    let prime = base.prime();
+    assert_eq!(
+        modulus::<F>(),
+        prime.modulus,
+        "Non-residue is not an element of {}",
+        prime.name
+    );
+    assert_eq!(
+        non_residue.len(),
+        base.degree(),
+        "Non-residue should have a coefficient per degree of {}",
+        base.name()
+    );
+    let non_residue = non_residue
+        .iter()
+        .map(|c| BigUint::new(utils::limbs_of::<_, u32>(c.into_repr())))
+        .collect::<Vec<_>>();
+
+    let base_name = base.name();
+    let zeros = vec![format!("{}_ZERO", base_name); degree - 1];
+    let constants = format!(
+        "#define {n}_ZERO {}\n#define {n}_ONE {}\n",
+        compound(
+            name,
+            &format!("{}_ZERO, {}", base_name, join(&zeros, ", ")),
+            prime.target
+        ),
+        compound(
+            name,
+            &format!("{}_ONE, {}", base_name, join(&zeros, ", ")),
+            prime.target
+        ),
+        n = name
+    );
+    let src = if degree == 2 {
+        FIELD2_SRC.replace("FIELD2", name)
+    } else {
+        FIELD3_SRC.replace("FIELD3", name)
+    };
+
+    let mut tower = base.tower();
+    tower.push((name.to_string(), degree));
+    GeneratedExtension {
+        name: name.to_string(),
+        source: join(
+            &[
+                constants,
+                mul_by_nonresidue(name, &base, &non_residue),
+                src.replace("FIELD", base_name),
+            ],
+            "\n",
+        ),
+        degree: base.degree() * degree,
+        prime,
+        tower,
+    }
+}
+
+/// Generates the quadratic extension `name` = `base[u] / (u^2 - non_residue)`. The non-residue is
+/// an element of `base`, given by its coefficients over the prime-field `F` (E.g. `[c0, c1]` when
+/// `base` is a quadratic extension itself).
+pub fn quadratic_extension<'a, F: PrimeField>(
+    name: &str,
+    base: impl Into<ExtensionBase<'a>>,
+    non_residue: &[F],
+) -> GeneratedExtension {
+    extension(name, base.into(), non_residue, 2)
+}
+
+/// Generates the cubic extension `name` = `base[v] / (v^3 - non_residue)`, see
+/// `quadratic_extension`
+pub fn cubic_extension<'a, F: PrimeField>(
+    name: &str,
+    base: impl Into<ExtensionBase<'a>>,
+    non_residue: &[F],
+) -> GeneratedExtension {
+    extension(name, base.into(), non_residue, 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{decode, encode, extension_driver, Harness};
+    use ff::Field;
+    use lazy_static::lazy_static;
+    use paired::bls12_381::{Fq, Fq12, Fq2, Fq6, Fr};
+    use rand::thread_rng;
+
+    // BLS12-381 tower, with the non-residues taken from `paired`
+    struct Tower {
+        fq: GeneratedField,
+        fq2: GeneratedExtension,
+        fq6: GeneratedExtension,
+        fq12: GeneratedExtension,
+    }
+
+    impl Tower {
+        fn new(limb_size: LimbSize) -> Tower {
+            let fq = FieldGenerator::<Fq>::new("Fq")
+                .limb_size(limb_size)
+                .generate();
+            // u^2
+            let mut u = Fq2::zero();
+            u.c1 = Fq::one();
+            u.square();
+            // v^3
+            let mut v = Fq6::zero();
+            v.c1 = Fq2::one();
+            let mut v3 = v;
+            v3.square();
+            v3.mul_assign(&v);
+            // w^2
+            let mut w = Fq12::zero();
+            w.c1 = Fq6::one();
+            w.square();
+            let w2 = w.c0;
+
+            let fq2 = quadratic_extension("Fq2", &fq, &[u.c0]);
+            let fq6 = cubic_extension("Fq6", &fq2, &[v3.c0.c0, v3.c0.c1]);
+            let fq12 = quadratic_extension(
+                "Fq12",
+                &fq6,
+                &[w2.c0.c0, w2.c0.c1, w2.c1.c0, w2.c1.c1, w2.c2.c0, w2.c2.c1],
+            );
+            Tower { fq, fq2, fq6, fq12 }
+        }
+
+        // Harness of the `level`-th extension of the tower
+        fn harness(&self, name: &str, level: usize) -> Harness {
+            let extensions = [&self.fq2, &self.fq6, &self.fq12];
+            let src = Some(self.fq.source.clone())
+                .into_iter()
+                .chain(extensions[..level].iter().map(|e| e.source.clone()))
+                .collect::<Vec<_>>();
+            let ext = extensions[level - 1];
+            Harness::compile(name, &join(src, "\n"), &extension_driver(&ext.name))
+        }
+    }
+
+    lazy_static! {
+        static ref TOWER32: Tower = Tower::new(LimbSize::Limb32);
+        static ref TOWER64: Tower = Tower::new(LimbSize::Limb64);
+        static ref FQ2: [Harness; 2] = [TOWER32.harness("Fq2_32", 1), TOWER64.harness("Fq2_64", 1)];
+        static ref FQ6: [Harness; 2] = [TOWER32.harness("Fq6_32", 2), TOWER64.harness("Fq6_64", 2)];
+        static ref FQ12: [Harness; 2] =
+            [TOWER32.harness("Fq12_32", 3), TOWER64.harness("Fq12_64", 3)];
+    }
+
+    const SAMPLES: usize = 20;
+
+    /// Checks `op` of the generated code against `f` on random inputs
+    fn check<F: Field>(harnesses: &[Harness], op: &str, arity: usize, f: impl Fn(&[F]) -> F) {
+        let mut rng = thread_rng();
+        let inputs = (0..SAMPLES)
+            .map(|_| (0..arity).map(|_| F::random(&mut rng)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let ops = inputs
+            .iter()
+            .map(|args| {
+                join(
+                    Some(op.to_string())
+                        .into_iter()
+                        .chain(args.iter().map(encode)),
+                    " ",
+                )
+            })
+            .collect::<Vec<_>>();
+        for h in harnesses {
+            for (out, args) in h.run(&ops).iter().zip(inputs.iter()) {
+                assert_eq!(decode::<F>(out), f(args), "{}", op);
+            }
+        }
+    }
+
+    fn check_extension<F: Field>(harnesses: &[Harness]) {
+        for h in harnesses {
+            assert_eq!(h.call::<F>("one".to_string()), F::one());
+        }
+        check::<F>(harnesses, "add", 2, |v| {
+            let mut c = v[0];
+            c.add_assign(&v[1]);
+            c
+        });
+        check::<F>(harnesses, "sub", 2, |v| {
+            let mut c = v[0];
+            c.sub_assign(&v[1]);
+            c
+        });
+        check::<F>(harnesses, "mul", 2, |v| {
+            let mut c = v[0];
+            c.mul_assign(&v[1]);
+            c
+        });
+        check::<F>(harnesses, "sqr", 1, |v| {
+            let mut c = v[0];
+            c.square();
+            c
+        });
+        check::<F>(harnesses, "double", 1, |v| {
+            let mut c = v[0];
+            c.double();
+            c
+        });
+        check::<F>(harnesses, "neg", 1, |v| {
+            let mut c = v[0];
+            c.negate();
+            c
+        });
+        check::<F>(harnesses, "inv", 1, |v| v[0].inverse().unwrap());
+    }
+
+    #[test]
+    fn test_fq2() {
+        check_extension::<Fq2>(&*FQ2);
+    }
+
+    #[test]
+    fn test_fq6() {
+        check_extension::<Fq6>(&*FQ6);
+    }
+
+    #[test]
+    fn test_fq12() {
+        check_extension::<Fq12>(&*FQ12);
+    }
+
+    #[test]
+    fn test_nonresidues() {
+        // The BLS12-381 tower only uses the dedicated multiplications
+        assert!(TOWER64.fq2.source.contains("return Fq_neg(a);"));
+        assert!(TOWER64.fq6.source.contains("Fq2_mul_by_nonresidue(a.c1)"));
+        assert!(TOWER64.fq12.source.contains("return Fq6_mul_by_v(a);"));
+        for ext in &[&TOWER64.fq2, &TOWER64.fq6, &TOWER64.fq12] {
+            assert!(!ext.source.contains("NON_RESIDUE"));
+        }
+    }
+
+    // Multiplies `a` and `b` of `base[x] / (x^n - non_residue)` by schoolbook multiplication
+    fn schoolbook<F: Field>(a: &[F], b: &[F], non_residue: &F) -> Vec<F> {
+        let n = a.len();
+        let mut res = vec![F::zero(); n];
+        for i in 0..n {
+            for j in 0..n {
+                let mut t = a[i];
+                t.mul_assign(&b[j]);
+                if i + j >= n {
+                    t.mul_assign(non_residue);
+                }
+                res[(i + j) % n].add_assign(&t);
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_generic_nonresidue() {
+        let mut rng = thread_rng();
+
+        // Quadratic extension of Fr by its multiplicative generator
+        let fr = FieldGenerator::<Fr>::new("Fr").generate();
+        let g = Fr::multiplicative_generator();
+        let fr2 = quadratic_extension("Fr2", &fr, &[g]);
+        assert!(fr2.source.contains("#define Fr2_NON_RESIDUE"));
+        let h = Harness::compile(
+            "Fr2",
+            &join(&[&fr.source, &fr2.source], "\n"),
+            &extension_driver("Fr2"),
+        );
+        for _ in 0..SAMPLES {
+            let a = [Fr::random(&mut rng), Fr::random(&mut rng)];
+            let b = [Fr::random(&mut rng), Fr::random(&mut rng)];
+            let ab = schoolbook(&a, &b, &g);
+            let out = h.call::<[Fr; 2]>(format!("mul {} {}", encode(&a), encode(&b)));
+            assert_eq!(&out[..], &ab[..]);
+            let aa = schoolbook(&a, &a, &g);
+            assert_eq!(
+                &h.call::<[Fr; 2]>(format!("sqr {}", encode(&a)))[..],
+                &aa[..]
+            );
+            let inv = h.call::<[Fr; 2]>(format!("inv {}", encode(&a)));
+            assert_eq!(schoolbook(&a, &inv, &g), vec![Fr::one(), Fr::zero()]);
+        }
+
+        // Cubic extension of Fq2 by an arbitrary element
+        let tower = &*TOWER32;
+        let xi = Fq2 {
+            c0: Fq::multiplicative_generator(),
+            c1: Fq::one(),
+        };
+        let fq6 = cubic_extension("Fq6", &tower.fq2, &[xi.c0, xi.c1]);
+        assert!(fq6.source.contains("#define Fq6_NON_RESIDUE"));
+        let h = Harness::compile(
+            "Fq6Generic",
+            &join(&[&tower.fq.source, &tower.fq2.source, &fq6.source], "\n"),
+            &extension_driver("Fq6"),
+        );
+        for _ in 0..SAMPLES {
+            let a = [
+                Fq2::random(&mut rng),
+                Fq2::random(&mut rng),
+                Fq2::random(&mut rng),
+            ];
+            let b = [
+                Fq2::random(&mut rng),
+                Fq2::random(&mut rng),
+                Fq2::random(&mut rng),
+            ];
+            let ab = schoolbook(&a, &b, &xi);
+            let out = h.call::<[Fq2; 3]>(format!("mul {} {}", encode(&a), encode(&b)));
+            assert_eq!(&out[..], &ab[..]);
+            let aa = schoolbook(&a, &a, &xi);
+            assert_eq!(
+                &h.call::<[Fq2; 3]>(format!("sqr {}", encode(&a)))[..],
+                &aa[..]
+            );
+        }
+    }
+}
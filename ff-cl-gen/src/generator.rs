@@ -0,0 +1,766 @@
+use crate::*;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Version of the generator, part of the `Metadata` of the generated code
+pub const GENERATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Language of the generated code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    OpenCL,
+    /// CUDA C++, built with NVRTC/nvcc
+    Cuda,
+    /// WGSL compute shaders, with 32-bit limbs only
+    Wgsl,
+}
+
+/// Size of the limbs of the generated code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimbSize {
+    Limb32,
+    Limb64,
+}
+
+/// How the helpers of `cl/common.cl` (And the CUDA prelude) are emitted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommonHelpers {
+    /// Included in the source behind an include guard, so only the first copy in a program is
+    /// used. All the fields of a program should then be generated with the same options.
+    Guarded,
+    /// Included in the source, with the helpers prefixed by the name of the field
+    Prefixed,
+    /// Left out of the source, `GeneratedField::common` should be emitted once per program
+    Omitted,
+}
+
+// Functions of `cl/common.cl`
+const HELPERS: [&str; 4] = [
+    "mac_with_carry_64",
+    "add_with_carry_64",
+    "mac_with_carry_32",
+    "add_with_carry_32",
+];
+
+/// Builder for the code of a prime-field `F`. The defaults are the same as `field()`.
+///
+/// ```ignore
+/// let fr = FieldGenerator::<Fr>::new("Fr")
+///     .limb_size(LimbSize::Limb32)
+///     .debug(false)
+///     .generate();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FieldGenerator<F: PrimeField> {
+    name: String,
+    limb_size: LimbSize,
+    target: Target,
+    debug: bool,
+    vendor_asm: bool,
+    common_helpers: CommonHelpers,
+    minimal_limbs: bool,
+    karatsuba: bool,
+    constant_time: bool,
+    pow_constants: Vec<(String, Vec<u64>)>,
+    _phantom: PhantomData<F>,
+}
+
+/// Output of `FieldGenerator`
+#[derive(Clone, Debug)]
+pub struct GeneratedField {
+    pub name: String,
+    /// Code of the field, includes `common` unless `CommonHelpers::Omitted` is used
+    pub source: String,
+    /// Helpers shared by all the fields of a program
+    pub common: String,
+    /// Values of the constants defined in `source` (e.g. `Fr_P`), as least significant limb
+    /// first
+    pub constants: BTreeMap<String, Vec<u64>>,
+    pub limbs: usize,
+    pub limb_bits: usize,
+    pub target: Target,
+    /// Hex-encoded SHA-256 of `source`
+    pub digest: String,
+    pub metadata: Metadata,
+}
+
+/// Everything that determines the code of a field, e.g. for keying caches of compiled programs or
+/// logging which code ran
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub generator_version: &'static str,
+    pub name: String,
+    /// Least significant limb first
+    pub modulus: Vec<u64>,
+    pub limbs: usize,
+    pub limb_bits: usize,
+    pub target: Target,
+    pub debug: bool,
+    pub vendor_asm: bool,
+    pub common_helpers: CommonHelpers,
+    pub minimal_limbs: bool,
+    pub karatsuba: bool,
+    pub constant_time: bool,
+    /// Names and exponents of the `FIELD_pow_{name}` functions
+    pub pow_constants: Vec<(String, Vec<u64>)>,
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let modulus = self
+            .modulus
+            .iter()
+            .rev()
+            .fold(BigUint::from(0u32), |acc, limb| {
+                (acc << self.limb_bits) + BigUint::from(*limb)
+            });
+        write!(
+            f,
+            "{} (ff-cl-gen {}): modulus 0x{:x}, {} {}-bit limbs, {:?}, debug: {}, vendor_asm: {}, \
+             common_helpers: {:?}, minimal_limbs: {}, karatsuba: {}, constant_time: {}",
+            self.name,
+            self.generator_version,
+            modulus,
+            self.limbs,
+            self.limb_bits,
+            self.target,
+            self.debug,
+            self.vendor_asm,
+            self.common_helpers,
+            self.minimal_limbs,
+            self.karatsuba,
+            self.constant_time
+        )?;
+        for (name, exponent) in self.pow_constants.iter() {
+            write!(f, ", pow_{}: {:x?}", name, exponent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hex-encoded SHA-256 of `source`, for identifying a program made of several generated sources
+pub fn digest(source: &str) -> String {
+    Sha256::digest(source.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl<F: PrimeField> FieldGenerator<F> {
+    pub fn new(name: &str) -> Self {
+        FieldGenerator {
+            name: name.to_string(),
+            limb_size: LimbSize::Limb64,
+            target: Target::OpenCL,
+            debug: true,
+            vendor_asm: true,
+            common_helpers: CommonHelpers::Guarded,
+            minimal_limbs: false,
+            karatsuba: false,
+            constant_time: false,
+            pow_constants: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn limb_size(mut self, limb_size: LimbSize) -> Self {
+        self.limb_size = limb_size;
+        self
+    }
+
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Emit `FIELD_print`, which needs `printf` support of the driver
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Use inline PTX assembly on NVIDIA devices
+    pub fn vendor_asm(mut self, vendor_asm: bool) -> Self {
+        self.vendor_asm = vendor_asm;
+        self
+    }
+
+    pub fn common_helpers(mut self, common_helpers: CommonHelpers) -> Self {
+        self.common_helpers = common_helpers;
+        self
+    }
+
+    /// Use as few limbs as the modulus needs, instead of the size of `F::Repr` (`fff` always
+    /// leaves a spare bit in the top limb, e.g. a 256-bit modulus gets five 64-bit limbs). The
+    /// Montgomery form then differs from the one of `F`, so elements have to be passed in
+    /// regular form and converted with `FIELD_mont`/`FIELD_unmont`.
+    pub fn minimal_limbs(mut self, minimal_limbs: bool) -> Self {
+        self.minimal_limbs = minimal_limbs;
+        self
+    }
+
+    /// Multiply with a level of Karatsuba, followed by a Montgomery reduction, instead of the
+    /// interleaved (CIOS) Montgomery multiplication. Pays off for large fields, e.g. the 753-bit
+    /// ones of MNT4/MNT6. The PTX assembly of the multiplication isn't used then.
+    pub fn karatsuba(mut self, karatsuba: bool) -> Self {
+        self.karatsuba = karatsuba;
+        self
+    }
+
+    /// Don't branch on (Or index memory with) the values of the elements, for when the timing of
+    /// the device could leak secrets (e.g. witnesses, on a shared GPU). Conditional subtractions
+    /// of the modulus become masked selections, and `FIELD_pow`/`FIELD_pow_field` always go
+    /// through the whole exponent. `FIELD_sqrt`, `FIELD_from_repr` and `FIELD_batch_inv` are
+    /// still variable-time.
+    pub fn constant_time(mut self, constant_time: bool) -> Self {
+        self.constant_time = constant_time;
+        self
+    }
+
+    /// Also emit `FIELD_pow_{name}(FIELD base)`, raising `base` to the constant `exponent` (Least
+    /// significant limb first). Faster than `FIELD_pow_field`, as the exponent is decomposed at
+    /// generation time. Panics if the name is given twice, or if `FIELD_pow_{name}` is already
+    /// one of the functions of the field (`field` or `lookup`).
+    pub fn pow_constant(mut self, name: &str, exponent: &[u64]) -> Self {
+        assert!(
+            !FIELD_SRC.contains(&format!("FIELD_pow_{}(", name)),
+            "FIELD_pow_{} is already defined by the field",
+            name
+        );
+        assert!(
+            self.pow_constants.iter().all(|(n, _)| n != name),
+            "Exponent {} is given twice",
+            name
+        );
+        self.pow_constants
+            .push((name.to_string(), exponent.to_vec()));
+        self
+    }
+
+    pub fn generate(&self) -> GeneratedField {
+        match self.limb_size {
+            LimbSize::Limb32 => generate::<F, Limb32>(self),
+            LimbSize::Limb64 => generate::<F, Limb64>(self),
+        }
+    }
+}
+
+/// Generates the code with limbs of type `L`, ignoring `options.limb_size`
+pub(crate) fn generate<F, L: Limb>(options: &FieldGenerator<F>) -> GeneratedField
+where
+    F: PrimeField,
+{
+    let limbs = if options.minimal_limbs {
+        (modulus::<F>().bits() + L::bits() - 1) / L::bits()
+    } else {
+        L::limbs_of(F::one()).len()
+    };
+    let constants = constants::<F, L>(limbs);
+    // Without a spare bit, the carries of additions can't be ignored
+    let no_spare_bit = modulus::<F>().bits() == limbs * L::bits();
+
+    let (source, common) = if options.target == Target::Wgsl {
+        assert!(
+            !options.karatsuba && !options.constant_time && options.pow_constants.is_empty(),
+            "Karatsuba multiplication, constant-time code and constant powers aren't supported in \
+             WGSL"
+        );
+        (wgsl::field_source(&constants, no_spare_bit), String::new())
+    } else {
+        source::<F, L>(options, limbs, &constants, no_spare_bit)
+    };
+
+    let source = source.replace("FIELD", &options.name);
+    let metadata = Metadata {
+        generator_version: GENERATOR_VERSION,
+        name: options.name.clone(),
+        modulus: L::limbs_of_biguint(&modulus::<F>(), limbs)
+            .iter()
+            .map(|l| l.value().into())
+            .collect(),
+        limbs,
+        limb_bits: L::bits(),
+        target: options.target,
+        debug: options.debug,
+        vendor_asm: options.vendor_asm,
+        common_helpers: options.common_helpers,
+        minimal_limbs: options.minimal_limbs,
+        karatsuba: options.karatsuba,
+        constant_time: options.constant_time,
+        pow_constants: options.pow_constants.clone(),
+    };
+
+    GeneratedField {
+        name: options.name.clone(),
+        digest: digest(&source),
+        source,
+        common: common.replace("FIELD", &options.name),
+        constants: constants
+            .into_iter()
+            .map(|(name, limbs)| {
+                (
+                    name.replace("FIELD", &options.name),
+                    limbs.iter().map(|l| l.value().into()).collect(),
+                )
+            })
+            .collect(),
+        limbs,
+        limb_bits: L::bits(),
+        target: options.target,
+        metadata,
+    }
+}
+
+/// OpenCL/CUDA code of the field and of the common helpers
+fn source<F, L: Limb>(
+    options: &FieldGenerator<F>,
+    limbs: usize,
+    constants: &[(&'static str, Vec<L>)],
+    no_spare_bit: bool,
+) -> (String, String)
+where
+    F: PrimeField,
+{
+    let prefixed = options.common_helpers == CommonHelpers::Prefixed;
+    let prefix_helpers = |src: &str| {
+        HELPERS.iter().fold(src.to_string(), |src, helper| {
+            src.replace(helper, &format!("FIELD_{}", helper))
+        })
+    };
+
+    let mut common = String::new();
+    if options.target == Target::Cuda {
+        common.push_str(CUDA_SRC);
+        common.push('\n');
+    }
+    if !options.vendor_asm {
+        common.push_str("#define COMMON_NO_ASM\n");
+    }
+    common.push_str(COMMON_SRC);
+    if prefixed {
+        common = prefix_helpers(&common).replace("COMMON_", "FIELD_COMMON_");
+    }
+
+    let mut options_defs = Vec::new();
+    if no_spare_bit {
+        options_defs.push("#define FIELD_NO_SPARE_BIT".to_string());
+    }
+    if options.debug {
+        options_defs.push("#define FIELD_DEBUG".to_string());
+    }
+    if !options.vendor_asm {
+        options_defs.push("#define FIELD_NO_ASM".to_string());
+    }
+    if options.karatsuba {
+        options_defs.push("#define FIELD_KARATSUBA".to_string());
+    }
+    if options.constant_time {
+        options_defs.push("#define FIELD_CONSTANT_TIME".to_string());
+    }
+
+    let mut parts = Vec::new();
+    if options.common_helpers != CommonHelpers::Omitted {
+        parts.push(common.clone());
+    }
+    parts.push(params::<L>(constants.to_vec(), options.target));
+    parts.extend(options_defs);
+    if options.vendor_asm {
+        parts.push(nvidia::field_add_sub_nvidia::<L>(limbs));
+        if !options.karatsuba {
+            parts.push(nvidia::field_mul_nvidia::<F, L>(limbs));
+        }
+        parts.push(nvidia::field_sqr_nvidia::<L>(limbs));
+    }
+    parts.push(if prefixed {
+        prefix_helpers(FIELD_SRC)
+    } else {
+        FIELD_SRC.to_string()
+    });
+    for (name, exponent) in options.pow_constants.iter() {
+        let exponent = BigUint::new(
+            exponent
+                .iter()
+                .flat_map(|l| vec![*l as u32, (*l >> 32) as u32])
+                .collect(),
+        );
+        parts.push(pow::field_pow_constant(name, &exponent));
+    }
+
+    (join(&parts, "\n"), common)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{decode, driver, encode, Harness};
+    use crate::secp256k1::Secp256k1Fq;
+    use ff::{Field, PrimeField};
+    use paired::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn test_defaults() {
+        let fr = FieldGenerator::<Fr>::new("Fr").generate();
+        assert_eq!(fr.source, field::<Fr, Limb64>("Fr"));
+        assert_eq!((fr.limbs, fr.limb_bits), (4, 64));
+        let fr = FieldGenerator::<Fr>::new("Fr")
+            .limb_size(LimbSize::Limb32)
+            .target(Target::Cuda)
+            .generate();
+        assert_eq!(fr.source, field_cuda::<Fr, Limb32>("Fr"));
+        assert_eq!((fr.limbs, fr.limb_bits), (8, 32));
+    }
+
+    #[test]
+    fn test_options() {
+        let fr = FieldGenerator::<Fr>::new("Fr")
+            .debug(false)
+            .vendor_asm(false)
+            .generate();
+        assert!(!fr.source.contains("#define Fr_DEBUG"));
+        assert!(fr.source.contains("#define Fr_NO_ASM"));
+        assert!(fr.source.contains("#define COMMON_NO_ASM"));
+        assert!(!fr.source.contains("_nvidia("));
+
+        let fr = FieldGenerator::<Fr>::new("Fr")
+            .common_helpers(CommonHelpers::Omitted)
+            .generate();
+        assert!(!fr.source.contains(&fr.common));
+        assert!(fr.common.contains("mac_with_carry_64"));
+    }
+
+    #[test]
+    fn test_constants() {
+        let fr = FieldGenerator::<Fr>::new("Fr").generate();
+        assert_eq!(fr.constants["Fr_P"], Fr::char().as_ref());
+        let one = Limb64::limbs_of(Fr::one());
+        assert_eq!(
+            fr.constants["Fr_ONE"],
+            one.iter().map(|l| l.value()).collect::<Vec<_>>()
+        );
+        assert_eq!(fr.constants["Fr_INV"], vec![0xfffffffeffffffff]);
+    }
+
+    #[test]
+    fn test_metadata() {
+        assert_eq!(
+            digest(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let fr = FieldGenerator::<Fr>::new("Fr").generate();
+        assert_eq!(fr.digest, digest(&fr.source));
+        assert_eq!(fr.digest, FieldGenerator::<Fr>::new("Fr").generate().digest);
+        assert_eq!(fr.metadata.generator_version, GENERATOR_VERSION);
+        assert_eq!(fr.metadata.modulus, Fr::char().as_ref());
+        assert_eq!((fr.metadata.limbs, fr.metadata.limb_bits), (4, 64));
+        assert!(fr
+            .metadata
+            .to_string()
+            .starts_with("Fr (ff-cl-gen 0.2.0): modulus 0x73eda753"));
+
+        let fr32 = FieldGenerator::<Fr>::new("Fr")
+            .limb_size(LimbSize::Limb32)
+            .karatsuba(true)
+            .generate();
+        assert_ne!(fr32.digest, fr.digest);
+        assert_eq!(fr32.metadata.modulus.len(), 8);
+        assert!(fr32.metadata.karatsuba);
+        assert_eq!(
+            fr32.metadata.to_string()[..60],
+            fr.metadata.to_string()[..60]
+        );
+    }
+
+    // Both fields are built into a single program, the driver only exercises `Fr`
+    fn compile_pair(name: &str, common_helpers: CommonHelpers, limb_size: LimbSize) -> Harness {
+        let fr = FieldGenerator::<Fr>::new("Fr")
+            .limb_size(limb_size)
+            .common_helpers(common_helpers)
+            .generate();
+        let fq = FieldGenerator::<Fq>::new("Fq")
+            .common_helpers(common_helpers)
+            .generate();
+        let mut src = vec![fr.source, fq.source];
+        if common_helpers == CommonHelpers::Omitted {
+            src.insert(0, fr.common);
+        }
+        Harness::compile(name, &join(&src, "\n"), &driver("Fr"))
+    }
+
+    #[test]
+    fn test_common_helpers() {
+        let mut rng = rand::thread_rng();
+        let a = Fr::random(&mut rng);
+        let mut b = a;
+        b.square();
+        for (i, common_helpers) in [CommonHelpers::Prefixed, CommonHelpers::Omitted]
+            .iter()
+            .enumerate()
+        {
+            let h = compile_pair(&format!("pair{}", i), *common_helpers, LimbSize::Limb32);
+            let op = format!("sqr {}", encode(&a));
+            assert_eq!(h.call::<Fr>(op), b);
+        }
+    }
+
+    #[test]
+    fn test_pow_constant() {
+        let mut rng = rand::thread_rng();
+        let p_minus_2 = Fr::char()
+            .as_ref()
+            .iter()
+            .enumerate()
+            .map(|(i, l)| if i == 0 { l - 2 } else { *l })
+            .collect::<Vec<_>>();
+        let random = Fr::random(&mut rng).into_repr().as_ref().to_vec();
+        let exponents = [
+            vec![0],
+            vec![1],
+            vec![2],
+            vec![0x8001],
+            vec![u64::max_value(), 1],
+            p_minus_2,
+            random,
+        ];
+        for (i, exponent) in exponents.iter().enumerate() {
+            for limb_size in &[LimbSize::Limb32, LimbSize::Limb64] {
+                let fr = FieldGenerator::<Fr>::new("Fr")
+                    .limb_size(*limb_size)
+                    .pow_constant("e", exponent)
+                    .generate();
+                assert!(fr.source.contains("Fr_pow_e(Fr base)"));
+                let src = format!("{}\n#define Fr_POW_CONSTANT Fr_pow_e", fr.source);
+                let name = format!("pow_constant{}_{}", i, fr.limb_bits);
+                let h = Harness::compile(&name, &src, &driver("Fr"));
+                for _ in 0..10 {
+                    let a = Fr::random(&mut rng);
+                    let op = format!("pow_constant {}", encode(&a));
+                    assert_eq!(h.call::<Fr>(op), a.pow(exponent));
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "FIELD_pow_lookup is already defined by the field")]
+    fn test_pow_constant_reserved() {
+        FieldGenerator::<Fr>::new("Fr").pow_constant("lookup", &[3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exponent e is given twice")]
+    fn test_pow_constant_duplicate() {
+        FieldGenerator::<Fr>::new("Fr")
+            .pow_constant("e", &[3])
+            .pow_constant("e", &[5]);
+    }
+
+    #[test]
+    fn test_karatsuba() {
+        let mut rng = rand::thread_rng();
+        for limb_size in &[LimbSize::Limb32, LimbSize::Limb64] {
+            let fr = FieldGenerator::<Fr>::new("Fr")
+                .limb_size(*limb_size)
+                .karatsuba(true)
+                .generate();
+            assert!(fr.source.contains("#define Fr_KARATSUBA"));
+            assert!(!fr.source.contains("Fr Fr_mul_nvidia("));
+            let fq = FieldGenerator::<Fq>::new("Fq")
+                .limb_size(*limb_size)
+                .karatsuba(true)
+                .generate();
+            // Five 64-bit limbs, the halves have different sizes
+            let secp = FieldGenerator::<Secp256k1Fq>::new("Secp")
+                .limb_size(*limb_size)
+                .karatsuba(true)
+                .generate();
+
+            macro_rules! check_mul {
+                ($field:ident, $generated:expr) => {
+                    let name = format!("karatsuba_{}{}", $generated.name, $generated.limb_bits);
+                    let h = Harness::compile(&name, &$generated.source, &driver(&$generated.name));
+                    let mut minus_one = $field::one();
+                    minus_one.negate();
+                    let mut samples = vec![$field::zero(), $field::one(), minus_one];
+                    samples.extend((0..20).map(|_| $field::random(&mut rng)));
+                    for (a, b) in samples.iter().zip(samples.iter().rev()) {
+                        let mut c = *a;
+                        c.mul_assign(b);
+                        let op = format!("mul {} {}", encode(a), encode(b));
+                        assert_eq!(h.call::<$field>(op), c);
+                    }
+                };
+            }
+            check_mul!(Fr, fr);
+            check_mul!(Fq, fq);
+            check_mul!(Secp256k1Fq, secp);
+        }
+    }
+
+    // Runs the same operations on the variable-time and the constant-time code of a field
+    fn check_constant_time(
+        label: &str,
+        variable: GeneratedField,
+        constant: GeneratedField,
+        inputs: &[String],
+    ) {
+        let define = format!("#define {}_CONSTANT_TIME", variable.name);
+        assert!(!variable.source.contains(&define));
+        assert!(constant.source.contains(&define));
+        assert!(constant.metadata.constant_time);
+        assert_ne!(constant.digest, variable.digest);
+
+        let mut ops = Vec::new();
+        for (i, a) in inputs.iter().enumerate() {
+            let b = &inputs[(i + 1) % inputs.len()];
+            for op in &["add", "sub", "mul"] {
+                ops.push(format!("{} {} {}", op, a, b));
+                ops.push(format!("{} {} {}", op, a, a));
+            }
+            for op in &["sqr", "double", "neg", "half", "inv", "legendre", "is_zero"] {
+                ops.push(format!("{} {}", op, a));
+            }
+            ops.push(format!(
+                "pow {} {}",
+                a,
+                [0, 1, 0x8000_0001, u32::max_value()][i % 4]
+            ));
+            ops.push(format!("pow_field {} {}", a, b));
+            ops.push(format!("get_bits {} {} {}", a, (i * 37) % 200, 1 + i % 16));
+        }
+
+        let results = [variable, constant]
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let name = format!("constant_time_{}{}_{}", label, field.limb_bits, i);
+                Harness::compile(&name, &field.source, &driver(&field.name)).run(&ops)
+            })
+            .collect::<Vec<_>>();
+        for (op, (v, c)) in ops.iter().zip(results[0].iter().zip(results[1].iter())) {
+            assert_eq!(v, c, "Results of `{}` differ", op);
+        }
+    }
+
+    #[test]
+    fn test_constant_time() {
+        let mut rng = rand::thread_rng();
+        for limb_size in &[LimbSize::Limb32, LimbSize::Limb64] {
+            let generator = FieldGenerator::<Fr>::new("Fr").limb_size(*limb_size);
+            let mut minus_one = Fr::one();
+            minus_one.negate();
+            let mut samples = vec![Fr::zero(), Fr::one(), minus_one];
+            samples.extend((0..10).map(|_| Fr::random(&mut rng)));
+            let inputs = samples.iter().map(encode).collect::<Vec<_>>();
+            check_constant_time(
+                "fr",
+                generator.generate(),
+                generator.clone().constant_time(true).generate(),
+                &inputs,
+            );
+            let karatsuba = generator.karatsuba(true);
+            check_constant_time(
+                "fr_karatsuba",
+                karatsuba.generate(),
+                karatsuba.constant_time(true).generate(),
+                &inputs,
+            );
+
+            // Without a spare bit, the carries are also selected
+            let generator = FieldGenerator::<Secp256k1Fq>::new("Secp")
+                .limb_size(*limb_size)
+                .minimal_limbs(true);
+            let mut minus_one = Secp256k1Fq::one();
+            minus_one.negate();
+            let mut samples = vec![Secp256k1Fq::zero(), Secp256k1Fq::one(), minus_one];
+            samples.extend((0..10).map(|_| Secp256k1Fq::random(&mut rng)));
+            let inputs = samples.iter().map(secp_encode).collect::<Vec<_>>();
+            check_constant_time(
+                "secp",
+                generator.generate(),
+                generator.clone().constant_time(true).generate(),
+                &inputs,
+            );
+            let karatsuba = generator.karatsuba(true);
+            check_constant_time(
+                "secp_karatsuba",
+                karatsuba.generate(),
+                karatsuba.constant_time(true).generate(),
+                &inputs,
+            );
+        }
+    }
+
+    // Regular form of `a`, cut to the four 64-bit limbs of the generated field
+    fn secp_encode(a: &Secp256k1Fq) -> String {
+        encode(&a.into_repr())[..64].to_string()
+    }
+
+    fn secp_decode(hex: &str) -> Secp256k1Fq {
+        Secp256k1Fq::from_repr(decode(&format!("{:0<80}", hex))).unwrap()
+    }
+
+    // The Montgomery form of the generated field differs from the one of `Secp256k1Fq`, so the
+    // operands are converted with `FIELD_mont` and the result with `FIELD_unmont`
+    fn secp_call(h: &Harness, op: &str, inputs: &[Secp256k1Fq]) -> Secp256k1Fq {
+        let monts = h.run(
+            &inputs
+                .iter()
+                .map(|a| format!("mont {}", secp_encode(a)))
+                .collect::<Vec<_>>(),
+        );
+        let result = h.run(&[format!("{} {}", op, join(&monts, " "))]);
+        secp_decode(&h.run(&[format!("unmont {}", result[0])])[0])
+    }
+
+    #[test]
+    fn test_minimal_limbs() {
+        let mut rng = rand::thread_rng();
+        let mut minus_one = Secp256k1Fq::one();
+        minus_one.negate();
+        let mut samples = vec![Secp256k1Fq::zero(), Secp256k1Fq::one(), minus_one];
+        samples.extend((0..20).map(|_| Secp256k1Fq::random(&mut rng)));
+        let mut two = Secp256k1Fq::one();
+        two.double();
+        let half = two.inverse().unwrap();
+
+        for limb_size in &[LimbSize::Limb32, LimbSize::Limb64] {
+            let secp = FieldGenerator::<Secp256k1Fq>::new("Secp")
+                .limb_size(*limb_size)
+                .minimal_limbs(true)
+                .generate();
+            assert_eq!(secp.limbs * secp.limb_bits, 256);
+            assert!(secp.source.contains("#define Secp_NO_SPARE_BIT"));
+            let name = format!("secp{}", secp.limb_bits);
+            let h = Harness::compile(&name, &secp.source, &driver("Secp"));
+
+            for (a, b) in samples.iter().zip(samples.iter().rev()) {
+                let mut c = *a;
+                c.add_assign(b);
+                assert_eq!(secp_call(&h, "add", &[*a, *b]), c);
+                let mut c = *a;
+                c.sub_assign(b);
+                assert_eq!(secp_call(&h, "sub", &[*a, *b]), c);
+                let mut c = *a;
+                c.mul_assign(b);
+                assert_eq!(secp_call(&h, "mul", &[*a, *b]), c);
+                let mut c = *a;
+                c.square();
+                assert_eq!(secp_call(&h, "sqr", &[*a]), c);
+                let mut c = *a;
+                c.double();
+                assert_eq!(secp_call(&h, "double", &[*a]), c);
+                let c = a.inverse().unwrap_or_else(Secp256k1Fq::zero);
+                assert_eq!(secp_call(&h, "inv", &[*a]), c);
+                let mut c = *a;
+                c.negate();
+                assert_eq!(secp_call(&h, "neg", &[*a]), c);
+                let mut c = *a;
+                c.mul_assign(&half);
+                assert_eq!(secp_call(&h, "half", &[*a]), c);
+            }
+        }
+    }
+}
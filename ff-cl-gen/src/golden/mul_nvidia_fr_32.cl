@@ -0,0 +1,360 @@
+#ifdef NVIDIA
+DEVICE FIELD FIELD_mul_nvidia(FIELD a, FIELD b) {
+FIELD_limb t[2 * FIELD_LIMBS + 1] = {0};
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[0]), "+r"(t[1]), "+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9])
+:"r"(b.val[0]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[1]), "+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10])
+:"r"(b.val[1]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11])
+:"r"(b.val[2]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12])
+:"r"(b.val[3]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13])
+:"r"(b.val[4]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13]), "+r"(t[14])
+:"r"(b.val[5]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13]), "+r"(t[14]), "+r"(t[15])
+:"r"(b.val[6]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("{\r\n"
+".reg .u32 m;\r\n"
+"mad.lo.cc.u32 %0, %10, %11, %0;\r\n"
+"madc.lo.cc.u32 %1, %10, %12, %1;\r\n"
+"madc.lo.cc.u32 %2, %10, %13, %2;\r\n"
+"madc.lo.cc.u32 %3, %10, %14, %3;\r\n"
+"madc.lo.cc.u32 %4, %10, %15, %4;\r\n"
+"madc.lo.cc.u32 %5, %10, %16, %5;\r\n"
+"madc.lo.cc.u32 %6, %10, %17, %6;\r\n"
+"madc.lo.cc.u32 %7, %10, %18, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, %10, %11, %1;\r\n"
+"madc.hi.cc.u32 %2, %10, %12, %2;\r\n"
+"madc.hi.cc.u32 %3, %10, %13, %3;\r\n"
+"madc.hi.cc.u32 %4, %10, %14, %4;\r\n"
+"madc.hi.cc.u32 %5, %10, %15, %5;\r\n"
+"madc.hi.cc.u32 %6, %10, %16, %6;\r\n"
+"madc.hi.cc.u32 %7, %10, %17, %7;\r\n"
+"madc.hi.cc.u32 %8, %10, %18, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mul.lo.u32 m, %0, 4294967295;\r\n"
+"mad.lo.cc.u32 %0, m, 1, %0;\r\n"
+"madc.lo.cc.u32 %1, m, 4294967295, %1;\r\n"
+"madc.lo.cc.u32 %2, m, 4294859774, %2;\r\n"
+"madc.lo.cc.u32 %3, m, 1404937218, %3;\r\n"
+"madc.lo.cc.u32 %4, m, 161601541, %4;\r\n"
+"madc.lo.cc.u32 %5, m, 859428872, %5;\r\n"
+"madc.lo.cc.u32 %6, m, 698187080, %6;\r\n"
+"madc.lo.cc.u32 %7, m, 1944954707, %7;\r\n"
+"addc.cc.u32 %8, %8, 0;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"mad.hi.cc.u32 %1, m, 1, %1;\r\n"
+"madc.hi.cc.u32 %2, m, 4294967295, %2;\r\n"
+"madc.hi.cc.u32 %3, m, 4294859774, %3;\r\n"
+"madc.hi.cc.u32 %4, m, 1404937218, %4;\r\n"
+"madc.hi.cc.u32 %5, m, 161601541, %5;\r\n"
+"madc.hi.cc.u32 %6, m, 859428872, %6;\r\n"
+"madc.hi.cc.u32 %7, m, 698187080, %7;\r\n"
+"madc.hi.cc.u32 %8, m, 1944954707, %8;\r\n"
+"addc.u32 %9, %9, 0;\r\n"
+"}\r\n"
+:"+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13]), "+r"(t[14]), "+r"(t[15]), "+r"(t[16])
+:"r"(b.val[7]), "r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+FIELD result;
+for(uchar i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[FIELD_LIMBS + i];
+return result;
+}
+#endif
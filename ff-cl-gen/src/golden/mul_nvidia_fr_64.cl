@@ -0,0 +1,120 @@
+#ifdef NVIDIA
+DEVICE FIELD FIELD_mul_nvidia(FIELD a, FIELD b) {
+FIELD_limb t[2 * FIELD_LIMBS + 1] = {0};
+asm("{\r\n"
+".reg .u64 m;\r\n"
+"mad.lo.cc.u64 %0, %6, %7, %0;\r\n"
+"madc.lo.cc.u64 %1, %6, %8, %1;\r\n"
+"madc.lo.cc.u64 %2, %6, %9, %2;\r\n"
+"madc.lo.cc.u64 %3, %6, %10, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, %6, %7, %1;\r\n"
+"madc.hi.cc.u64 %2, %6, %8, %2;\r\n"
+"madc.hi.cc.u64 %3, %6, %9, %3;\r\n"
+"madc.hi.cc.u64 %4, %6, %10, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mul.lo.u64 m, %0, 18446744069414584319;\r\n"
+"mad.lo.cc.u64 %0, m, 18446744069414584321, %0;\r\n"
+"madc.lo.cc.u64 %1, m, 6034159408538082302, %1;\r\n"
+"madc.lo.cc.u64 %2, m, 3691218898639771653, %2;\r\n"
+"madc.lo.cc.u64 %3, m, 8353516859464449352, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, m, 18446744069414584321, %1;\r\n"
+"madc.hi.cc.u64 %2, m, 6034159408538082302, %2;\r\n"
+"madc.hi.cc.u64 %3, m, 3691218898639771653, %3;\r\n"
+"madc.hi.cc.u64 %4, m, 8353516859464449352, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"}\r\n"
+:"+l"(t[0]), "+l"(t[1]), "+l"(t[2]), "+l"(t[3]), "+l"(t[4]), "+l"(t[5])
+:"l"(b.val[0]), "l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("{\r\n"
+".reg .u64 m;\r\n"
+"mad.lo.cc.u64 %0, %6, %7, %0;\r\n"
+"madc.lo.cc.u64 %1, %6, %8, %1;\r\n"
+"madc.lo.cc.u64 %2, %6, %9, %2;\r\n"
+"madc.lo.cc.u64 %3, %6, %10, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, %6, %7, %1;\r\n"
+"madc.hi.cc.u64 %2, %6, %8, %2;\r\n"
+"madc.hi.cc.u64 %3, %6, %9, %3;\r\n"
+"madc.hi.cc.u64 %4, %6, %10, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mul.lo.u64 m, %0, 18446744069414584319;\r\n"
+"mad.lo.cc.u64 %0, m, 18446744069414584321, %0;\r\n"
+"madc.lo.cc.u64 %1, m, 6034159408538082302, %1;\r\n"
+"madc.lo.cc.u64 %2, m, 3691218898639771653, %2;\r\n"
+"madc.lo.cc.u64 %3, m, 8353516859464449352, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, m, 18446744069414584321, %1;\r\n"
+"madc.hi.cc.u64 %2, m, 6034159408538082302, %2;\r\n"
+"madc.hi.cc.u64 %3, m, 3691218898639771653, %3;\r\n"
+"madc.hi.cc.u64 %4, m, 8353516859464449352, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"}\r\n"
+:"+l"(t[1]), "+l"(t[2]), "+l"(t[3]), "+l"(t[4]), "+l"(t[5]), "+l"(t[6])
+:"l"(b.val[1]), "l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("{\r\n"
+".reg .u64 m;\r\n"
+"mad.lo.cc.u64 %0, %6, %7, %0;\r\n"
+"madc.lo.cc.u64 %1, %6, %8, %1;\r\n"
+"madc.lo.cc.u64 %2, %6, %9, %2;\r\n"
+"madc.lo.cc.u64 %3, %6, %10, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, %6, %7, %1;\r\n"
+"madc.hi.cc.u64 %2, %6, %8, %2;\r\n"
+"madc.hi.cc.u64 %3, %6, %9, %3;\r\n"
+"madc.hi.cc.u64 %4, %6, %10, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mul.lo.u64 m, %0, 18446744069414584319;\r\n"
+"mad.lo.cc.u64 %0, m, 18446744069414584321, %0;\r\n"
+"madc.lo.cc.u64 %1, m, 6034159408538082302, %1;\r\n"
+"madc.lo.cc.u64 %2, m, 3691218898639771653, %2;\r\n"
+"madc.lo.cc.u64 %3, m, 8353516859464449352, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, m, 18446744069414584321, %1;\r\n"
+"madc.hi.cc.u64 %2, m, 6034159408538082302, %2;\r\n"
+"madc.hi.cc.u64 %3, m, 3691218898639771653, %3;\r\n"
+"madc.hi.cc.u64 %4, m, 8353516859464449352, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"}\r\n"
+:"+l"(t[2]), "+l"(t[3]), "+l"(t[4]), "+l"(t[5]), "+l"(t[6]), "+l"(t[7])
+:"l"(b.val[2]), "l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("{\r\n"
+".reg .u64 m;\r\n"
+"mad.lo.cc.u64 %0, %6, %7, %0;\r\n"
+"madc.lo.cc.u64 %1, %6, %8, %1;\r\n"
+"madc.lo.cc.u64 %2, %6, %9, %2;\r\n"
+"madc.lo.cc.u64 %3, %6, %10, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, %6, %7, %1;\r\n"
+"madc.hi.cc.u64 %2, %6, %8, %2;\r\n"
+"madc.hi.cc.u64 %3, %6, %9, %3;\r\n"
+"madc.hi.cc.u64 %4, %6, %10, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mul.lo.u64 m, %0, 18446744069414584319;\r\n"
+"mad.lo.cc.u64 %0, m, 18446744069414584321, %0;\r\n"
+"madc.lo.cc.u64 %1, m, 6034159408538082302, %1;\r\n"
+"madc.lo.cc.u64 %2, m, 3691218898639771653, %2;\r\n"
+"madc.lo.cc.u64 %3, m, 8353516859464449352, %3;\r\n"
+"addc.cc.u64 %4, %4, 0;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"mad.hi.cc.u64 %1, m, 18446744069414584321, %1;\r\n"
+"madc.hi.cc.u64 %2, m, 6034159408538082302, %2;\r\n"
+"madc.hi.cc.u64 %3, m, 3691218898639771653, %3;\r\n"
+"madc.hi.cc.u64 %4, m, 8353516859464449352, %4;\r\n"
+"addc.u64 %5, %5, 0;\r\n"
+"}\r\n"
+:"+l"(t[3]), "+l"(t[4]), "+l"(t[5]), "+l"(t[6]), "+l"(t[7]), "+l"(t[8])
+:"l"(b.val[3]), "l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+FIELD result;
+for(uchar i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[FIELD_LIMBS + i];
+return result;
+}
+#endif
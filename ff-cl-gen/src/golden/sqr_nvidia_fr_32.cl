@@ -0,0 +1,131 @@
+#ifdef NVIDIA
+DEVICE void FIELD_sqr_wide_nvidia(FIELD a, FIELD_limb *t) {
+for(uchar i = 0; i < 2 * FIELD_LIMBS; i++) t[i] = 0;
+asm("mad.lo.cc.u32 %0, %8, %9, %0;\r\n"
+"madc.lo.cc.u32 %1, %8, %10, %1;\r\n"
+"madc.lo.cc.u32 %2, %8, %11, %2;\r\n"
+"madc.lo.cc.u32 %3, %8, %12, %3;\r\n"
+"madc.lo.cc.u32 %4, %8, %13, %4;\r\n"
+"madc.lo.cc.u32 %5, %8, %14, %5;\r\n"
+"madc.lo.cc.u32 %6, %8, %15, %6;\r\n"
+"addc.u32 %7, 0, 0;\r\n"
+:"+r"(t[1]), "+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8])
+:"r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %7, %8, %0;\r\n"
+"madc.hi.cc.u32 %1, %7, %9, %1;\r\n"
+"madc.hi.cc.u32 %2, %7, %10, %2;\r\n"
+"madc.hi.cc.u32 %3, %7, %11, %3;\r\n"
+"madc.hi.cc.u32 %4, %7, %12, %4;\r\n"
+"madc.hi.cc.u32 %5, %7, %13, %5;\r\n"
+"madc.hi.u32 %6, %7, %14, %6;\r\n"
+:"+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8])
+:"r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %7, %8, %0;\r\n"
+"madc.lo.cc.u32 %1, %7, %9, %1;\r\n"
+"madc.lo.cc.u32 %2, %7, %10, %2;\r\n"
+"madc.lo.cc.u32 %3, %7, %11, %3;\r\n"
+"madc.lo.cc.u32 %4, %7, %12, %4;\r\n"
+"madc.lo.cc.u32 %5, %7, %13, %5;\r\n"
+"addc.u32 %6, 0, 0;\r\n"
+:"+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9])
+:"r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %6, %7, %0;\r\n"
+"madc.hi.cc.u32 %1, %6, %8, %1;\r\n"
+"madc.hi.cc.u32 %2, %6, %9, %2;\r\n"
+"madc.hi.cc.u32 %3, %6, %10, %3;\r\n"
+"madc.hi.cc.u32 %4, %6, %11, %4;\r\n"
+"madc.hi.u32 %5, %6, %12, %5;\r\n"
+:"+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9])
+:"r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %6, %7, %0;\r\n"
+"madc.lo.cc.u32 %1, %6, %8, %1;\r\n"
+"madc.lo.cc.u32 %2, %6, %9, %2;\r\n"
+"madc.lo.cc.u32 %3, %6, %10, %3;\r\n"
+"madc.lo.cc.u32 %4, %6, %11, %4;\r\n"
+"addc.u32 %5, 0, 0;\r\n"
+:"+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10])
+:"r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %5, %6, %0;\r\n"
+"madc.hi.cc.u32 %1, %5, %7, %1;\r\n"
+"madc.hi.cc.u32 %2, %5, %8, %2;\r\n"
+"madc.hi.cc.u32 %3, %5, %9, %3;\r\n"
+"madc.hi.u32 %4, %5, %10, %4;\r\n"
+:"+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10])
+:"r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %5, %6, %0;\r\n"
+"madc.lo.cc.u32 %1, %5, %7, %1;\r\n"
+"madc.lo.cc.u32 %2, %5, %8, %2;\r\n"
+"madc.lo.cc.u32 %3, %5, %9, %3;\r\n"
+"addc.u32 %4, 0, 0;\r\n"
+:"+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11])
+:"r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %4, %5, %0;\r\n"
+"madc.hi.cc.u32 %1, %4, %6, %1;\r\n"
+"madc.hi.cc.u32 %2, %4, %7, %2;\r\n"
+"madc.hi.u32 %3, %4, %8, %3;\r\n"
+:"+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11])
+:"r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %4, %5, %0;\r\n"
+"madc.lo.cc.u32 %1, %4, %6, %1;\r\n"
+"madc.lo.cc.u32 %2, %4, %7, %2;\r\n"
+"addc.u32 %3, 0, 0;\r\n"
+:"+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12])
+:"r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %3, %4, %0;\r\n"
+"madc.hi.cc.u32 %1, %3, %5, %1;\r\n"
+"madc.hi.u32 %2, %3, %6, %2;\r\n"
+:"+r"(t[10]), "+r"(t[11]), "+r"(t[12])
+:"r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %3, %4, %0;\r\n"
+"madc.lo.cc.u32 %1, %3, %5, %1;\r\n"
+"addc.u32 %2, 0, 0;\r\n"
+:"+r"(t[11]), "+r"(t[12]), "+r"(t[13])
+:"r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.cc.u32 %0, %2, %3, %0;\r\n"
+"madc.hi.u32 %1, %2, %4, %1;\r\n"
+:"+r"(t[12]), "+r"(t[13])
+:"r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+asm("mad.lo.cc.u32 %0, %2, %3, %0;\r\n"
+"addc.u32 %1, 0, 0;\r\n"
+:"+r"(t[13]), "+r"(t[14])
+:"r"(a.val[6]), "r"(a.val[7]));
+asm("mad.hi.u32 %0, %1, %2, %0;\r\n"
+:"+r"(t[14])
+:"r"(a.val[6]), "r"(a.val[7]));
+asm("add.cc.u32 %0, %0, %0;\r\n"
+"addc.cc.u32 %1, %1, %1;\r\n"
+"addc.cc.u32 %2, %2, %2;\r\n"
+"addc.cc.u32 %3, %3, %3;\r\n"
+"addc.cc.u32 %4, %4, %4;\r\n"
+"addc.cc.u32 %5, %5, %5;\r\n"
+"addc.cc.u32 %6, %6, %6;\r\n"
+"addc.cc.u32 %7, %7, %7;\r\n"
+"addc.cc.u32 %8, %8, %8;\r\n"
+"addc.cc.u32 %9, %9, %9;\r\n"
+"addc.cc.u32 %10, %10, %10;\r\n"
+"addc.cc.u32 %11, %11, %11;\r\n"
+"addc.cc.u32 %12, %12, %12;\r\n"
+"addc.cc.u32 %13, %13, %13;\r\n"
+"addc.u32 %14, 0, 0;\r\n"
+:"+r"(t[1]), "+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13]), "+r"(t[14]), "+r"(t[15])
+:);
+asm("mad.lo.cc.u32 %0, %16, %16, %0;\r\n"
+"madc.hi.cc.u32 %1, %16, %16, %1;\r\n"
+"madc.lo.cc.u32 %2, %17, %17, %2;\r\n"
+"madc.hi.cc.u32 %3, %17, %17, %3;\r\n"
+"madc.lo.cc.u32 %4, %18, %18, %4;\r\n"
+"madc.hi.cc.u32 %5, %18, %18, %5;\r\n"
+"madc.lo.cc.u32 %6, %19, %19, %6;\r\n"
+"madc.hi.cc.u32 %7, %19, %19, %7;\r\n"
+"madc.lo.cc.u32 %8, %20, %20, %8;\r\n"
+"madc.hi.cc.u32 %9, %20, %20, %9;\r\n"
+"madc.lo.cc.u32 %10, %21, %21, %10;\r\n"
+"madc.hi.cc.u32 %11, %21, %21, %11;\r\n"
+"madc.lo.cc.u32 %12, %22, %22, %12;\r\n"
+"madc.hi.cc.u32 %13, %22, %22, %13;\r\n"
+"madc.lo.cc.u32 %14, %23, %23, %14;\r\n"
+"madc.hi.u32 %15, %23, %23, %15;\r\n"
+:"+r"(t[0]), "+r"(t[1]), "+r"(t[2]), "+r"(t[3]), "+r"(t[4]), "+r"(t[5]), "+r"(t[6]), "+r"(t[7]), "+r"(t[8]), "+r"(t[9]), "+r"(t[10]), "+r"(t[11]), "+r"(t[12]), "+r"(t[13]), "+r"(t[14]), "+r"(t[15])
+:"r"(a.val[0]), "r"(a.val[1]), "r"(a.val[2]), "r"(a.val[3]), "r"(a.val[4]), "r"(a.val[5]), "r"(a.val[6]), "r"(a.val[7]));
+}
+#endif
@@ -0,0 +1,51 @@
+#ifdef NVIDIA
+DEVICE void FIELD_sqr_wide_nvidia(FIELD a, FIELD_limb *t) {
+for(uchar i = 0; i < 2 * FIELD_LIMBS; i++) t[i] = 0;
+asm("mad.lo.cc.u64 %0, %4, %5, %0;\r\n"
+"madc.lo.cc.u64 %1, %4, %6, %1;\r\n"
+"madc.lo.cc.u64 %2, %4, %7, %2;\r\n"
+"addc.u64 %3, 0, 0;\r\n"
+:"+l"(t[1]), "+l"(t[2]), "+l"(t[3]), "+l"(t[4])
+:"l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("mad.hi.cc.u64 %0, %3, %4, %0;\r\n"
+"madc.hi.cc.u64 %1, %3, %5, %1;\r\n"
+"madc.hi.u64 %2, %3, %6, %2;\r\n"
+:"+l"(t[2]), "+l"(t[3]), "+l"(t[4])
+:"l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("mad.lo.cc.u64 %0, %3, %4, %0;\r\n"
+"madc.lo.cc.u64 %1, %3, %5, %1;\r\n"
+"addc.u64 %2, 0, 0;\r\n"
+:"+l"(t[3]), "+l"(t[4]), "+l"(t[5])
+:"l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("mad.hi.cc.u64 %0, %2, %3, %0;\r\n"
+"madc.hi.u64 %1, %2, %4, %1;\r\n"
+:"+l"(t[4]), "+l"(t[5])
+:"l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+asm("mad.lo.cc.u64 %0, %2, %3, %0;\r\n"
+"addc.u64 %1, 0, 0;\r\n"
+:"+l"(t[5]), "+l"(t[6])
+:"l"(a.val[2]), "l"(a.val[3]));
+asm("mad.hi.u64 %0, %1, %2, %0;\r\n"
+:"+l"(t[6])
+:"l"(a.val[2]), "l"(a.val[3]));
+asm("add.cc.u64 %0, %0, %0;\r\n"
+"addc.cc.u64 %1, %1, %1;\r\n"
+"addc.cc.u64 %2, %2, %2;\r\n"
+"addc.cc.u64 %3, %3, %3;\r\n"
+"addc.cc.u64 %4, %4, %4;\r\n"
+"addc.cc.u64 %5, %5, %5;\r\n"
+"addc.u64 %6, 0, 0;\r\n"
+:"+l"(t[1]), "+l"(t[2]), "+l"(t[3]), "+l"(t[4]), "+l"(t[5]), "+l"(t[6]), "+l"(t[7])
+:);
+asm("mad.lo.cc.u64 %0, %8, %8, %0;\r\n"
+"madc.hi.cc.u64 %1, %8, %8, %1;\r\n"
+"madc.lo.cc.u64 %2, %9, %9, %2;\r\n"
+"madc.hi.cc.u64 %3, %9, %9, %3;\r\n"
+"madc.lo.cc.u64 %4, %10, %10, %4;\r\n"
+"madc.hi.cc.u64 %5, %10, %10, %5;\r\n"
+"madc.lo.cc.u64 %6, %11, %11, %6;\r\n"
+"madc.hi.u64 %7, %11, %11, %7;\r\n"
+:"+l"(t[0]), "+l"(t[1]), "+l"(t[2]), "+l"(t[3]), "+l"(t[4]), "+l"(t[5]), "+l"(t[6]), "+l"(t[7])
+:"l"(a.val[0]), "l"(a.val[1]), "l"(a.val[2]), "l"(a.val[3]));
+}
+#endif
@@ -0,0 +1,617 @@
+//! Host harness for testing the generated code without an OpenCL device.
+//!
+//! The output of `field()` is compiled together with `cl/host.h`, which maps the OpenCL builtins
+//! to plain C, and `cl/host_test.c`, a small driver reading operations from stdin. The output of
+//! `field_cuda()` is compiled as C++ along with the same driver, with `cl/cuda_host.h` providing
+//! the CUDA builtins. The compilers can be overridden through the `CC`/`CXX` environment
+//! variables.
+
+use super::*;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+static HOST_SRC: &str = include_str!("cl/host.h");
+static CUDA_HOST_SRC: &str = include_str!("cl/cuda_host.h");
+static DRIVER_SRC: &str = include_str!("cl/host_test.c");
+static EXTENSION_DRIVER_SRC: &str = include_str!("cl/host_test_extension.c");
+static CURVE_DRIVER_SRC: &str = include_str!("cl/host_test_curve.c");
+
+/// Hex-dump of the in-memory representation of `value`
+pub fn encode<T>(value: &T) -> String {
+    let bytes = unsafe {
+        std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>())
+    };
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Reverse of `encode`
+pub fn decode<T>(hex: &str) -> T {
+    let bytes = (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+        .collect::<Vec<u8>>();
+    assert_eq!(bytes.len(), std::mem::size_of::<T>());
+    unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) }
+}
+
+/// Test driver, operating on field `name`
+pub fn driver(name: &str) -> String {
+    DRIVER_SRC.replace("FIELD", name)
+}
+
+/// Test driver, operating on extension field `name`
+pub fn extension_driver(name: &str) -> String {
+    EXTENSION_DRIVER_SRC.replace("FIELD", name)
+}
+
+/// Test driver, operating on curve `name`
+pub fn curve_driver(name: &str) -> String {
+    CURVE_DRIVER_SRC.replace("POINT", name)
+}
+
+/// Generated code of a single field, compiled into a native executable
+pub struct Harness {
+    binary: PathBuf,
+}
+
+impl Harness {
+    pub fn new<F, L: Limb>(name: &str) -> Harness
+    where
+        F: PrimeField,
+    {
+        Harness::compile(name, &field::<F, L>(name), &driver(name))
+    }
+
+    pub fn new_cuda<F, L: Limb>(name: &str) -> Harness
+    where
+        F: PrimeField,
+    {
+        Harness::build(
+            name,
+            &join(
+                &[CUDA_HOST_SRC, &field_cuda::<F, L>(name), &driver(name)],
+                "\n",
+            ),
+            ("CXX", "c++"),
+            &["-x", "c++", "-std=c++11"],
+        )
+    }
+
+    /// Compiles `src` along with the OpenCL shim and a `driver` providing `main`
+    pub fn compile(name: &str, src: &str, driver: &str) -> Harness {
+        Harness::build(
+            name,
+            &join(&[HOST_SRC, src, driver], "\n"),
+            ("CC", "cc"),
+            &["-x", "c", "-std=gnu11"],
+        )
+    }
+
+    /// Builds `source` with the compiler named by the environment variable `compiler.0`, or
+    /// `compiler.1` if it's not set
+    fn build(name: &str, source: &str, compiler: (&str, &str), args: &[&str]) -> Harness {
+        let dir = env::temp_dir().join(format!("ff-cl-gen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.src", name));
+        let binary = dir.join(name);
+        fs::write(&path, source).unwrap();
+
+        let cc = env::var(compiler.0).unwrap_or_else(|_| compiler.1.to_string());
+        let output = Command::new(cc)
+            .args(args)
+            .args(&["-fsigned-char", "-O2", "-o"])
+            .arg(&binary)
+            .arg(&path)
+            .output()
+            .expect("Cannot run the compiler!");
+        assert!(
+            output.status.success(),
+            "Compilation of {} failed:\n{}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        Harness { binary }
+    }
+
+    /// Runs the operations in a single process, returns one line of output per operation
+    pub fn run(&self, ops: &[String]) -> Vec<String> {
+        let mut child = Command::new(&self.binary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let input = join(ops, "\n");
+        // Write from another thread, so that a full stdout pipe can't block us
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()).unwrap());
+        let output = child.wait_with_output().unwrap();
+        writer.join().unwrap();
+        assert!(output.status.success(), "Host harness failed!");
+
+        let lines = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), ops.len());
+        lines
+    }
+
+    /// Runs a single operation and decodes its result
+    pub fn call<T>(&self, op: String) -> T {
+        decode(&self.run(&[op])[0])
+    }
+
+    /// Runs the `multiexp` kernel of a curve on every thread, returns the result of each thread
+    pub fn multiexp<A, P, S>(
+        &self,
+        bases: &[A],
+        exps: &[S],
+        (num_groups, num_windows, window_size): (usize, usize, usize),
+    ) -> Vec<P> {
+        let op = format!(
+            "multiexp {} {} {} {} {} {}",
+            bases.len(),
+            num_groups,
+            num_windows,
+            window_size,
+            join(bases.iter().map(encode), " "),
+            join(exps.iter().map(encode), " ")
+        );
+        let res = &self.run(&[op])[0];
+        let size = 2 * std::mem::size_of::<P>();
+        assert_eq!(res.len(), size * num_groups * num_windows);
+        (0..num_groups * num_windows)
+            .map(|i| decode(&res[i * size..(i + 1) * size]))
+            .collect()
+    }
+}
+
+mod tests {
+    use super::*;
+    use ff::{Field, LegendreSymbol, PrimeField, PrimeFieldRepr, SqrtField};
+    use lazy_static::lazy_static;
+    use paired::bls12_381::{Fq, FqRepr, Fr, FrRepr};
+    use rand::{thread_rng, Rng};
+
+    // 753-bit fields of MNT4-753 (Swapped on MNT6-753), twelve 64-bit limbs. The derive defines
+    // the constants of a field next to it, so each field has its own module.
+    mod mnt4_fq {
+        use ff::{Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr};
+
+        #[derive(PrimeField)]
+        #[PrimeFieldModulus = "41898490967918953402344214791240637128170709919953949071783502921025352812571106773058893763790338921418070971888458477323173057491593855069696241854796396165721416325350064441470418137846398469611935719059908164220784476160001"]
+        #[PrimeFieldGenerator = "17"]
+        pub struct Mnt4Fq(Mnt4FqRepr);
+    }
+
+    mod mnt4_fr {
+        use ff::{Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr};
+
+        #[derive(PrimeField)]
+        #[PrimeFieldModulus = "41898490967918953402344214791240637128170709919953949071783502921025352812571106773058893763790338921418070971888253786114353726529584385201591605722013126468931404347949840543007986327743462853720628051692141265303114721689601"]
+        #[PrimeFieldGenerator = "17"]
+        pub struct Mnt4Fr(Mnt4FrRepr);
+    }
+
+    use mnt4_fq::Mnt4Fq;
+    use mnt4_fr::Mnt4Fr;
+
+    fn new_karatsuba<F: PrimeField>(name: &str, limb_size: LimbSize) -> Harness {
+        let field = FieldGenerator::<F>::new(name)
+            .limb_size(limb_size)
+            .karatsuba(true)
+            .generate();
+        Harness::compile(name, &field.source, &driver(name))
+    }
+
+    lazy_static! {
+        static ref FR32: Harness = Harness::new::<Fr, Limb32>("Fr32");
+        static ref FR64: Harness = Harness::new::<Fr, Limb64>("Fr64");
+        static ref FQ32: Harness = Harness::new::<Fq, Limb32>("Fq32");
+        static ref FQ64: Harness = Harness::new::<Fq, Limb64>("Fq64");
+        static ref FR32_CUDA: Harness = Harness::new_cuda::<Fr, Limb32>("Fr32Cuda");
+        static ref FR64_CUDA: Harness = Harness::new_cuda::<Fr, Limb64>("Fr64Cuda");
+        static ref FQ64_CUDA: Harness = Harness::new_cuda::<Fq, Limb64>("Fq64Cuda");
+        static ref MNT4_FQ32: Harness = Harness::new::<Mnt4Fq, Limb32>("Mnt4Fq32");
+        static ref MNT4_FQ64: Harness = Harness::new::<Mnt4Fq, Limb64>("Mnt4Fq64");
+        static ref MNT4_FR32: Harness = Harness::new::<Mnt4Fr, Limb32>("Mnt4Fr32");
+        static ref MNT4_FR64: Harness = Harness::new::<Mnt4Fr, Limb64>("Mnt4Fr64");
+        static ref MNT4_FQ64_CUDA: Harness = Harness::new_cuda::<Mnt4Fq, Limb64>("Mnt4Fq64Cuda");
+        static ref MNT4_FQ32_KARATSUBA: Harness =
+            new_karatsuba::<Mnt4Fq>("Mnt4Fq32Karatsuba", LimbSize::Limb32);
+        static ref MNT4_FQ64_KARATSUBA: Harness =
+            new_karatsuba::<Mnt4Fq>("Mnt4Fq64Karatsuba", LimbSize::Limb64);
+    }
+
+    const SAMPLES: usize = 100;
+
+    /// Checks `op` of the generated code against `f` on random inputs
+    fn check<F: PrimeField>(harnesses: &[&Harness], op: &str, arity: usize, f: impl Fn(&[F]) -> F) {
+        let mut rng = thread_rng();
+        let inputs = (0..SAMPLES)
+            .map(|_| (0..arity).map(|_| F::random(&mut rng)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let ops = inputs
+            .iter()
+            .map(|args| {
+                join(
+                    Some(op.to_string())
+                        .into_iter()
+                        .chain(args.iter().map(encode)),
+                    " ",
+                )
+            })
+            .collect::<Vec<_>>();
+        for h in harnesses {
+            for (out, args) in h.run(&ops).iter().zip(inputs.iter()) {
+                assert_eq!(decode::<F>(out), f(args), "{}", op);
+            }
+        }
+    }
+
+    macro_rules! check_field {
+        ($field:ident, $harnesses:expr) => {
+            check::<$field>($harnesses, "add", 2, |v| {
+                let mut c = v[0];
+                c.add_assign(&v[1]);
+                c
+            });
+            check::<$field>($harnesses, "sub", 2, |v| {
+                let mut c = v[0];
+                c.sub_assign(&v[1]);
+                c
+            });
+            check::<$field>($harnesses, "mul", 2, |v| {
+                let mut c = v[0];
+                c.mul_assign(&v[1]);
+                c
+            });
+            check::<$field>($harnesses, "sqr", 1, |v| {
+                let mut c = v[0];
+                c.square();
+                c
+            });
+            check::<$field>($harnesses, "double", 1, |v| {
+                let mut c = v[0];
+                c.double();
+                c
+            });
+            check::<$field>($harnesses, "inv", 1, |v| v[0].inverse().unwrap());
+        };
+    }
+
+    #[test]
+    fn test_arithmetic_fr() {
+        check_field!(Fr, &[&*FR32, &*FR64]);
+    }
+
+    #[test]
+    fn test_arithmetic_fq() {
+        check_field!(Fq, &[&*FQ32, &*FQ64]);
+    }
+
+    #[test]
+    fn test_arithmetic_cuda() {
+        check_field!(Fr, &[&*FR32_CUDA, &*FR64_CUDA]);
+        check_field!(Fq, &[&*FQ64_CUDA]);
+    }
+
+    #[test]
+    fn test_arithmetic_753() {
+        let fq = FieldGenerator::<Mnt4Fq>::new("Fq").generate();
+        assert_eq!((fq.limbs, fq.limb_bits), (12, 64));
+        check_field!(
+            Mnt4Fq,
+            &[
+                &*MNT4_FQ32,
+                &*MNT4_FQ64,
+                &*MNT4_FQ64_CUDA,
+                &*MNT4_FQ32_KARATSUBA,
+                &*MNT4_FQ64_KARATSUBA
+            ]
+        );
+        check_field!(Mnt4Fr, &[&*MNT4_FR32, &*MNT4_FR64]);
+
+        let mut minus_one = Mnt4Fq::one();
+        minus_one.negate();
+        let edge_cases = [Mnt4Fq::zero(), Mnt4Fq::one(), minus_one];
+        for a in edge_cases.iter() {
+            for b in edge_cases.iter() {
+                let mut c = *a;
+                c.mul_assign(b);
+                for h in &[
+                    &*MNT4_FQ32,
+                    &*MNT4_FQ64,
+                    &*MNT4_FQ32_KARATSUBA,
+                    &*MNT4_FQ64_KARATSUBA,
+                ] {
+                    assert_eq!(
+                        h.call::<Mnt4Fq>(format!("mul {} {}", encode(a), encode(b))),
+                        c
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_neg_half() {
+        macro_rules! check_neg_half {
+            ($field:ident, $harnesses:expr) => {
+                check::<$field>($harnesses, "neg", 1, |v| {
+                    let mut c = v[0];
+                    c.negate();
+                    c
+                });
+                let mut two = $field::one();
+                two.double();
+                let half = two.inverse().unwrap();
+                check::<$field>($harnesses, "half", 1, |v| {
+                    let mut c = v[0];
+                    c.mul_assign(&half);
+                    c
+                });
+            };
+        }
+        check_neg_half!(Fr, &[&*FR32, &*FR64, &*FR64_CUDA]);
+        check_neg_half!(Fq, &[&*FQ32, &*FQ64]);
+        check_neg_half!(Mnt4Fq, &[&*MNT4_FQ32, &*MNT4_FQ64]);
+        for h in &[&*FR32, &*FR64] {
+            let zero = encode(&Fr::zero());
+            assert_eq!(h.call::<Fr>(format!("neg {}", zero)), Fr::zero());
+            assert_eq!(h.call::<Fr>(format!("half {}", zero)), Fr::zero());
+        }
+    }
+
+    #[test]
+    fn test_is_zero() {
+        let one = Fr::one();
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            let ops = [
+                format!("is_zero {}", encode(&Fr::zero())),
+                format!("is_zero {}", encode(&one)),
+            ];
+            assert_eq!(h.run(&ops), vec!["1", "0"]);
+        }
+    }
+
+    #[test]
+    fn test_from_int() {
+        let mut rng = thread_rng();
+        let mut values = vec![0, 1, u32::max_value() as u64, u64::max_value()];
+        values.extend((0..10).map(|_| rng.gen::<u64>()));
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            for v in values.iter() {
+                let expected = Fr::from_repr(FrRepr::from(*v)).unwrap();
+                assert_eq!(h.call::<Fr>(format!("from_u64 {}", v)), expected);
+                if *v <= u32::max_value() as u64 {
+                    assert_eq!(h.call::<Fr>(format!("from_u32 {}", v)), expected);
+                }
+            }
+        }
+        let v = rng.gen::<u64>();
+        let expected = Fq::from_repr(FqRepr::from(v)).unwrap();
+        assert_eq!(FQ32.call::<Fq>(format!("from_u64 {}", v)), expected);
+    }
+
+    #[test]
+    fn test_from_repr() {
+        let mut rng = thread_rng();
+        let mut reprs = vec![Fr::char(), FrRepr::from(0)];
+        let mut above = Fr::char();
+        above.as_mut()[0] += 1;
+        reprs.push(above);
+        let mut max = FrRepr::default();
+        max.as_mut().iter_mut().for_each(|l| *l = u64::max_value());
+        reprs.push(max);
+        reprs.extend((0..10).map(|_| Fr::random(&mut rng).into_repr()));
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            let ops = reprs
+                .iter()
+                .map(|r| format!("from_repr {}", encode(r)))
+                .collect::<Vec<_>>();
+            for (out, repr) in h.run(&ops).iter().zip(reprs.iter()) {
+                match Fr::from_repr(*repr) {
+                    Ok(expected) => assert_eq!(decode::<Fr>(out), expected),
+                    Err(_) => assert_eq!(out, "none"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let mut rng = thread_rng();
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            for _ in 0..10 {
+                let a = Fr::random(&mut rng);
+                let (mut le, mut be) = (Vec::new(), Vec::new());
+                a.into_repr().write_le(&mut le).unwrap();
+                a.into_repr().write_be(&mut be).unwrap();
+                let hex = |bytes: Vec<u8>| join(bytes.iter().map(|b| format!("{:02x}", b)), "");
+                let ops = [
+                    format!("to_bytes_le {}", encode(&a)),
+                    format!("to_bytes_be {}", encode(&a)),
+                ];
+                assert_eq!(h.run(&ops), vec![hex(le), hex(be)]);
+            }
+        }
+        let a = Fq::random(&mut rng);
+        let mut le = Vec::new();
+        a.into_repr().write_le(&mut le).unwrap();
+        let out = FQ32.run(&[format!("to_bytes_le {}", encode(&a))]);
+        assert_eq!(out[0], join(le.iter().map(|b| format!("{:02x}", b)), ""));
+        let a = Mnt4Fq::random(&mut rng);
+        let mut be = Vec::new();
+        a.into_repr().write_be(&mut be).unwrap();
+        let out = MNT4_FQ32.run(&[format!("to_bytes_be {}", encode(&a))]);
+        assert_eq!(out[0], join(be.iter().map(|b| format!("{:02x}", b)), ""));
+    }
+
+    #[test]
+    fn test_pow() {
+        let mut rng = thread_rng();
+        for h in &[&*FR32, &*FR64] {
+            for _ in 0..10 {
+                let a = Fr::random(&mut rng);
+                let b = rng.gen::<u32>();
+                let c = a.pow([b as u64]);
+                assert_eq!(h.call::<Fr>(format!("pow {} {}", encode(&a), b)), c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pow_field() {
+        let mut rng = thread_rng();
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        let mut exponents = vec![Fr::zero().into_repr(), minus_one.into_repr()];
+        exponents.extend((0..10).map(|_| Fr::random(&mut rng).into_repr()));
+        exponents.push(FrRepr::from(rng.gen::<u64>()));
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            for e in exponents.iter() {
+                let a = Fr::random(&mut rng);
+                let b = a.pow(e);
+                let op = format!("pow_field {} {}", encode(&a), encode(e));
+                assert_eq!(h.call::<Fr>(op), b);
+            }
+        }
+        let a = Fq::random(&mut rng);
+        let e = Fq::random(&mut rng).into_repr();
+        let op = format!("pow_field {} {}", encode(&a), encode(&e));
+        assert_eq!(FQ64.call::<Fq>(op), a.pow(e));
+    }
+
+    #[test]
+    fn test_mont() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let a_repr = Fr::random(&mut rng).into_repr();
+            let b = Fr::from_repr(a_repr).unwrap();
+            for h in &[&*FR32, &*FR64] {
+                assert_eq!(h.call::<Fr>(format!("mont {}", encode(&a_repr))), b);
+                assert_eq!(h.call::<FrRepr>(format!("unmont {}", encode(&b))), a_repr);
+            }
+            let a_repr = Fq::random(&mut rng).into_repr();
+            let b = Fq::from_repr(a_repr).unwrap();
+            for h in &[&*FQ32, &*FQ64] {
+                assert_eq!(h.call::<Fq>(format!("mont {}", encode(&a_repr))), b);
+                assert_eq!(h.call::<FqRepr>(format!("unmont {}", encode(&b))), a_repr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqr_edge_cases() {
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        for a in &[Fr::zero(), Fr::one(), minus_one] {
+            let mut b = *a;
+            b.square();
+            for h in &[&*FR32, &*FR64] {
+                assert_eq!(h.call::<Fr>(format!("sqr {}", encode(a))), b);
+            }
+        }
+        let mut minus_one = Fq::one();
+        minus_one.negate();
+        for a in &[Fq::zero(), Fq::one(), minus_one] {
+            let mut b = *a;
+            b.square();
+            for h in &[&*FQ32, &*FQ64] {
+                assert_eq!(h.call::<Fq>(format!("sqr {}", encode(a))), b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inv_zero() {
+        for h in &[&*FR32, &*FR64] {
+            assert_eq!(
+                h.call::<Fr>(format!("inv {}", encode(&Fr::zero()))),
+                Fr::zero()
+            );
+        }
+    }
+
+    /// Checks `FIELD_sqrt` and `FIELD_legendre` against `SqrtField`
+    fn check_sqrt<F: PrimeField + SqrtField>(harnesses: &[&Harness]) {
+        let mut rng = thread_rng();
+        let mut inputs = vec![
+            F::zero(),
+            F::one(),
+            F::root_of_unity(),
+            F::multiplicative_generator(),
+        ];
+        for _ in 0..SAMPLES / 2 {
+            let a = F::random(&mut rng);
+            let mut b = a;
+            b.square();
+            inputs.extend(&[a, b]);
+        }
+        let ops = |op: &str| {
+            inputs
+                .iter()
+                .map(|a| format!("{} {}", op, encode(a)))
+                .collect::<Vec<_>>()
+        };
+        for h in harnesses {
+            let roots = h.run(&ops("sqrt"));
+            let legendres = h.run(&ops("legendre"));
+            for ((a, root), legendre) in inputs.iter().zip(roots).zip(legendres) {
+                match a.sqrt() {
+                    Some(expected) => {
+                        // Either of the roots is fine
+                        let root = decode::<F>(&root);
+                        let mut minus_root = root;
+                        minus_root.negate();
+                        assert!(root == expected || minus_root == expected);
+                    }
+                    None => assert_eq!(root, "none"),
+                }
+                let expected = match a.legendre() {
+                    LegendreSymbol::Zero => "0",
+                    LegendreSymbol::QuadraticResidue => "1",
+                    LegendreSymbol::QuadraticNonResidue => "-1",
+                };
+                assert_eq!(legendre, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt() {
+        check_sqrt::<Fr>(&[&*FR32, &*FR64, &*FR64_CUDA]);
+        check_sqrt::<Fq>(&[&*FQ32, &*FQ64]);
+        check_sqrt::<Mnt4Fq>(&[&*MNT4_FQ32, &*MNT4_FQ64]);
+        check_sqrt::<Mnt4Fr>(&[&*MNT4_FR64]);
+    }
+
+    #[test]
+    fn test_batch_inv() {
+        const N: usize = 100;
+        const CHUNK_SIZE: usize = 8;
+        let mut rng = thread_rng();
+        let mut elements = (0..N).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        elements[3] = Fr::zero();
+        let expected = join(
+            elements
+                .iter()
+                .map(|e| encode(&e.inverse().unwrap_or(Fr::zero()))),
+            "",
+        );
+        let op = format!(
+            "batch_inv {} {} {}",
+            N,
+            CHUNK_SIZE,
+            join(elements.iter().map(encode), " ")
+        );
+        for h in &[&*FR32, &*FR64, &*FR64_CUDA] {
+            assert_eq!(h.run(&[op.clone()])[0], expected);
+        }
+    }
+}
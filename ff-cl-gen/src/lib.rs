@@ -1,15 +1,41 @@
+mod curve;
+mod edwards;
+mod extension;
+mod generator;
 mod nvidia;
+mod pow;
 mod utils;
+mod wgsl;
+
+// `fff_derive` refers to the crate by its package name, the tests derive their own fields
+#[cfg(test)]
+extern crate ff as fff;
+
+#[cfg(test)]
+mod host;
+#[cfg(test)]
+mod secp256k1;
+#[cfg(test)]
+mod wgsl_interpreter;
+
+pub use curve::{curve, curve_cuda};
+pub use edwards::{edwards, edwards_cuda};
+pub use extension::{cubic_extension, quadratic_extension, ExtensionBase, GeneratedExtension};
+pub use generator::{
+    digest, CommonHelpers, FieldGenerator, GeneratedField, LimbSize, Metadata, Target,
+    GENERATOR_VERSION,
+};
 
 use ff::PrimeField;
 use itertools::*;
 use num_bigint::BigUint;
 
 static COMMON_SRC: &str = include_str!("cl/common.cl");
+static CUDA_SRC: &str = include_str!("cl/cuda.cu");
 static FIELD_SRC: &str = include_str!("cl/field.cl");
 
 pub trait Limb: Sized + Clone + Copy {
-    type LimbType: Clone + std::fmt::Display;
+    type LimbType: Clone + std::fmt::Display + Into<u64>;
     fn zero() -> Self;
     fn new(val: Self::LimbType) -> Self;
     fn value(&self) -> Self::LimbType;
@@ -26,6 +52,8 @@ pub trait Limb: Sized + Clone + Copy {
     /// * `a` - Is the first limb of modulus
     fn calc_inv(a: Self) -> Self;
     fn calculate_r2<F: PrimeField>() -> Vec<Self>;
+    /// Divide a big integer into `count` limbs (Least significant limb first)
+    fn limbs_of_biguint(value: &BigUint, count: usize) -> Vec<Self>;
 }
 
 #[derive(Clone, Copy)]
@@ -64,6 +92,11 @@ impl Limb for Limb32 {
             .map(|l| Self::new(l))
             .collect()
     }
+    fn limbs_of_biguint(value: &BigUint, count: usize) -> Vec<Self> {
+        let mut digits = value.to_u32_digits();
+        digits.resize(count, 0);
+        digits.into_iter().map(|l| Self::new(l)).collect()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -103,58 +136,114 @@ impl Limb for Limb64 {
             .map(|(lo, hi)| Self::new(((hi as u64) << 32) + (lo as u64)))
             .collect()
     }
+    fn limbs_of_biguint(value: &BigUint, count: usize) -> Vec<Self> {
+        let mut digits = value.to_u32_digits();
+        digits.resize(count * 2, 0);
+        digits
+            .into_iter()
+            .tuples()
+            .map(|(lo, hi)| Self::new(((hi as u64) << 32) + (lo as u64)))
+            .collect()
+    }
 }
 
-fn define_field<L: Limb>(name: &str, limbs: Vec<L>) -> String {
+fn define_field<L: Limb>(name: &str, limbs: Vec<L>, target: Target) -> String {
+    let limbs = join(limbs.iter().map(|l| literal(l, target)), ", ");
     format!(
-        "#define {} ((FIELD){{ {{ {} }} }})",
+        "#define {} {}",
         name,
-        join(limbs.iter().map(|l| l.value()), ", ")
+        compound("FIELD", &format!("{{ {} }}", limbs), target)
     )
 }
 
+/// Literal of struct `ty`, initialized with `fields`
+fn compound(ty: &str, fields: &str, target: Target) -> String {
+    match target {
+        Target::OpenCL => format!("(({}){{ {} }})", ty, fields),
+        // Compound literals are not part of C++
+        Target::Cuda => format!("({}{{ {} }})", ty, fields),
+        Target::Wgsl => format!("{}({})", ty, fields),
+    }
+}
+
+/// Integer literal of a limb. In C++, decimal literals without a suffix can't be larger than
+/// `long long`.
+fn literal<L: Limb>(limb: &L, target: Target) -> String {
+    match target {
+        Target::OpenCL => limb.value().to_string(),
+        Target::Cuda | Target::Wgsl => format!("{}u", limb.value()),
+    }
+}
+
+/// Returns the field modulus as a big integer
+fn modulus<F: PrimeField>() -> BigUint {
+    BigUint::new(utils::limbs_of::<_, u32>(F::char()))
+}
+
 /// Calculates `R ^ 2 mod P` and returns the result as a vector of 32bit limbs
 fn calculate_r2<F: PrimeField>() -> Vec<u32> {
     // R ^ 2 mod P
     BigUint::new(utils::limbs_of::<_, u32>(F::one()))
         .modpow(
-            &BigUint::from_slice(&[2]),                          // ^ 2
-            &BigUint::new(utils::limbs_of::<_, u32>(F::char())), // mod P
+            &BigUint::from_slice(&[2]), // ^ 2
+            &modulus::<F>(),            // mod P
         )
         .to_u32_digits()
 }
 
-/// Generates OpenCL constants and type definitions of prime-field `F`
-fn params<F, L: Limb>() -> String
+/// Constants of prime-field `F` as `limbs` limbs (Montgomery form is based on that size),
+/// `FIELD_INV` and `FIELD_S` are a single limb
+fn constants<F, L: Limb>(limbs: usize) -> Vec<(&'static str, Vec<L>)>
 where
     F: PrimeField,
 {
-    let one = L::limbs_of(F::one()); // Get Montgomery form of F::one()
-    let p = L::limbs_of(F::char()); // Get regular form of field modulus
-    let r2 = L::calculate_r2::<F>();
-    let limbs = one.len(); // Number of limbs
+    let modulus = modulus::<F>();
+    let r = BigUint::from(1u32) << (limbs * L::bits());
+    let p = L::limbs_of_biguint(&modulus, limbs); // Regular form of field modulus
+    let one = L::limbs_of_biguint(&(&r % &modulus), limbs); // Montgomery form of one
+    let r2 = L::limbs_of_biguint(&(&r * &r % &modulus), limbs);
+    let p_minus_2 = L::limbs_of_biguint(&(&modulus - 2u32), limbs);
     let inv = L::calc_inv(p[0]);
+
+    // Tonelli-Shanks constants, where `p - 1 = 2^S * t`
+    let s = L::limbs_of_biguint(&BigUint::from(F::S), 1);
+    let root_of_unity = BigUint::new(utils::limbs_of::<_, u32>(F::root_of_unity().into_repr()));
+    let root_of_unity = L::limbs_of_biguint(&(root_of_unity * &r % &modulus), limbs);
+    let t = (&modulus - 1u32) >> F::S as usize;
+    let t_minus_1_over_2 = L::limbs_of_biguint(&((t - 1u32) >> 1), limbs);
+    let p_minus_1_over_2 = L::limbs_of_biguint(&((&modulus - 1u32) >> 1), limbs);
+
+    vec![
+        ("FIELD_ONE", one),
+        ("FIELD_P", p),
+        ("FIELD_R2", r2),
+        ("FIELD_ZERO", vec![L::zero(); limbs]),
+        ("FIELD_P_MINUS_2", p_minus_2),
+        ("FIELD_INV", vec![inv]),
+        ("FIELD_S", s),
+        ("FIELD_ROOT_OF_UNITY", root_of_unity),
+        ("FIELD_T_MINUS_1_OVER_2", t_minus_1_over_2),
+        ("FIELD_P_MINUS_1_OVER_2", p_minus_1_over_2),
+    ]
+}
+
+/// Generates OpenCL constants and type definitions of a prime-field, given its `constants`
+fn params<L: Limb>(constants: Vec<(&'static str, Vec<L>)>, target: Target) -> String {
     let limb_def = format!("#define FIELD_limb {}", L::opencl_type());
-    let limbs_def = format!("#define FIELD_LIMBS {}", limbs);
+    let limbs_def = format!("#define FIELD_LIMBS {}", constants[0].1.len());
     let limb_bits_def = format!("#define FIELD_LIMB_BITS {}", L::bits());
-    let p_def = define_field("FIELD_P", p);
-    let r2_def = define_field("FIELD_R2", r2);
-    let one_def = define_field("FIELD_ONE", one);
-    let zero_def = define_field("FIELD_ZERO", vec![L::zero(); limbs]);
-    let inv_def = format!("#define FIELD_INV {}", inv.value());
     let typedef = format!("typedef struct {{ FIELD_limb val[FIELD_LIMBS]; }} FIELD;");
     join(
-        &[
-            limb_def,
-            limbs_def,
-            limb_bits_def,
-            one_def,
-            p_def,
-            r2_def,
-            zero_def,
-            inv_def,
-            typedef,
-        ],
+        vec![limb_def, limbs_def, limb_bits_def]
+            .into_iter()
+            .chain(constants.into_iter().map(|(name, limbs)| {
+                if name == "FIELD_INV" || name == "FIELD_S" {
+                    format!("#define {} {}", name, literal(&limbs[0], target))
+                } else {
+                    define_field(name, limbs, target)
+                }
+            }))
+            .chain(Some(typedef)),
         "\n",
     )
 }
@@ -165,16 +254,25 @@ pub fn field<F, L: Limb>(name: &str) -> String
 where
     F: PrimeField,
 {
-    join(
-        &[
-            COMMON_SRC.to_string(),
-            params::<F, L>(),
-            nvidia::field_add_sub_nvidia::<F, L>(),
-            String::from(FIELD_SRC),
-        ],
-        "\n",
-    )
-    .replace("FIELD", name)
+    generator::generate::<F, L>(&FieldGenerator::new(name)).source
+}
+
+/// Returns CUDA source-code of a ff::PrimeField with name `name`, providing the same interface as
+/// `field` through `__device__` functions
+pub fn field_cuda<F, L: Limb>(name: &str) -> String
+where
+    F: PrimeField,
+{
+    generator::generate::<F, L>(&FieldGenerator::new(name).target(Target::Cuda)).source
+}
+
+/// Returns WGSL source-code of a ff::PrimeField with name `name` and 32-bit limbs, providing the
+/// interface of `field` that can be expressed in WGSL (Find details in README.md)
+pub fn field_wgsl<F>(name: &str) -> String
+where
+    F: PrimeField,
+{
+    generator::generate::<F, Limb32>(&FieldGenerator::new(name).target(Target::Wgsl)).source
 }
 
 #[cfg(test)]
@@ -304,6 +402,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_inv() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let a = Fr::random(&mut rng);
+            let b = a.inverse().unwrap();
+            assert_eq!(call_kernel!("test_inv_32", GpuFr(a)), b);
+            assert_eq!(call_kernel!("test_inv_64", GpuFr(a)), b);
+        }
+        assert_eq!(call_kernel!("test_inv_32", GpuFr(Fr::zero())), Fr::zero());
+        assert_eq!(call_kernel!("test_inv_64", GpuFr(Fr::zero())), Fr::zero());
+    }
+
+    #[test]
+    fn test_batch_inv() {
+        const N: usize = 100;
+        const CHUNK_SIZE: usize = 8;
+        let mut rng = thread_rng();
+        let mut elements = (0..N)
+            .map(|_| GpuFr(Fr::random(&mut rng)))
+            .collect::<Vec<_>>();
+        elements[3] = GpuFr(Fr::zero());
+        let expected = elements
+            .iter()
+            .map(|e| e.0.inverse().unwrap_or(Fr::zero()))
+            .collect::<Vec<_>>();
+
+        for name in &["Fr32_batch_inv", "Fr64_batch_inv"] {
+            let buffer = PROQUE
+                .buffer_builder::<GpuFr>()
+                .len(N)
+                .copy_host_slice(&elements)
+                .build()
+                .unwrap();
+            let tmp = PROQUE.buffer_builder::<GpuFr>().len(N).build().unwrap();
+            let kernel = PROQUE
+                .kernel_builder(*name)
+                .global_work_size((N + CHUNK_SIZE - 1) / CHUNK_SIZE)
+                .arg(&buffer)
+                .arg(&tmp)
+                .arg(N as u32)
+                .arg(CHUNK_SIZE as u32)
+                .build()
+                .unwrap();
+            unsafe {
+                kernel.enq().unwrap();
+            }
+            let mut result = vec![GpuFr::default(); N];
+            buffer.read(&mut result).enq().unwrap();
+            assert_eq!(
+                result.into_iter().map(|e| e.0).collect::<Vec<_>>(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_p_minus_2() {
+        // `FIELD_inv` computes a^(p - 2), make sure the exponent we generate is right
+        let mut rng = thread_rng();
+        let exponent = Limb64::limbs_of_biguint(&(modulus::<Fr>() - 2u32), 4)
+            .into_iter()
+            .map(|l| l.value())
+            .collect::<Vec<_>>();
+        for _ in 0..10 {
+            let a = Fr::random(&mut rng);
+            assert_eq!(a.pow(&exponent), a.inverse().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_cuda_source() {
+        let src = field_cuda::<Fr, Limb64>("Fr");
+        assert!(src.starts_with(CUDA_SRC));
+        assert!(src.contains("#define Fr_ONE (Fr{ {"));
+        assert!(!src.contains("(Fr){"));
+        assert!(src.contains("DEVICE Fr Fr_add_nvidia(Fr a, Fr b)"));
+        assert!(src.contains("__kernel void Fr_batch_inv("));
+    }
+
     #[test]
     fn test_unmont() {
         let mut rng = thread_rng();
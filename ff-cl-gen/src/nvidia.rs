@@ -2,19 +2,14 @@ use crate::Limb;
 use ff::PrimeField;
 use itertools::*;
 
-/// Generates PTX-Assembly implementation of FIELD_add_/FIELD_sub_
-pub fn field_add_sub_nvidia<F, L: Limb>() -> String
-where
-    F: PrimeField,
-{
+/// Generates PTX-Assembly implementation of FIELD_add_/FIELD_sub_ for `len` limbs
+pub fn field_add_sub_nvidia<L: Limb>(len: usize) -> String {
     let mut result = String::new();
     let (ptx_type, ptx_reg) = L::ptx_info();
 
     result.push_str("#ifdef NVIDIA\n");
     for op in &["sub", "add"] {
-        let len = L::limbs_of(F::one()).len();
-
-        let mut src = format!("FIELD FIELD_{}_nvidia(FIELD a, FIELD b) {{\n", op);
+        let mut src = format!("DEVICE FIELD FIELD_{}_nvidia(FIELD a, FIELD b) {{\n", op);
         if len > 1 {
             src.push_str("asm(");
             src.push_str(format!("\"{}.cc.{} %0, %0, %{};\\r\\n\"\n", op, ptx_type, len).as_str());
@@ -65,3 +60,300 @@ where
 
     result
 }
+
+/// A chain of PTX instructions, emitted as a single inline-assembly statement. Operands are C
+/// expressions, the ones written by any of the instructions are passed as read-write outputs,
+/// the rest as inputs (Numeric literals are embedded as immediates, and registers declared with
+/// `Asm::local` are scoped to the statement).
+struct Asm {
+    ptx_type: &'static str,
+    ptx_reg: &'static str,
+    locals: Vec<&'static str>,
+    instructions: Vec<(&'static str, Vec<String>)>,
+}
+
+impl Asm {
+    fn new<L: Limb>() -> Asm {
+        let (ptx_type, ptx_reg) = L::ptx_info();
+        Asm {
+            ptx_type,
+            ptx_reg,
+            locals: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Declares a PTX register, only visible inside this statement
+    fn local(&mut self, name: &'static str) {
+        self.locals.push(name);
+    }
+
+    /// Appends `op` (Without the type suffix), destination operand comes first
+    fn push(&mut self, op: &'static str, operands: Vec<String>) {
+        self.instructions.push((op, operands));
+    }
+
+    fn emit(&self) -> String {
+        let is_literal =
+            |o: &String| o.chars().all(|c| c.is_ascii_digit()) || self.locals.contains(&o.as_str());
+        let outputs = self
+            .instructions
+            .iter()
+            .map(|(_, operands)| operands[0].clone())
+            .filter(|o| !self.locals.contains(&o.as_str()))
+            .unique()
+            .collect::<Vec<_>>();
+        let inputs = self
+            .instructions
+            .iter()
+            .flat_map(|(_, operands)| operands[1..].iter().cloned())
+            .filter(|o| !is_literal(o) && !outputs.contains(o))
+            .unique()
+            .collect::<Vec<_>>();
+        let index = |o: &String| {
+            if is_literal(o) {
+                o.clone()
+            } else if let Some(i) = outputs.iter().position(|x| x == o) {
+                format!("%{}", i)
+            } else {
+                format!(
+                    "%{}",
+                    outputs.len() + inputs.iter().position(|x| x == o).unwrap()
+                )
+            }
+        };
+
+        let mut src = String::from("asm(");
+        if !self.locals.is_empty() {
+            src.push_str("\"{\\r\\n\"\n");
+            for l in &self.locals {
+                src.push_str(format!("\".reg .{} {};\\r\\n\"\n", self.ptx_type, l).as_str());
+            }
+        }
+        for (op, operands) in &self.instructions {
+            src.push_str(
+                format!(
+                    "\"{}.{} {};\\r\\n\"\n",
+                    op,
+                    self.ptx_type,
+                    join(operands.iter().map(index), ", ")
+                )
+                .as_str(),
+            );
+        }
+        if !self.locals.is_empty() {
+            src.push_str("\"}\\r\\n\"\n");
+        }
+        src.push_str(":");
+        src.push_str(&join(
+            outputs
+                .iter()
+                .map(|o| format!("\"+{}\"({})", self.ptx_reg, o)),
+            ", ",
+        ));
+        src.push_str("\n:");
+        src.push_str(&join(
+            inputs
+                .iter()
+                .map(|o| format!("\"{}\"({})", self.ptx_reg, o)),
+            ", ",
+        ));
+        src.push_str(");\n");
+        src
+    }
+}
+
+/// Generates PTX-Assembly implementation of FIELD_sqr_wide, the double-width square of `a` with
+/// `len` limbs
+pub fn field_sqr_nvidia<L: Limb>(len: usize) -> String {
+    let a = |i: usize| format!("a.val[{}]", i);
+    let t = |i: usize| format!("t[{}]", i);
+
+    let mut src = String::from("#ifdef NVIDIA\n");
+    src.push_str("DEVICE void FIELD_sqr_wide_nvidia(FIELD a, FIELD_limb *t) {\n");
+    src.push_str("for(uchar i = 0; i < 2 * FIELD_LIMBS; i++) t[i] = 0;\n");
+
+    if len > 1 {
+        // Cross products a_i * a_j (i < j). Lower and higher halves of each row are added in
+        // separate carry chains.
+        for i in 0..len - 1 {
+            let mut lo = Asm::new::<L>();
+            for j in i + 1..len {
+                let op = if j == i + 1 {
+                    "mad.lo.cc"
+                } else {
+                    "madc.lo.cc"
+                };
+                lo.push(op, vec![t(i + j), a(i), a(j), t(i + j)]);
+            }
+            lo.push("addc", vec![t(i + len), "0".into(), "0".into()]);
+            src.push_str(&lo.emit());
+
+            let mut hi = Asm::new::<L>();
+            for j in i + 1..len {
+                let op = match (j == i + 1, j == len - 1) {
+                    (true, true) => "mad.hi",
+                    (true, false) => "mad.hi.cc",
+                    (false, false) => "madc.hi.cc",
+                    (false, true) => "madc.hi",
+                };
+                hi.push(op, vec![t(i + j + 1), a(i), a(j), t(i + j + 1)]);
+            }
+            src.push_str(&hi.emit());
+        }
+
+        // Doubling
+        let mut dbl = Asm::new::<L>();
+        dbl.push("add.cc", vec![t(1), t(1), t(1)]);
+        for i in 2..2 * len - 1 {
+            dbl.push("addc.cc", vec![t(i), t(i), t(i)]);
+        }
+        dbl.push("addc", vec![t(2 * len - 1), "0".into(), "0".into()]);
+        src.push_str(&dbl.emit());
+    }
+
+    // Squares
+    let mut sqr = Asm::new::<L>();
+    for i in 0..len {
+        sqr.push(
+            if i == 0 { "mad.lo.cc" } else { "madc.lo.cc" },
+            vec![t(2 * i), a(i), a(i), t(2 * i)],
+        );
+        sqr.push(
+            if i == len - 1 {
+                "madc.hi"
+            } else {
+                "madc.hi.cc"
+            },
+            vec![t(2 * i + 1), a(i), a(i), t(2 * i + 1)],
+        );
+    }
+    src.push_str(&sqr.emit());
+
+    src.push_str("}\n");
+    src.push_str("#endif\n");
+    src
+}
+
+/// Appends `t[offset..offset + len + 2] += x * ys` to `asm`, where `len` is the length of `ys`.
+/// Lower and higher halves of the products are added in two separate carry chains.
+fn mad_row(asm: &mut Asm, t: &dyn Fn(usize) -> String, offset: usize, x: &str, ys: &[String]) {
+    let len = ys.len();
+    for (j, y) in ys.iter().enumerate() {
+        let op = if j == 0 { "mad.lo.cc" } else { "madc.lo.cc" };
+        asm.push(op, vec![t(offset + j), x.into(), y.clone(), t(offset + j)]);
+    }
+    asm.push(
+        "addc.cc",
+        vec![t(offset + len), t(offset + len), "0".into()],
+    );
+    asm.push(
+        "addc",
+        vec![t(offset + len + 1), t(offset + len + 1), "0".into()],
+    );
+    for (j, y) in ys.iter().enumerate() {
+        let op = if j == 0 { "mad.hi.cc" } else { "madc.hi.cc" };
+        asm.push(
+            op,
+            vec![t(offset + j + 1), x.into(), y.clone(), t(offset + j + 1)],
+        );
+    }
+    asm.push(
+        "addc",
+        vec![t(offset + len + 1), t(offset + len + 1), "0".into()],
+    );
+}
+
+/// Generates PTX-Assembly implementation of FIELD_mul_ for `len` limbs, a fully unrolled CIOS
+/// Montgomery multiplication (Without the final subtraction, result is in [0, 2p))
+pub fn field_mul_nvidia<F, L: Limb>(len: usize) -> String
+where
+    F: PrimeField,
+{
+    let full_width = crate::modulus::<F>().bits() == len * L::bits();
+    let modulus = L::limbs_of_biguint(&crate::modulus::<F>(), len);
+    let p = modulus
+        .iter()
+        .map(|l| l.value().to_string())
+        .collect::<Vec<_>>();
+    let inv = L::calc_inv(modulus[0]).value().to_string();
+    let a = (0..len)
+        .map(|i| format!("a.val[{}]", i))
+        .collect::<Vec<_>>();
+    let t = |i: usize| format!("t[{}]", i);
+
+    let mut src = String::from("#ifdef NVIDIA\n");
+    src.push_str("DEVICE FIELD FIELD_mul_nvidia(FIELD a, FIELD b) {\n");
+    // Instead of shifting `t` by one limb on each iteration, the i-th iteration works on
+    // `t[i..i + FIELD_LIMBS + 2]`
+    src.push_str("FIELD_limb t[2 * FIELD_LIMBS + 1] = {0};\n");
+    for i in 0..len {
+        let mut asm = Asm::new::<L>();
+        asm.local("m");
+        mad_row(&mut asm, &t, i, &format!("b.val[{}]", i), &a);
+        asm.push("mul.lo", vec!["m".into(), t(i), inv.clone()]);
+        mad_row(&mut asm, &t, i, "m", &p);
+        src.push_str(&asm.emit());
+    }
+    src.push_str("FIELD result;\n");
+    src.push_str("for(uchar i = 0; i < FIELD_LIMBS; i++) result.val[i] = t[FIELD_LIMBS + i];\n");
+    if full_width {
+        // Result might not fit in `len` limbs. `FIELD_select` isn't defined yet, the constant-time
+        // subtraction is masked in place.
+        src.push_str("#ifdef FIELD_CONSTANT_TIME\n");
+        src.push_str("const FIELD reduced = FIELD_sub_nvidia(result, FIELD_P);\n");
+        src.push_str("const FIELD_limb mask = -t[2 * FIELD_LIMBS];\n");
+        src.push_str(
+            "for(uchar i = 0; i < FIELD_LIMBS; i++) \
+             result.val[i] ^= (result.val[i] ^ reduced.val[i]) & mask;\n",
+        );
+        src.push_str("#else\n");
+        src.push_str("if(t[2 * FIELD_LIMBS]) result = FIELD_sub_nvidia(result, FIELD_P);\n");
+        src.push_str("#endif\n");
+    }
+    src.push_str("return result;\n}\n");
+    src.push_str("#endif\n");
+    src
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limb32, Limb64};
+    use paired::bls12_381::Fr;
+
+    // The PTX can't be checked without an NVIDIA GPU, so at least make sure it doesn't change
+    // unnoticed. The golden files only pin the output, the results of the PTX are checked by the
+    // OpenCL tests when they run on an NVIDIA device.
+    fn assert_golden(generated: String, golden: &str) {
+        assert!(
+            generated == golden,
+            "Generated PTX differs from the golden file:\n{}",
+            generated
+        );
+    }
+
+    #[test]
+    fn test_mul_golden() {
+        assert_golden(
+            field_mul_nvidia::<Fr, Limb32>(8),
+            include_str!("golden/mul_nvidia_fr_32.cl"),
+        );
+        assert_golden(
+            field_mul_nvidia::<Fr, Limb64>(4),
+            include_str!("golden/mul_nvidia_fr_64.cl"),
+        );
+    }
+
+    #[test]
+    fn test_sqr_golden() {
+        assert_golden(
+            field_sqr_nvidia::<Limb32>(8),
+            include_str!("golden/sqr_nvidia_fr_32.cl"),
+        );
+        assert_golden(
+            field_sqr_nvidia::<Limb64>(4),
+            include_str!("golden/sqr_nvidia_fr_64.cl"),
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use num_bigint::BigUint;
+
+// Maximum number of bits of a sliding window, the table holds the odd powers up to
+// `base^(2^WINDOW - 1)`
+const WINDOW: usize = 4;
+
+/// Generates `FIELD_pow_{name}(FIELD base)`, raising `base` to the constant `exponent`. The
+/// sliding-window decomposition of the exponent is done here, so the code is a straight sequence
+/// of squarings and multiplications.
+pub fn field_pow_constant(name: &str, exponent: &BigUint) -> String {
+    assert!(
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        "Invalid name of exponent: {}",
+        name
+    );
+    let bits = exponent.bits();
+    let bytes = exponent.to_bytes_le();
+    let bit = |i: usize| (bytes[i / 8] >> (i % 8)) & 1 == 1;
+
+    let mut src = format!("DEVICE FIELD FIELD_pow_{}(FIELD base) {{\n", name);
+    if bits == 0 {
+        src.push_str("return FIELD_ONE;\n}\n");
+        return src;
+    }
+
+    // Windows of the exponent, as (Squarings before the window, odd value of the window)
+    let mut windows = Vec::new();
+    let mut squarings = 0;
+    let mut i = bits as isize - 1;
+    while i >= 0 {
+        if !bit(i as usize) {
+            squarings += 1;
+            i -= 1;
+            continue;
+        }
+        let mut low = std::cmp::max(i - WINDOW as isize + 1, 0);
+        while !bit(low as usize) {
+            low += 1;
+        }
+        let value = (low..=i)
+            .rev()
+            .fold(0, |v, j| (v << 1) | bit(j as usize) as usize);
+        windows.push((squarings + (i - low + 1) as usize, value));
+        squarings = 0;
+        i = low - 1;
+    }
+    let table_size = windows.iter().map(|(_, v)| v / 2 + 1).max().unwrap();
+
+    // table[i] = base^(2 * i + 1)
+    src.push_str(&format!("FIELD table[{}];\n", table_size));
+    src.push_str("table[0] = base;\n");
+    if table_size > 1 {
+        src.push_str("const FIELD base2 = FIELD_sqr(base);\n");
+        src.push_str(&format!(
+            "for(uint i = 1; i < {}; i++) table[i] = FIELD_mul(table[i - 1], base2);\n",
+            table_size
+        ));
+    }
+
+    // The squarings of the first window are skipped, as they only apply to one
+    src.push_str(&format!("FIELD res = table[{}];\n", windows[0].1 / 2));
+    for &(squarings, value) in &windows[1..] {
+        src.push_str(&format!(
+            "for(uint i = 0; i < {}; i++) res = FIELD_sqr(res);\n",
+            squarings
+        ));
+        src.push_str(&format!("res = FIELD_mul(res, table[{}]);\n", value / 2));
+    }
+    if squarings > 0 {
+        src.push_str(&format!(
+            "for(uint i = 0; i < {}; i++) res = FIELD_sqr(res);\n",
+            squarings
+        ));
+    }
+    src.push_str("return res;\n}\n");
+    src
+}
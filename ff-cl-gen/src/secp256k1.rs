@@ -0,0 +1,254 @@
+//! Base field of secp256k1, whose modulus uses all the bits of its limbs.
+//!
+//! `fff_derive` needs a spare bit in the top limb, so the field is written by hand for the tests,
+//! with the layout a derived field would have: five 64-bit limbs, in Montgomery form with
+//! `R = 2^320`. The arithmetic is done on `BigUint`s, it only has to be correct.
+
+use crate::{Limb, Limb64};
+use ff::{Field, LegendreSymbol, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, SqrtField};
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use rand_core::RngCore;
+use std::cmp::Ordering;
+use std::fmt;
+
+const LIMBS: usize = 5;
+
+lazy_static! {
+    static ref P: BigUint = BigUint::parse_bytes(
+        b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        16
+    )
+    .unwrap();
+    static ref R: BigUint = (BigUint::from(1u32) << (64 * LIMBS)) % &*P;
+    static ref R_INV: BigUint = R.modpow(&(&*P - 2u32), &P);
+}
+
+fn to_biguint(limbs: &[u64; LIMBS]) -> BigUint {
+    BigUint::new(
+        limbs
+            .iter()
+            .flat_map(|l| vec![*l as u32, (*l >> 32) as u32])
+            .collect(),
+    )
+}
+
+fn to_limbs(value: &BigUint) -> [u64; LIMBS] {
+    let mut limbs = [0u64; LIMBS];
+    for (l, v) in limbs.iter_mut().zip(Limb64::limbs_of_biguint(value, LIMBS)) {
+        *l = v.value();
+    }
+    limbs
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Secp256k1FqRepr(pub [u64; LIMBS]);
+
+impl Secp256k1FqRepr {
+    fn update(&mut self, f: impl FnOnce(BigUint) -> BigUint) {
+        self.0 = to_limbs(&f(to_biguint(&self.0)));
+    }
+}
+
+impl Ord for Secp256k1FqRepr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for Secp256k1FqRepr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Secp256k1FqRepr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:x}", to_biguint(&self.0))
+    }
+}
+
+impl AsRef<[u64]> for Secp256k1FqRepr {
+    fn as_ref(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl AsMut<[u64]> for Secp256k1FqRepr {
+    fn as_mut(&mut self) -> &mut [u64] {
+        &mut self.0
+    }
+}
+
+impl From<u64> for Secp256k1FqRepr {
+    fn from(value: u64) -> Self {
+        let mut repr = Self::default();
+        repr.0[0] = value;
+        repr
+    }
+}
+
+impl From<Secp256k1Fq> for Secp256k1FqRepr {
+    fn from(value: Secp256k1Fq) -> Self {
+        value.into_repr()
+    }
+}
+
+impl PrimeFieldRepr for Secp256k1FqRepr {
+    fn sub_noborrow(&mut self, other: &Self) {
+        let other = to_biguint(&other.0);
+        self.update(|v| v - other);
+    }
+    fn add_nocarry(&mut self, other: &Self) {
+        let other = to_biguint(&other.0);
+        self.update(|v| v + other);
+    }
+    fn num_bits(&self) -> u32 {
+        to_biguint(&self.0).bits() as u32
+    }
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(|l| *l == 0)
+    }
+    fn is_odd(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+    fn is_even(&self) -> bool {
+        !self.is_odd()
+    }
+    fn div2(&mut self) {
+        self.shr(1);
+    }
+    fn shr(&mut self, amt: u32) {
+        self.update(|v| v >> amt as usize);
+    }
+    fn mul2(&mut self) {
+        self.shl(1);
+    }
+    fn shl(&mut self, amt: u32) {
+        self.update(|v| v << amt as usize);
+    }
+}
+
+/// Element in Montgomery form, like the ones of the derived fields
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Secp256k1Fq(Secp256k1FqRepr);
+
+impl Secp256k1Fq {
+    fn from_biguint(value: BigUint) -> Self {
+        Secp256k1Fq(Secp256k1FqRepr(to_limbs(&(value * &*R % &*P))))
+    }
+
+    /// Regular form of the element
+    fn regular(&self) -> BigUint {
+        to_biguint(&(self.0).0) * &*R_INV % &*P
+    }
+
+    fn update(&mut self, f: impl FnOnce(BigUint) -> BigUint) {
+        *self = Self::from_biguint(f(self.regular()) % &*P);
+    }
+}
+
+impl fmt::Display for Secp256k1Fq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secp256k1Fq({})", self.into_repr())
+    }
+}
+
+impl Field for Secp256k1Fq {
+    fn random<R: RngCore>(rng: &mut R) -> Self {
+        let mut limbs = [0u64; LIMBS];
+        for l in limbs.iter_mut() {
+            *l = rng.next_u64();
+        }
+        Self::from_biguint(to_biguint(&limbs) % &*P)
+    }
+    fn zero() -> Self {
+        Self::from_biguint(BigUint::from(0u32))
+    }
+    fn one() -> Self {
+        Self::from_biguint(BigUint::from(1u32))
+    }
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+    fn square(&mut self) {
+        self.update(|v| &v * &v);
+    }
+    fn double(&mut self) {
+        self.update(|v| v << 1);
+    }
+    fn negate(&mut self) {
+        self.update(|v| &*P - v);
+    }
+    fn add_assign(&mut self, other: &Self) {
+        self.update(|v| v + other.regular());
+    }
+    fn sub_assign(&mut self, other: &Self) {
+        self.update(|v| v + &*P - other.regular());
+    }
+    fn mul_assign(&mut self, other: &Self) {
+        self.update(|v| v * other.regular());
+    }
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self.pow(to_limbs(&(&*P - 2u32))))
+        }
+    }
+    fn frobenius_map(&mut self, _: usize) {}
+}
+
+impl SqrtField for Secp256k1Fq {
+    fn legendre(&self) -> LegendreSymbol {
+        let symbol = self.regular().modpow(&((&*P - 1u32) >> 1), &P);
+        if symbol == BigUint::from(0u32) {
+            LegendreSymbol::Zero
+        } else if symbol == BigUint::from(1u32) {
+            LegendreSymbol::QuadraticResidue
+        } else {
+            LegendreSymbol::QuadraticNonResidue
+        }
+    }
+    // `p = 3 mod 4`
+    fn sqrt(&self) -> Option<Self> {
+        let root = Self::from_biguint(self.regular().modpow(&((&*P + 1u32) >> 2), &P));
+        let mut square = root;
+        square.square();
+        if square == *self {
+            Some(root)
+        } else {
+            None
+        }
+    }
+}
+
+impl PrimeField for Secp256k1Fq {
+    type Repr = Secp256k1FqRepr;
+
+    fn from_repr(repr: Secp256k1FqRepr) -> Result<Self, PrimeFieldDecodingError> {
+        let value = to_biguint(&repr.0);
+        if value < *P {
+            Ok(Self::from_biguint(value))
+        } else {
+            Err(PrimeFieldDecodingError::NotInField(repr.to_string()))
+        }
+    }
+    fn into_repr(&self) -> Secp256k1FqRepr {
+        Secp256k1FqRepr(to_limbs(&self.regular()))
+    }
+    fn char() -> Secp256k1FqRepr {
+        Secp256k1FqRepr(to_limbs(&P))
+    }
+    const NUM_BITS: u32 = 256;
+    const CAPACITY: u32 = 255;
+    fn multiplicative_generator() -> Self {
+        Self::from_biguint(BigUint::from(3u32))
+    }
+    const S: u32 = 1;
+    fn root_of_unity() -> Self {
+        let mut minus_one = Self::one();
+        minus_one.negate();
+        minus_one
+    }
+}
@@ -0,0 +1,316 @@
+use crate::*;
+
+static FIELD_WGSL_SRC: &str = include_str!("cl/field.wgsl");
+
+/// Generates WGSL code of a prime-field, given its `constants` (See `params`). WGSL has no
+/// preprocessor, so the options are constants (E.g. `FIELD_NO_SPARE_BIT: bool`) and the helpers
+/// of `cl/common.cl` are part of the field, prefixed with its name.
+pub(crate) fn field_source<L: Limb>(
+    constants: &[(&'static str, Vec<L>)],
+    no_spare_bit: bool,
+) -> String {
+    assert_eq!(
+        L::bits(),
+        32,
+        "WGSL has no 64-bit integers, only 32-bit limbs are supported"
+    );
+    let limbs = constants[0].1.len();
+    let mut defs = vec![
+        format!("const FIELD_LIMBS: u32 = {}u;", limbs),
+        // Array sizes can't be expressions
+        format!("const FIELD_WIDE_LIMBS: u32 = {}u;", limbs * 2),
+        "const FIELD_LIMB_BITS: u32 = 32u;".to_string(),
+        format!("const FIELD_BITS: u32 = {}u;", limbs * 32),
+        format!("const FIELD_NO_SPARE_BIT: bool = {};", no_spare_bit),
+        "struct FIELD { val: array<u32, FIELD_LIMBS> }".to_string(),
+    ];
+    for (name, limbs) in constants {
+        let values = join(limbs.iter().map(|l| literal(l, Target::Wgsl)), ", ");
+        defs.push(if *name == "FIELD_INV" || *name == "FIELD_S" {
+            format!("const {}: u32 = {};", name, values)
+        } else {
+            let array = compound("array<u32, FIELD_LIMBS>", &values, Target::Wgsl);
+            format!(
+                "const {} = {};",
+                name,
+                compound("FIELD", &array, Target::Wgsl)
+            )
+        });
+    }
+    join(
+        defs.iter().map(String::as_str).chain(Some(FIELD_WGSL_SRC)),
+        "\n",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::Secp256k1Fq;
+    use crate::wgsl_interpreter::{Module, Value};
+    use ff::{Field, LegendreSymbol, PrimeField, SqrtField};
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+    use paired::bls12_381::{Fq, Fr, FrRepr};
+    use rand::{thread_rng, Rng};
+
+    /// Parses and validates `src` with naga
+    fn validate(src: &str) {
+        let module = naga::front::wgsl::parse_str(src)
+            .unwrap_or_else(|e| panic!("Invalid WGSL:\n{}", e.emit_to_string(src)));
+        Validator::new(ValidationFlags::all(), Capabilities::empty())
+            .validate(&module)
+            .unwrap_or_else(|e| panic!("Invalid WGSL: {:?}", e));
+    }
+
+    /// Generated code of `F`, validated by naga and loaded in the reference evaluator
+    struct Wgsl<F: PrimeField> {
+        name: String,
+        module: Module,
+        minimal_limbs: bool,
+        _phantom: std::marker::PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Wgsl<F> {
+        fn new(name: &str, minimal_limbs: bool) -> Self {
+            let src = FieldGenerator::<F>::new(name)
+                .target(Target::Wgsl)
+                .limb_size(LimbSize::Limb32)
+                .minimal_limbs(minimal_limbs)
+                .generate()
+                .source;
+            validate(&src);
+            Wgsl {
+                name: name.to_string(),
+                module: Module::parse(&src).unwrap(),
+                minimal_limbs,
+                _phantom: std::marker::PhantomData,
+            }
+        }
+
+        // With minimal limbs, elements are passed in regular form
+        fn encode(&self, a: F) -> Value {
+            let limbs = if self.minimal_limbs {
+                Limb32::limbs_of(a.into_repr())
+            } else {
+                Limb32::limbs_of(a)
+            };
+            let len = self
+                .module
+                .constant(&format!("{}_LIMBS", self.name))
+                .as_u32() as usize;
+            let val = limbs[..len].iter().map(|l| Value::U32(l.value())).collect();
+            Value::Struct(self.name.clone(), vec![Value::Array(val)])
+        }
+
+        fn decode(&self, a: &Value) -> F {
+            let mut repr = F::Repr::default();
+            for (i, limb) in a.fields()[0].elements().iter().enumerate() {
+                let limb = limb.as_u32() as u64;
+                repr.as_mut()[i / 2] |= limb << (32 * (i % 2));
+            }
+            if self.minimal_limbs {
+                F::from_repr(repr).unwrap()
+            } else {
+                // Montgomery form
+                let mut res = F::zero();
+                unsafe { *(&mut res as *mut F as *mut F::Repr) = repr };
+                res
+            }
+        }
+
+        fn call(&self, op: &str, args: Vec<Value>) -> Value {
+            self.module
+                .call(&format!("{}_{}", self.name, op), args)
+                .unwrap()
+        }
+
+        // Montgomery form of `a`, as an argument
+        fn to_mont(&self, a: F) -> Value {
+            let a = self.encode(a);
+            if self.minimal_limbs {
+                self.call("mont", vec![a])
+            } else {
+                a
+            }
+        }
+
+        fn from_mont(&self, a: Value) -> F {
+            self.decode(&if self.minimal_limbs {
+                self.call("unmont", vec![a])
+            } else {
+                a
+            })
+        }
+
+        // Calls the field operation `op` in Montgomery form
+        fn field_op(&self, op: &str, args: &[F]) -> F {
+            let args = args.iter().map(|a| self.to_mont(*a)).collect();
+            self.from_mont(self.call(op, args))
+        }
+    }
+
+    const SAMPLES: usize = 4;
+
+    /// Checks the arithmetic of the generated code against `F`. The interpreter is slow, so the
+    /// operations based on full-size exponentiations are checked on fewer elements.
+    fn check_field<F: PrimeField + SqrtField>(wgsl: &Wgsl<F>) {
+        let mut rng = thread_rng();
+        let mut minus_one = F::one();
+        minus_one.negate();
+        let elements = [
+            F::random(&mut rng),
+            F::random(&mut rng),
+            F::zero(),
+            F::one(),
+            minus_one,
+        ];
+
+        for a in elements.iter() {
+            for b in elements.iter() {
+                let mut expected = *a;
+                expected.add_assign(b);
+                assert_eq!(wgsl.field_op("add", &[*a, *b]), expected);
+                let mut expected = *a;
+                expected.sub_assign(b);
+                assert_eq!(wgsl.field_op("sub", &[*a, *b]), expected);
+                let mut expected = *a;
+                expected.mul_assign(b);
+                assert_eq!(wgsl.field_op("mul", &[*a, *b]), expected);
+            }
+
+            let mut expected = *a;
+            expected.square();
+            assert_eq!(wgsl.field_op("sqr", &[*a]), expected);
+            let mut expected = *a;
+            expected.double();
+            assert_eq!(wgsl.field_op("double", &[*a]), expected);
+            let mut expected = *a;
+            expected.negate();
+            assert_eq!(wgsl.field_op("neg", &[*a]), expected);
+            let mut expected = *a;
+            expected.mul_assign(&F::from_repr(2.into()).unwrap().inverse().unwrap());
+            assert_eq!(wgsl.field_op("half", &[*a]), expected);
+
+            let exponent = rng.gen::<u32>();
+            let args = vec![wgsl.to_mont(*a), Value::U32(exponent)];
+            let res = wgsl.from_mont(wgsl.call("pow", args));
+            assert_eq!(res, a.pow(&[exponent as u64]));
+        }
+
+        let a = elements[0];
+        assert_eq!(wgsl.field_op("inv", &[a]), a.inverse().unwrap());
+        for a in [a, F::zero()].iter() {
+            let legendre = match a.legendre() {
+                LegendreSymbol::Zero => 0,
+                LegendreSymbol::QuadraticResidue => 1,
+                LegendreSymbol::QuadraticNonResidue => -1,
+            };
+            let res = wgsl.call("legendre", vec![wgsl.to_mont(*a)]);
+            assert_eq!(res.as_i32(), legendre);
+        }
+    }
+
+    #[test]
+    fn test_fr() {
+        check_field(&Wgsl::<Fr>::new("Fr", false));
+    }
+
+    #[test]
+    fn test_fq() {
+        check_field(&Wgsl::<Fq>::new("Fq", false));
+    }
+
+    #[test]
+    fn test_no_spare_bit() {
+        check_field(&Wgsl::<Secp256k1Fq>::new("Secp", true));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let fr = Wgsl::<Fr>::new("Fr", false);
+        let mut rng = thread_rng();
+        for _ in 0..SAMPLES {
+            let a = Fr::random(&mut rng);
+            let (found, root) = fr
+                .module
+                .call_with_pointer("Fr_sqrt", vec![fr.encode(a)])
+                .unwrap();
+            match a.sqrt() {
+                Some(_) => {
+                    assert!(found.as_bool());
+                    let mut square = fr.decode(&root);
+                    square.square();
+                    assert_eq!(square, a);
+                }
+                None => assert!(!found.as_bool()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let fr = Wgsl::<Fr>::new("Fr", false);
+        let mut rng = thread_rng();
+        for _ in 0..SAMPLES {
+            let a = Fr::random(&mut rng);
+            let regular = fr.call("unmont", vec![fr.encode(a)]);
+            let limbs = Limb32::limbs_of(a.into_repr());
+            assert!(regular.fields()[0]
+                .elements()
+                .iter()
+                .zip(limbs.iter())
+                .all(|(x, l)| x.as_u32() == l.value()));
+            assert_eq!(fr.decode(&fr.call("mont", vec![regular.clone()])), a);
+
+            let v = rng.gen::<u32>();
+            let expected = Fr::from_repr(FrRepr::from(v as u64)).unwrap();
+            assert_eq!(
+                fr.decode(&fr.call("from_u32", vec![Value::U32(v)])),
+                expected
+            );
+
+            // Bits of the regular form, from the most significant one
+            let skip = rng.gen_range(0u32, 250);
+            let value = BigUint::new(limbs.iter().map(|l| l.value()).collect());
+            let expected = (value >> (256 - skip - 6) as usize) % 64u32;
+            let res = fr.call("get_bits", vec![regular, Value::U32(skip), Value::U32(6)]);
+            assert_eq!(BigUint::from(res.as_u32()), expected);
+        }
+
+        let zero = fr.encode(Fr::zero());
+        assert!(fr.call("is_zero", vec![zero.clone()]).as_bool());
+        assert!(fr.call("eq", vec![zero.clone(), zero.clone()]).as_bool());
+        assert!(fr
+            .call("gte", vec![fr.encode(Fr::one()), zero.clone()])
+            .as_bool());
+        assert!(!fr.call("gte", vec![zero, fr.encode(Fr::one())]).as_bool());
+    }
+
+    #[test]
+    fn test_source() {
+        let src = field_wgsl::<Fr>("Fr");
+        assert!(src.contains("const Fr_LIMBS: u32 = 8u;"));
+        assert!(src.contains("const Fr_NO_SPARE_BIT: bool = false;"));
+        assert!(src.contains("const Fr_P = Fr(array<u32, Fr_LIMBS>(1u, 4294967295u, "));
+        assert!(!src.contains("#"));
+        validate(&src);
+        let secp = FieldGenerator::<Secp256k1Fq>::new("Secp")
+            .target(Target::Wgsl)
+            .limb_size(LimbSize::Limb32)
+            .minimal_limbs(true)
+            .generate();
+        assert!(secp
+            .source
+            .contains("const Secp_NO_SPARE_BIT: bool = true;"));
+        assert_eq!(secp.common, "");
+        validate(&secp.source);
+    }
+
+    #[test]
+    #[should_panic(expected = "only 32-bit limbs are supported")]
+    fn test_limb64() {
+        FieldGenerator::<Fr>::new("Fr")
+            .target(Target::Wgsl)
+            .generate();
+    }
+}
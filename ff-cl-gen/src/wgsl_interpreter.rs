@@ -0,0 +1,1395 @@
+//! Small WGSL evaluator, used by the differential tests to run the generated code offline,
+//! without a GPU. It covers the subset of the language the generator emits (Scalars, fixed-size
+//! arrays, structs, constants, aliases, function-local pointers and the usual statements) and
+//! assumes the code is valid, the validation is done by naga.
+
+use std::collections::HashMap;
+use std::mem;
+
+/// Operators, longest first
+#[rustfmt::skip]
+static PUNCTUATION: &[&str] = &[
+    "<<=", ">>=", "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "++", "--", "+=", "-=",
+    "*=", "/=", "%=", "&=", "|=", "^=", "(", ")", "[", "]", "{", "}", "<", ">", "=", "+", "-", "*",
+    "/", "%", "&", "|", "^", "!", "~", ",", ";", ":", ".",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64, Option<char>),
+    Punct(&'static str),
+    End,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    LogicalAnd,
+    LogicalOr,
+}
+
+/// Binary operators, by increasing precedence. WGSL requires parentheses to mix most of them,
+/// the ones that are valid together keep their usual precedence.
+#[rustfmt::skip]
+static BINARY: &[&[(&str, Op)]] = &[
+    &[("||", Op::LogicalOr)],
+    &[("&&", Op::LogicalAnd)],
+    &[("|", Op::Or)],
+    &[("^", Op::Xor)],
+    &[("&", Op::And)],
+    &[("<=", Op::Le), (">=", Op::Ge), ("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt)],
+    &[("<<", Op::Shl), (">>", Op::Shr)],
+    &[("+", Op::Add), ("-", Op::Sub)],
+    &[("*", Op::Mul), ("/", Op::Div), ("%", Op::Rem)],
+];
+
+static COMPOUND_ASSIGNMENTS: &[(&str, Op)] = &[
+    ("+=", Op::Add),
+    ("-=", Op::Sub),
+    ("*=", Op::Mul),
+    ("/=", Op::Div),
+    ("%=", Op::Rem),
+    ("&=", Op::And),
+    ("|=", Op::Or),
+    ("^=", Op::Xor),
+    ("<<=", Op::Shl),
+    (">>=", Op::Shr),
+];
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars = src.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if chars[i..].starts_with(&['/', '/']) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if chars[i..].starts_with(&['/', '*']) {
+            while !chars[i..].starts_with(&['*', '/']) {
+                if i == chars.len() {
+                    return Err(format!("line {}: unterminated comment", line));
+                }
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else if c.is_ascii_digit() {
+            let hex = chars[i..].starts_with(&['0', 'x']);
+            let (radix, start) = if hex { (16, i + 2) } else { (10, i) };
+            i = start;
+            while i < chars.len() && chars[i].is_digit(radix) {
+                i += 1;
+            }
+            let digits = chars[start..i].iter().collect::<String>();
+            let value = i64::from_str_radix(&digits, radix)
+                .map_err(|e| format!("line {}: invalid literal: {}", line, e))?;
+            let suffix = match chars.get(i) {
+                Some('u') | Some('i') => {
+                    i += 1;
+                    Some(chars[i - 1])
+                }
+                _ => None,
+            };
+            tokens.push((Token::Int(value, suffix), line));
+        } else {
+            for p in PUNCTUATION {
+                let p_chars = p.chars().collect::<Vec<_>>();
+                if chars[i..].starts_with(&p_chars) {
+                    tokens.push((Token::Punct(p), line));
+                    i += p_chars.len();
+                    continue 'outer;
+                }
+            }
+            return Err(format!("line {}: unexpected character {:?}", line, c));
+        }
+    }
+    tokens.push((Token::End, line));
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Type {
+    Bool,
+    U32,
+    I32,
+    Array(Box<Type>, usize),
+    Struct(String),
+    Ptr(Box<Type>),
+}
+
+/// Path of a component within a variable, as indices of array elements and struct members
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Path {
+    len: usize,
+    indices: [usize; 4],
+}
+
+impl Path {
+    fn push(&mut self, index: usize) -> Result<(), String> {
+        if self.len == self.indices.len() {
+            return Err("too many nested components".to_string());
+        }
+        self.indices[self.len] = index;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &usize> {
+        self.indices[..self.len].iter()
+    }
+}
+
+/// Runtime value. Abstract integers are the literals without a suffix.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    AbstractInt(i64),
+    Array(Vec<Value>),
+    Struct(String, Vec<Value>),
+    /// Pointer to a variable (Its slot in the stack), or to a component of it
+    Ptr(usize, Path),
+}
+
+impl Value {
+    pub fn as_u32(&self) -> u32 {
+        match self {
+            Value::U32(v) => *v,
+            _ => panic!("Expected an u32, found {:?}", self),
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            Value::I32(v) => *v,
+            _ => panic!("Expected an i32, found {:?}", self),
+        }
+    }
+
+    pub fn as_bool(&self) -> bool {
+        match self {
+            Value::Bool(v) => *v,
+            _ => panic!("Expected a bool, found {:?}", self),
+        }
+    }
+
+    pub fn fields(&self) -> &[Value] {
+        match self {
+            Value::Struct(_, fields) => fields,
+            _ => panic!("Expected a struct, found {:?}", self),
+        }
+    }
+
+    pub fn elements(&self) -> &[Value] {
+        match self {
+            Value::Array(elements) => elements,
+            _ => panic!("Expected an array, found {:?}", self),
+        }
+    }
+
+    fn index(&self) -> Result<usize, String> {
+        match self {
+            Value::U32(v) => Ok(*v as usize),
+            Value::I32(v) if *v >= 0 => Ok(*v as usize),
+            Value::AbstractInt(v) if *v >= 0 => Ok(*v as usize),
+            _ => Err(format!("invalid index {:?}", self)),
+        }
+    }
+
+    fn truth(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(v) => Ok(*v),
+            _ => Err(format!("expected a bool condition, found {:?}", self)),
+        }
+    }
+
+    fn component(&self, i: usize) -> Result<&Value, String> {
+        match self {
+            Value::Array(v) | Value::Struct(_, v) => v
+                .get(i)
+                .ok_or_else(|| format!("index {} out of bounds ({})", i, v.len())),
+            _ => Err(format!("{:?} has no components", self)),
+        }
+    }
+
+    fn component_mut(&mut self, i: usize) -> Result<&mut Value, String> {
+        match self {
+            Value::Array(v) | Value::Struct(_, v) => {
+                let len = v.len();
+                v.get_mut(i)
+                    .ok_or_else(|| format!("index {} out of bounds ({})", i, len))
+            }
+            _ => Err(format!("{:?} has no components", self)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Literal(Value),
+    /// Local variable, `let` binding or parameter, with its slot in the frame
+    Local(usize),
+    Constant(usize),
+    Unary(&'static str, Box<Expr>),
+    Deref(Box<Expr>),
+    AddressOf(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Member(Box<Expr>, String),
+    /// Call of a function, given its index
+    Call(usize, Vec<Expr>),
+    Select(Vec<Expr>),
+    Construct(Type, Vec<Expr>),
+}
+
+#[derive(Clone, Debug)]
+enum Stmt {
+    Let(usize, Option<Type>, Expr),
+    Var(usize, Option<Type>, Option<Expr>),
+    /// Assignment, with the operator of compound assignments
+    Assign(Expr, Option<Op>, Expr),
+    Call(Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    For(
+        Option<Box<Stmt>>,
+        Option<Expr>,
+        Option<Box<Stmt>>,
+        Vec<Stmt>,
+    ),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+    Block(Vec<Stmt>),
+}
+
+struct Function {
+    name: String,
+    params: Vec<Type>,
+    /// Number of slots of parameters and local variables
+    slots: usize,
+    ret: Option<Type>,
+    body: Vec<Stmt>,
+}
+
+/// Parsed WGSL module
+pub struct Module {
+    constants: Vec<Value>,
+    constant_names: HashMap<String, usize>,
+    aliases: HashMap<String, Type>,
+    structs: Vec<(String, Vec<(String, Type)>)>,
+    functions: Vec<Function>,
+    function_names: HashMap<String, usize>,
+}
+
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Option<Value>),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    module: Module,
+    /// Slots of the identifiers in scope of the current function
+    scopes: Vec<HashMap<String, usize>>,
+    slots: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T, String> {
+        Err(format!(
+            "line {}: {} (found {:?})",
+            self.tokens[self.pos].1,
+            msg,
+            self.peek()
+        ))
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is(&self, p: &str) -> bool {
+        match self.peek() {
+            Token::Punct(q) => *q == p,
+            _ => false,
+        }
+    }
+
+    fn is_keyword(&self, k: &str) -> bool {
+        match self.peek() {
+            Token::Ident(i) => i == k,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, p: &str) -> bool {
+        if self.is(p) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, p: &str) -> Result<(), String> {
+        if self.eat(p) {
+            Ok(())
+        } else {
+            self.error(&format!("expected `{}`", p))
+        }
+    }
+
+    /// Closes a template list, splitting `>>`
+    fn expect_template_end(&mut self) -> Result<(), String> {
+        if self.is(">>") {
+            self.tokens[self.pos].0 = Token::Punct(">");
+            Ok(())
+        } else {
+            self.expect(">")
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek().clone() {
+            Token::Ident(name) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.error("expected an identifier"),
+        }
+    }
+
+    fn parse_module(&mut self) -> Result<(), String> {
+        while *self.peek() != Token::End {
+            let keyword = self.ident()?;
+            match keyword.as_str() {
+                "const" => {
+                    let name = self.ident()?;
+                    let ty = if self.eat(":") {
+                        Some(self.parse_type()?)
+                    } else {
+                        None
+                    };
+                    self.expect("=")?;
+                    let expr = self.parse_expression()?;
+                    self.expect(";")?;
+                    let value = self.module.eval_const(&expr)?;
+                    let value = match ty {
+                        Some(ty) => self.module.conform(value, &ty)?,
+                        None => value,
+                    };
+                    let index = self.module.constants.len();
+                    self.module.constants.push(value);
+                    self.module.constant_names.insert(name, index);
+                }
+                "alias" => {
+                    let name = self.ident()?;
+                    self.expect("=")?;
+                    let ty = self.parse_type()?;
+                    self.expect(";")?;
+                    self.module.aliases.insert(name, ty);
+                }
+                "struct" => {
+                    let name = self.ident()?;
+                    self.expect("{")?;
+                    let mut fields = Vec::new();
+                    while !self.eat("}") {
+                        let field = self.ident()?;
+                        self.expect(":")?;
+                        fields.push((field, self.parse_type()?));
+                        if !self.eat(",") {
+                            self.expect("}")?;
+                            break;
+                        }
+                    }
+                    self.eat(";");
+                    self.module.structs.push((name, fields));
+                }
+                "fn" => {
+                    let name = self.ident()?;
+                    self.expect("(")?;
+                    let mut params = Vec::new();
+                    self.scopes = vec![HashMap::new()];
+                    self.slots = 0;
+                    while !self.eat(")") {
+                        let param = self.ident()?;
+                        self.expect(":")?;
+                        params.push(self.parse_type()?);
+                        self.declare(param);
+                        if !self.eat(",") {
+                            self.expect(")")?;
+                            break;
+                        }
+                    }
+                    let ret = if self.eat("->") {
+                        Some(self.parse_type()?)
+                    } else {
+                        None
+                    };
+                    let body = self.parse_block()?;
+                    let function = Function {
+                        name,
+                        params,
+                        slots: self.slots,
+                        ret,
+                        body,
+                    };
+                    self.module.functions.push(function);
+                }
+                _ => {
+                    self.pos -= 1;
+                    return self.error("expected a declaration");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_type(&mut self) -> Result<Type, String> {
+        let name = self.ident()?;
+        Ok(match name.as_str() {
+            "bool" => Type::Bool,
+            "u32" => Type::U32,
+            "i32" => Type::I32,
+            "array" => {
+                self.expect("<")?;
+                let element = self.parse_type()?;
+                self.expect(",")?;
+                // Additive expression, the size can't contain `>` without parentheses
+                let size = self.parse_binary(BINARY.len() - 2)?;
+                let size = self.module.eval_const(&size)?.index()?;
+                self.expect_template_end()?;
+                Type::Array(Box::new(element), size)
+            }
+            "ptr" => {
+                self.expect("<")?;
+                if self.ident()? != "function" {
+                    self.pos -= 1;
+                    return self.error("only pointers to the function address space are supported");
+                }
+                self.expect(",")?;
+                let pointee = self.parse_type()?;
+                self.expect_template_end()?;
+                Type::Ptr(Box::new(pointee))
+            }
+            _ => {
+                if let Some(ty) = self.module.aliases.get(&name) {
+                    ty.clone()
+                } else if self.module.struct_fields(&name).is_some() {
+                    Type::Struct(name)
+                } else {
+                    self.pos -= 1;
+                    return self.error("unknown type");
+                }
+            }
+        })
+    }
+
+    /// Declares a local identifier, returns its slot
+    fn declare(&mut self, name: String) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+        self.scopes.last_mut().unwrap().insert(name, slot);
+        slot
+    }
+
+    fn parse_block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect("{")?;
+        self.scopes.push(HashMap::new());
+        let mut stmts = Vec::new();
+        while !self.eat("}") {
+            stmts.push(self.parse_statement()?);
+        }
+        self.scopes.pop();
+        Ok(stmts)
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt, String> {
+        if self.is("{") {
+            return Ok(Stmt::Block(self.parse_block()?));
+        }
+        if self.eat(";") {
+            return Ok(Stmt::Block(Vec::new()));
+        }
+        if self.is_keyword("if") {
+            self.next();
+            let cond = self.parse_expression()?;
+            let then = self.parse_block()?;
+            let otherwise = if self.is_keyword("else") {
+                self.next();
+                if self.is_keyword("if") {
+                    vec![self.parse_statement()?]
+                } else {
+                    self.parse_block()?
+                }
+            } else {
+                Vec::new()
+            };
+            return Ok(Stmt::If(cond, then, otherwise));
+        }
+        if self.is_keyword("for") {
+            self.next();
+            self.expect("(")?;
+            self.scopes.push(HashMap::new());
+            let init = if self.is(";") {
+                None
+            } else {
+                Some(Box::new(self.parse_simple_statement()?))
+            };
+            self.expect(";")?;
+            let cond = if self.is(";") {
+                None
+            } else {
+                Some(self.parse_expression()?)
+            };
+            self.expect(";")?;
+            let update = if self.is(")") {
+                None
+            } else {
+                Some(Box::new(self.parse_simple_statement()?))
+            };
+            self.expect(")")?;
+            let body = self.parse_block()?;
+            self.scopes.pop();
+            return Ok(Stmt::For(init, cond, update, body));
+        }
+        if self.is_keyword("while") {
+            self.next();
+            let cond = self.parse_expression()?;
+            return Ok(Stmt::While(cond, self.parse_block()?));
+        }
+        let stmt = if self.is_keyword("return") {
+            self.next();
+            Stmt::Return(if self.is(";") {
+                None
+            } else {
+                Some(self.parse_expression()?)
+            })
+        } else if self.is_keyword("break") {
+            self.next();
+            Stmt::Break
+        } else if self.is_keyword("continue") {
+            self.next();
+            Stmt::Continue
+        } else {
+            self.parse_simple_statement()?
+        };
+        self.expect(";")?;
+        Ok(stmt)
+    }
+
+    /// Declarations, assignments, increments and calls, without the semicolon
+    fn parse_simple_statement(&mut self) -> Result<Stmt, String> {
+        if self.is_keyword("let") || self.is_keyword("var") {
+            let var = self.ident()? == "var";
+            let name = self.ident()?;
+            let ty = if self.eat(":") {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            return if var {
+                let init = if self.eat("=") {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                Ok(Stmt::Var(self.declare(name), ty, init))
+            } else {
+                self.expect("=")?;
+                let init = self.parse_expression()?;
+                Ok(Stmt::Let(self.declare(name), ty, init))
+            };
+        }
+        let lhs = self.parse_unary()?;
+        for &(token, op) in COMPOUND_ASSIGNMENTS {
+            if self.eat(token) {
+                return Ok(Stmt::Assign(lhs, Some(op), self.parse_expression()?));
+            }
+        }
+        if self.eat("=") {
+            return Ok(Stmt::Assign(lhs, None, self.parse_expression()?));
+        }
+        let one = Expr::Literal(Value::AbstractInt(1));
+        if self.eat("++") {
+            return Ok(Stmt::Assign(lhs, Some(Op::Add), one));
+        }
+        if self.eat("--") {
+            return Ok(Stmt::Assign(lhs, Some(Op::Sub), one));
+        }
+        match lhs {
+            Expr::Call(_, _) => Ok(Stmt::Call(lhs)),
+            _ => self.error("expected a statement"),
+        }
+    }
+
+    /// Operator of `ops` at the current position
+    fn eat_op(&mut self, ops: &[(&str, Op)]) -> Option<Op> {
+        for &(token, op) in ops {
+            if self.eat(token) {
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn parse_expression(&mut self) -> Result<Expr, String> {
+        self.parse_binary(0)
+    }
+
+    /// Expression made of the binary operators of `BINARY[level..]`
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY.len() {
+            return self.parse_unary();
+        }
+        let mut expr = self.parse_binary(level + 1)?;
+        while let Some(op) = self.eat_op(BINARY[level]) {
+            let rhs = self.parse_binary(level + 1)?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        for &op in &["-", "!", "~"] {
+            if self.eat(op) {
+                return Ok(Expr::Unary(op, Box::new(self.parse_unary()?)));
+            }
+        }
+        if self.eat("*") {
+            return Ok(Expr::Deref(Box::new(self.parse_unary()?)));
+        }
+        if self.eat("&") {
+            return Ok(Expr::AddressOf(Box::new(self.parse_unary()?)));
+        }
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat("[") {
+                let index = self.parse_expression()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else if self.eat(".") {
+                expr = Expr::Member(Box::new(expr), self.ident()?);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<Expr>, String> {
+        self.expect("(")?;
+        let mut args = Vec::new();
+        while !self.eat(")") {
+            args.push(self.parse_expression()?);
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Token::Int(value, suffix) => Ok(Expr::Literal(match suffix {
+                Some('u') if value <= u32::max_value() as i64 => Value::U32(value as u32),
+                Some('i') if value <= i32::max_value() as i64 => Value::I32(value as i32),
+                None => Value::AbstractInt(value),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("literal out of range");
+                }
+            })),
+            Token::Punct("(") => {
+                let expr = self.parse_expression()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) => {
+                let is_type = ["bool", "u32", "i32", "array"].contains(&name.as_str())
+                    || self.module.aliases.contains_key(&name)
+                    || self.module.struct_fields(&name).is_some();
+                if name == "true" || name == "false" {
+                    Ok(Expr::Literal(Value::Bool(name == "true")))
+                } else if is_type && (self.is("(") || name == "array") {
+                    self.pos -= 1;
+                    let ty = self.parse_type()?;
+                    Ok(Expr::Construct(ty, self.parse_arguments()?))
+                } else if self.is("(") {
+                    let args = self.parse_arguments()?;
+                    if name == "select" {
+                        if args.len() != 3 {
+                            return self.error("select has three arguments");
+                        }
+                        return Ok(Expr::Select(args));
+                    }
+                    match self.module.function_names.get(&name) {
+                        Some(index) => Ok(Expr::Call(*index, args)),
+                        None => self.error(&format!("unknown function `{}`", name)),
+                    }
+                } else if let Some(slot) =
+                    self.scopes.iter().rev().filter_map(|s| s.get(&name)).next()
+                {
+                    Ok(Expr::Local(*slot))
+                } else if let Some(index) = self.module.constant_names.get(&name) {
+                    Ok(Expr::Constant(*index))
+                } else {
+                    self.pos -= 1;
+                    self.error("unknown identifier")
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                self.error("expected an expression")
+            }
+        }
+    }
+}
+
+/// Place of a value in memory: a variable and the path of the component within it
+struct Place {
+    root: Root,
+    path: Path,
+}
+
+enum Root {
+    /// Slot of the stack
+    Stack(usize),
+    Constant(usize),
+}
+
+impl Module {
+    pub fn parse(src: &str) -> Result<Module, String> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+            module: Module {
+                constants: Vec::new(),
+                constant_names: HashMap::new(),
+                aliases: HashMap::new(),
+                structs: Vec::new(),
+                functions: Vec::new(),
+                function_names: HashMap::new(),
+            },
+            scopes: Vec::new(),
+            slots: 0,
+        };
+        // Functions can be called before their declaration
+        for window in parser.tokens.windows(2) {
+            if let (Token::Ident(keyword), Token::Ident(name)) = (&window[0].0, &window[1].0) {
+                if keyword == "fn" {
+                    let index = parser.module.function_names.len();
+                    parser.module.function_names.insert(name.clone(), index);
+                }
+            }
+        }
+        parser.parse_module()?;
+        Ok(parser.module)
+    }
+
+    fn struct_fields(&self, name: &str) -> Option<&[(String, Type)]> {
+        self.structs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, fields)| &fields[..])
+    }
+
+    fn function(&self, name: &str) -> Result<usize, String> {
+        self.function_names
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("unknown function `{}`", name))
+    }
+
+    /// Value of a module-scope constant
+    pub fn constant(&self, name: &str) -> Value {
+        self.constants[self.constant_names[name]].clone()
+    }
+
+    /// Calls the function `name`, pointer parameters are not supported
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, String> {
+        self.call_on(self.function(name)?, args, &mut Vec::new())
+    }
+
+    /// Calls the function `name`, whose last parameter is a pointer. Returns the result and the
+    /// value pointed by the last parameter after the call.
+    pub fn call_with_pointer(
+        &self,
+        name: &str,
+        mut args: Vec<Value>,
+    ) -> Result<(Value, Value), String> {
+        let index = self.function(name)?;
+        let pointee = match self.functions[index].params.last() {
+            Some(Type::Ptr(pointee)) => self.zero(pointee),
+            _ => return Err(format!("the last parameter of `{}` is not a pointer", name)),
+        };
+        let mut stack = vec![pointee];
+        args.push(Value::Ptr(0, Path::default()));
+        let res = self.call_on(index, args, &mut stack)?;
+        Ok((res, stack.pop().unwrap()))
+    }
+
+    fn call_on(
+        &self,
+        index: usize,
+        args: Vec<Value>,
+        stack: &mut Vec<Value>,
+    ) -> Result<Value, String> {
+        self.call_function(index, args, stack)?
+            .ok_or_else(|| format!("`{}` doesn't return a value", self.functions[index].name))
+    }
+
+    fn zero(&self, ty: &Type) -> Value {
+        match ty {
+            Type::Bool => Value::Bool(false),
+            Type::U32 => Value::U32(0),
+            Type::I32 => Value::I32(0),
+            Type::Array(element, size) => Value::Array(vec![self.zero(element); *size]),
+            Type::Struct(name) => Value::Struct(
+                name.clone(),
+                self.struct_fields(name)
+                    .unwrap()
+                    .iter()
+                    .map(|(_, t)| self.zero(t))
+                    .collect(),
+            ),
+            Type::Ptr(_) => panic!("Pointers have no zero value"),
+        }
+    }
+
+    /// Converts `value` to `ty`, abstract integers are converted to concrete ones
+    fn conform(&self, value: Value, ty: &Type) -> Result<Value, String> {
+        Ok(match (value, ty) {
+            (Value::AbstractInt(v), Type::U32) if v >= 0 && v <= u32::max_value() as i64 => {
+                Value::U32(v as u32)
+            }
+            (Value::AbstractInt(v), Type::I32)
+                if v >= i32::min_value() as i64 && v <= i32::max_value() as i64 =>
+            {
+                Value::I32(v as i32)
+            }
+            (v @ Value::Bool(_), Type::Bool)
+            | (v @ Value::U32(_), Type::U32)
+            | (v @ Value::I32(_), Type::I32)
+            | (v @ Value::Ptr(_, _), Type::Ptr(_)) => v,
+            (Value::Array(elements), Type::Array(element, size)) if elements.len() == *size => {
+                Value::Array(
+                    elements
+                        .into_iter()
+                        .map(|e| self.conform(e, element))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            (Value::Struct(name, fields), Type::Struct(ty_name)) if name == *ty_name => {
+                Value::Struct(name, fields)
+            }
+            (value, ty) => return Err(format!("{:?} is not a valid {:?}", value, ty)),
+        })
+    }
+
+    /// Converts `value` to the type of `old`
+    fn conform_like(&self, value: Value, old: &Value) -> Result<Value, String> {
+        match (&value, old) {
+            (Value::AbstractInt(_), Value::U32(_)) => self.conform(value, &Type::U32),
+            (Value::AbstractInt(_), Value::I32(_)) => self.conform(value, &Type::I32),
+            (Value::Array(a), Value::Array(b)) if a.len() == b.len() => Ok(Value::Array(
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| self.conform_like(a.clone(), b))
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ if mem::discriminant(&value) == mem::discriminant(old) => match (&value, old) {
+                (Value::Struct(a, _), Value::Struct(b, _)) if a != b => {
+                    Err(format!("can't assign a `{}` to a `{}`", a, b))
+                }
+                _ => Ok(value),
+            },
+            _ => Err(format!("can't assign {:?} to {:?}", value, old)),
+        }
+    }
+
+    /// Concrete value of a `let` or `var` declared without a type
+    fn concretize(&self, value: Value) -> Result<Value, String> {
+        match value {
+            Value::AbstractInt(_) => self.conform(value, &Type::I32),
+            value => Ok(value),
+        }
+    }
+
+    fn eval_const(&self, expr: &Expr) -> Result<Value, String> {
+        self.eval(expr, &mut Vec::new(), 0)
+    }
+
+    fn binary(&self, op: Op, lhs: Value, rhs: Value) -> Result<Value, String> {
+        use Op::*;
+        use Value::*;
+        // Shift amounts of concrete integers are taken modulo their width, as on the GPU
+        if op == Shl || op == Shr {
+            let amount = match rhs {
+                U32(v) => v,
+                AbstractInt(v) if v >= 0 && v < 64 => v as u32,
+                _ => return Err(format!("invalid shift amount {:?}", rhs)),
+            };
+            return Ok(match (op, lhs) {
+                (Shl, U32(v)) => U32(v << (amount & 31)),
+                (Shr, U32(v)) => U32(v >> (amount & 31)),
+                (Shl, I32(v)) => I32(v << (amount & 31)),
+                (Shr, I32(v)) => I32(v >> (amount & 31)),
+                (Shl, AbstractInt(v)) => AbstractInt(v << amount),
+                (Shr, AbstractInt(v)) => AbstractInt(v >> amount),
+                (_, lhs) => return Err(format!("invalid operand of {:?}: {:?}", op, lhs)),
+            });
+        }
+        let (lhs, rhs) = match (lhs, rhs) {
+            (AbstractInt(a), b @ U32(_)) => (self.conform(AbstractInt(a), &Type::U32)?, b),
+            (AbstractInt(a), b @ I32(_)) => (self.conform(AbstractInt(a), &Type::I32)?, b),
+            (a @ U32(_), AbstractInt(b)) => (a, self.conform(AbstractInt(b), &Type::U32)?),
+            (a @ I32(_), AbstractInt(b)) => (a, self.conform(AbstractInt(b), &Type::I32)?),
+            pair => pair,
+        };
+        Ok(match (op, lhs, rhs) {
+            (Add, U32(a), U32(b)) => U32(a.wrapping_add(b)),
+            (Sub, U32(a), U32(b)) => U32(a.wrapping_sub(b)),
+            (Mul, U32(a), U32(b)) => U32(a.wrapping_mul(b)),
+            (Div, U32(a), U32(b)) if b != 0 => U32(a / b),
+            (Rem, U32(a), U32(b)) if b != 0 => U32(a % b),
+            (And, U32(a), U32(b)) => U32(a & b),
+            (Or, U32(a), U32(b)) => U32(a | b),
+            (Xor, U32(a), U32(b)) => U32(a ^ b),
+            (Lt, U32(a), U32(b)) => Bool(a < b),
+            (Gt, U32(a), U32(b)) => Bool(a > b),
+            (Le, U32(a), U32(b)) => Bool(a <= b),
+            (Ge, U32(a), U32(b)) => Bool(a >= b),
+            (Add, I32(a), I32(b)) => I32(a.wrapping_add(b)),
+            (Sub, I32(a), I32(b)) => I32(a.wrapping_sub(b)),
+            (Mul, I32(a), I32(b)) => I32(a.wrapping_mul(b)),
+            (And, I32(a), I32(b)) => I32(a & b),
+            (Or, I32(a), I32(b)) => I32(a | b),
+            (Xor, I32(a), I32(b)) => I32(a ^ b),
+            (Lt, I32(a), I32(b)) => Bool(a < b),
+            (Gt, I32(a), I32(b)) => Bool(a > b),
+            (Le, I32(a), I32(b)) => Bool(a <= b),
+            (Ge, I32(a), I32(b)) => Bool(a >= b),
+            (Add, AbstractInt(a), AbstractInt(b)) => AbstractInt(a + b),
+            (Sub, AbstractInt(a), AbstractInt(b)) => AbstractInt(a - b),
+            (Mul, AbstractInt(a), AbstractInt(b)) => AbstractInt(a * b),
+            (Div, AbstractInt(a), AbstractInt(b)) if b != 0 => AbstractInt(a / b),
+            (Rem, AbstractInt(a), AbstractInt(b)) if b != 0 => AbstractInt(a % b),
+            (Lt, AbstractInt(a), AbstractInt(b)) => Bool(a < b),
+            (Gt, AbstractInt(a), AbstractInt(b)) => Bool(a > b),
+            (Le, AbstractInt(a), AbstractInt(b)) => Bool(a <= b),
+            (Ge, AbstractInt(a), AbstractInt(b)) => Bool(a >= b),
+            (LogicalAnd, Bool(a), Bool(b)) | (And, Bool(a), Bool(b)) => Bool(a && b),
+            (LogicalOr, Bool(a), Bool(b)) | (Or, Bool(a), Bool(b)) => Bool(a || b),
+            (Eq, a, b) if mem::discriminant(&a) == mem::discriminant(&b) => Bool(a == b),
+            (Ne, a, b) if mem::discriminant(&a) == mem::discriminant(&b) => Bool(a != b),
+            (op, a, b) => return Err(format!("invalid operands of {:?}: {:?}, {:?}", op, a, b)),
+        })
+    }
+
+    fn construct(&self, ty: &Type, args: Vec<Value>) -> Result<Value, String> {
+        if args.is_empty() {
+            return Ok(self.zero(ty));
+        }
+        match ty {
+            Type::Array(_, _) => self.conform(Value::Array(args), ty),
+            Type::Struct(name) => {
+                let fields = &self.struct_fields(name).unwrap();
+                if fields.len() != args.len() {
+                    return Err(format!("wrong number of members of `{}`", name));
+                }
+                Ok(Value::Struct(
+                    name.clone(),
+                    args.into_iter()
+                        .zip(fields.iter())
+                        .map(|(a, field)| self.conform(a, &field.1))
+                        .collect::<Result<_, _>>()?,
+                ))
+            }
+            // Conversions
+            _ if args.len() == 1 => Ok(match (ty, &args[0]) {
+                (Type::U32, Value::U32(v)) => Value::U32(*v),
+                (Type::U32, Value::I32(v)) => Value::U32(*v as u32),
+                (Type::U32, Value::Bool(v)) => Value::U32(*v as u32),
+                (Type::I32, Value::I32(v)) => Value::I32(*v),
+                (Type::I32, Value::U32(v)) => Value::I32(*v as i32),
+                (Type::I32, Value::Bool(v)) => Value::I32(*v as i32),
+                (Type::Bool, Value::Bool(v)) => Value::Bool(*v),
+                (Type::Bool, Value::U32(v)) => Value::Bool(*v != 0),
+                (Type::Bool, Value::I32(v)) => Value::Bool(*v != 0),
+                (ty, v @ Value::AbstractInt(_)) => self.conform(v.clone(), ty)?,
+                (ty, v) => return Err(format!("can't convert {:?} to {:?}", v, ty)),
+            }),
+            _ => Err(format!("invalid construction of {:?}", ty)),
+        }
+    }
+
+    /// Place of a reference expression, None for other expressions
+    fn place(
+        &self,
+        expr: &Expr,
+        stack: &mut Vec<Value>,
+        base: usize,
+    ) -> Result<Option<Place>, String> {
+        Ok(Some(match expr {
+            Expr::Local(slot) => Place {
+                root: Root::Stack(base + slot),
+                path: Path::default(),
+            },
+            Expr::Constant(index) => Place {
+                root: Root::Constant(*index),
+                path: Path::default(),
+            },
+            Expr::Deref(ptr) => match self.eval(ptr, stack, base)? {
+                Value::Ptr(slot, path) => Place {
+                    root: Root::Stack(slot),
+                    path,
+                },
+                v => return Err(format!("can't dereference {:?}", v)),
+            },
+            Expr::Index(array, index) => {
+                let index = self.eval(index, stack, base)?.index()?;
+                match self.place(array, stack, base)? {
+                    Some(mut place) => {
+                        place.path.push(index)?;
+                        place
+                    }
+                    None => return Ok(None),
+                }
+            }
+            Expr::Member(object, member) => match self.place(object, stack, base)? {
+                Some(mut place) => {
+                    let index = self.member_index(self.get(&place, stack)?, member)?;
+                    place.path.push(index)?;
+                    place
+                }
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    fn member_index(&self, value: &Value, member: &str) -> Result<usize, String> {
+        match value {
+            Value::Struct(name, _) => self
+                .struct_fields(name)
+                .unwrap()
+                .iter()
+                .position(|(field, _)| field == member)
+                .ok_or_else(|| format!("`{}` has no member `{}`", name, member)),
+            _ => Err(format!("{:?} has no member `{}`", value, member)),
+        }
+    }
+
+    fn get<'a>(&'a self, place: &Place, stack: &'a [Value]) -> Result<&'a Value, String> {
+        let mut value = match place.root {
+            Root::Stack(slot) => &stack[slot],
+            Root::Constant(index) => &self.constants[index],
+        };
+        for i in place.path.iter() {
+            value = value.component(*i)?;
+        }
+        Ok(value)
+    }
+
+    fn write(&self, place: &Place, new: Value, stack: &mut Vec<Value>) -> Result<(), String> {
+        let mut value = match place.root {
+            Root::Stack(slot) => &mut stack[slot],
+            Root::Constant(_) => return Err("can't assign to a constant".to_string()),
+        };
+        for i in place.path.iter() {
+            value = value.component_mut(*i)?;
+        }
+        *value = self.conform_like(new, value)?;
+        Ok(())
+    }
+
+    fn eval_all(
+        &self,
+        exprs: &[Expr],
+        stack: &mut Vec<Value>,
+        base: usize,
+    ) -> Result<Vec<Value>, String> {
+        exprs.iter().map(|e| self.eval(e, stack, base)).collect()
+    }
+
+    fn eval(&self, expr: &Expr, stack: &mut Vec<Value>, base: usize) -> Result<Value, String> {
+        Ok(match expr {
+            Expr::Literal(value) => value.clone(),
+            Expr::Local(_)
+            | Expr::Constant(_)
+            | Expr::Deref(_)
+            | Expr::Index(_, _)
+            | Expr::Member(_, _) => match self.place(expr, stack, base)? {
+                Some(place) => self.get(&place, stack)?.clone(),
+                None => match expr {
+                    Expr::Index(array, index) => {
+                        let index = self.eval(index, stack, base)?.index()?;
+                        self.eval(array, stack, base)?.component(index)?.clone()
+                    }
+                    Expr::Member(object, member) => {
+                        let object = self.eval(object, stack, base)?;
+                        object
+                            .component(self.member_index(&object, member)?)?
+                            .clone()
+                    }
+                    _ => unreachable!(),
+                },
+            },
+            Expr::AddressOf(expr) => match self.place(expr, stack, base)? {
+                Some(Place {
+                    root: Root::Stack(slot),
+                    path,
+                }) => Value::Ptr(slot, path),
+                _ => return Err(format!("can't take the address of {:?}", expr)),
+            },
+            Expr::Unary(op, expr) => match (*op, self.eval(expr, stack, base)?) {
+                ("-", Value::I32(v)) => Value::I32(v.wrapping_neg()),
+                ("-", Value::AbstractInt(v)) => Value::AbstractInt(-v),
+                ("!", Value::Bool(v)) => Value::Bool(!v),
+                ("~", Value::U32(v)) => Value::U32(!v),
+                ("~", Value::I32(v)) => Value::I32(!v),
+                (op, v) => return Err(format!("invalid operand of `{}`: {:?}", op, v)),
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, stack, base)?;
+                // Short-circuiting
+                if let (Op::LogicalAnd, Value::Bool(false)) | (Op::LogicalOr, Value::Bool(true)) =
+                    (op, &lhs)
+                {
+                    return Ok(lhs);
+                }
+                let rhs = self.eval(rhs, stack, base)?;
+                self.binary(*op, lhs, rhs)?
+            }
+            Expr::Construct(ty, args) => {
+                let args = self.eval_all(args, stack, base)?;
+                self.construct(ty, args)?
+            }
+            Expr::Call(index, args) => {
+                let args = self.eval_all(args, stack, base)?;
+                self.call_on(*index, args, stack)?
+            }
+            Expr::Select(args) => match &self.eval_all(args, stack, base)?[..] {
+                [f, t, Value::Bool(c)] if mem::discriminant(f) == mem::discriminant(t) => {
+                    if *c {
+                        t.clone()
+                    } else {
+                        f.clone()
+                    }
+                }
+                args => return Err(format!("invalid arguments of select: {:?}", args)),
+            },
+        })
+    }
+
+    /// Calls a function with a new frame on top of `stack`
+    fn call_function(
+        &self,
+        index: usize,
+        args: Vec<Value>,
+        stack: &mut Vec<Value>,
+    ) -> Result<Option<Value>, String> {
+        let function = &self.functions[index];
+        let name = &function.name;
+        if function.params.len() != args.len() {
+            return Err(format!("wrong number of arguments of `{}`", name));
+        }
+        let base = stack.len();
+        for (arg, ty) in args.into_iter().zip(function.params.iter()) {
+            let arg = self.conform(arg, ty)?;
+            stack.push(arg);
+        }
+        stack.resize(base + function.slots, Value::Bool(false));
+        let flow = self.exec_block(&function.body, stack, base);
+        stack.truncate(base);
+        let res = match flow? {
+            Flow::Return(value) => value,
+            Flow::Normal => None,
+            _ => {
+                return Err(format!(
+                    "`break` or `continue` outside of a loop in `{}`",
+                    name
+                ))
+            }
+        };
+        match (res, function.ret.as_ref()) {
+            (Some(value), Some(ty)) => Ok(Some(self.conform(value, ty)?)),
+            (None, None) => Ok(None),
+            _ => Err(format!("invalid return of `{}`", name)),
+        }
+    }
+
+    fn exec_block(
+        &self,
+        stmts: &[Stmt],
+        stack: &mut Vec<Value>,
+        base: usize,
+    ) -> Result<Flow, String> {
+        for stmt in stmts {
+            match self.exec(stmt, stack, base)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn exec(&self, stmt: &Stmt, stack: &mut Vec<Value>, base: usize) -> Result<Flow, String> {
+        match stmt {
+            Stmt::Let(slot, ty, init) | Stmt::Var(slot, ty, Some(init)) => {
+                let value = self.eval(init, stack, base)?;
+                let value = match ty {
+                    Some(ty) => self.conform(value, ty)?,
+                    None => self.concretize(value)?,
+                };
+                stack[base + slot] = value;
+            }
+            Stmt::Var(slot, ty, None) => {
+                stack[base + slot] = self.zero(ty.as_ref().unwrap());
+            }
+            Stmt::Assign(lhs, op, rhs) => {
+                let rhs = self.eval(rhs, stack, base)?;
+                let place = match self.place(lhs, stack, base)? {
+                    Some(place) => place,
+                    None => return Err(format!("can't assign to {:?}", lhs)),
+                };
+                let value = match op {
+                    Some(op) => self.binary(*op, self.get(&place, stack)?.clone(), rhs)?,
+                    None => rhs,
+                };
+                self.write(&place, value, stack)?;
+            }
+            Stmt::Call(expr) => match expr {
+                Expr::Call(index, args) => {
+                    let args = self.eval_all(args, stack, base)?;
+                    self.call_function(*index, args, stack)?;
+                }
+                _ => unreachable!(),
+            },
+            Stmt::If(cond, then, otherwise) => {
+                return if self.eval(cond, stack, base)?.truth()? {
+                    self.exec_block(then, stack, base)
+                } else {
+                    self.exec_block(otherwise, stack, base)
+                };
+            }
+            Stmt::For(init, cond, update, body) => {
+                if let Some(init) = init {
+                    self.exec(init, stack, base)?;
+                }
+                loop {
+                    if let Some(cond) = cond {
+                        if !self.eval(cond, stack, base)?.truth()? {
+                            break;
+                        }
+                    }
+                    match self.exec_block(body, stack, base)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                    if let Some(update) = update {
+                        self.exec(update, stack, base)?;
+                    }
+                }
+            }
+            Stmt::While(cond, body) => {
+                while self.eval(cond, stack, base)?.truth()? {
+                    match self.exec_block(body, stack, base)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+            }
+            Stmt::Return(value) => {
+                let value = match value {
+                    Some(value) => Some(self.eval(value, stack, base)?),
+                    None => None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Break => return Ok(Flow::Break),
+            Stmt::Continue => return Ok(Flow::Continue),
+            Stmt::Block(stmts) => return self.exec_block(stmts, stack, base),
+        }
+        Ok(Flow::Normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let src = "
+            const N: u32 = 4u;
+            struct S { v: array<u32, N * 2u> }
+            fn f(a: u32, b: ptr<function, u32>) -> u32 {
+              *b = a >> 1u;
+              return (a & 0xffu) + 1u;
+            }";
+        let module = Module::parse(src).unwrap();
+        let (res, b) = module
+            .call_with_pointer("f", vec![Value::U32(0x1234)])
+            .unwrap();
+        assert_eq!((res, b), (Value::U32(0x35), Value::U32(0x91a)));
+
+        let module = Module::parse(
+            "fn f(a: u32, b: u32, c: u32) -> bool { return a + b * c == 7u && (a & b) < c; }",
+        )
+        .unwrap();
+        let args = vec![Value::U32(1), Value::U32(2), Value::U32(3)];
+        assert_eq!(module.call("f", args), Ok(Value::Bool(true)));
+
+        // Shift amounts are masked
+        let module = Module::parse("fn f(a: u32) -> u32 { return 1u << a; }").unwrap();
+        assert_eq!(module.call("f", vec![Value::U32(33)]), Ok(Value::U32(2)));
+    }
+}